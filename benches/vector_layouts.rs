@@ -0,0 +1,56 @@
+//! Timing comparison of the interleaved (`Grid<Vector<2>, 2>`) and
+//! per-component (`VectorField<2>`) vector layouts on a 256×256 field.
+//!
+//! Run with `cargo bench --bench vector_layouts`.
+
+use std::{hint::black_box, time::Instant};
+
+use nsh::simulation::{
+    grid::{CoordInt, Grid},
+    vector::{Float, Vector},
+    vector_field::VectorField,
+};
+
+const SIZE: i32 = 256;
+const RUNS: u32 = 20;
+
+fn rotating_field(size: i32, delta: Float) -> Grid<Vector<2>, 2> {
+    let mut grid = Grid::new(CoordInt([size, size]), delta);
+    for (coord, value) in &mut grid {
+        let [x, y] = coord.0.map(|c| c as Float * delta);
+        *value = Vector([0.3 * x - 0.5 * y + 0.1 * x * y, 0.8 * x - 0.4 * y * y]);
+    }
+    grid
+}
+
+fn time(name: &str, f: impl Fn() -> Float) {
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+    println!("{name:<16} {:>10.3?}", start.elapsed() / RUNS);
+}
+
+fn main() {
+    let delta = 1.0 / SIZE as Float;
+    let dt = 0.01;
+    let aos = rotating_field(SIZE, delta);
+    let soa = VectorField::from(&aos);
+    let coords: Vec<_> = aos.coords().collect();
+
+    time("aos divergence", || {
+        coords.iter().map(|c| aos.divergence(*c)).sum()
+    });
+    time("soa divergence", || {
+        coords.iter().map(|c| soa.divergence(*c)).sum()
+    });
+    time("aos curl", || coords.iter().map(|c| aos.curl(*c)).sum());
+    time("soa curl", || coords.iter().map(|c| soa.curl(*c)).sum());
+    time("aos advect", || {
+        coords.iter().map(|c| aos.advect(&aos, *c, dt).0[0]).sum()
+    });
+    time("soa advect", || {
+        coords.iter().map(|c| soa.advect(&soa, *c, dt).0[0]).sum()
+    });
+}
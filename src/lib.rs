@@ -0,0 +1,9 @@
+//! Grid-based incompressible fluid simulation.
+//!
+//! The [`simulation`] module holds the solver and everything around it: grids
+//! and vectors, boundary conditions, scene files, batch runs and file formats.
+//! The interactive viewer lives in the binary, behind the default `gui`
+//! feature; build with `--no-default-features` to leave out its windowing
+//! dependencies.
+
+pub mod simulation;
@@ -1,12 +1,110 @@
-mod simulation;
+#[cfg(feature = "gui")]
 mod ui;
 
-use std::time::{Duration, Instant};
+use std::{path::PathBuf, process::ExitCode};
 
-use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
-use ui::ui::{FluidSimulation, CELL_SIZE, HEIGHT, WIDTH};
+use nsh::simulation::{
+    batch::{self, Options},
+    scene::RunLength,
+    vector::Float,
+};
+
+const USAGE: &str = "\
+usage: nsh [--headless SCENE [options]]
+
+Without arguments, opens the interactive viewer. With --headless, runs the
+scene file to the end without a window, printing progress to stderr.
+
+options:
+    --steps N        run N > 0 steps, overriding the scene's [run] table
+    --end-time T     run until simulated time T > 0
+    --output DIR     write outputs and diagnostics.csv into DIR
+    --quiet          print only errors
+
+exit status: 0 on success, 1 if writing output failed, 2 for invalid
+arguments or scene files, 3 if the simulation diverged";
+
+/// Runs a scene without a window, as `nsh --headless SCENE [options]`.
+fn headless(mut args: impl Iterator<Item = String>) -> Result<ExitCode, String> {
+    let mut scene = None;
+    let mut options = Options::default();
+    let mut quiet = false;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "--steps" => {
+                let steps = value("--steps")?;
+                let steps = steps
+                    .parse()
+                    .ok()
+                    .filter(|&n: &u64| n > 0)
+                    .ok_or_else(|| format!("invalid step count {steps:?}"))?;
+                options.run = Some(RunLength::Steps(steps));
+            }
+            "--end-time" => {
+                let time = value("--end-time")?;
+                let time = time
+                    .parse()
+                    .ok()
+                    .filter(|&t: &Float| t > 0.0 && t.is_finite())
+                    .ok_or_else(|| format!("invalid end time {time:?}"))?;
+                options.run = Some(RunLength::EndTime(time));
+            }
+            "--output" => options.output = Some(PathBuf::from(value("--output")?)),
+            "--quiet" => quiet = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+            _ if scene.is_none() => scene = Some(arg),
+            _ => return Err(format!("unexpected argument {arg:?}")),
+        }
+    }
+    let scene = scene.ok_or("missing scene file")?;
+
+    let progress: Box<dyn std::io::Write> = if quiet {
+        Box::new(std::io::sink())
+    } else {
+        Box::new(std::io::stderr())
+    };
+    match batch::run_file(&scene, &options, progress) {
+        Ok(_) => Ok(ExitCode::SUCCESS),
+        Err(error) => {
+            eprintln!("{scene}: {error}");
+            Ok(ExitCode::from(error.exit_code() as u8))
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        None => viewer(),
+        Some("--headless") => headless(args).unwrap_or_else(|error| {
+            eprintln!("nsh: {error}\n\n{USAGE}");
+            ExitCode::from(2)
+        }),
+        Some("--help" | "-h") => {
+            println!("{USAGE}");
+            ExitCode::SUCCESS
+        }
+        Some(arg) => {
+            eprintln!("nsh: unexpected argument {arg:?}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(not(feature = "gui"))]
+fn viewer() -> ExitCode {
+    eprintln!("nsh: built without the gui feature; use --headless\n\n{USAGE}");
+    ExitCode::from(2)
+}
+
+#[cfg(feature = "gui")]
+fn viewer() -> ExitCode {
+    use std::time::{Duration, Instant};
+
+    use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
+    use ui::ui::{FluidSimulation, CELL_SIZE, HEIGHT, WIDTH};
 
-fn main() {
     let window_width = WIDTH * CELL_SIZE;
     let window_height = HEIGHT * CELL_SIZE;
 
@@ -42,4 +140,5 @@ fn main() {
             .update_with_buffer(&buffer, window_width, window_height)
             .unwrap();
     }
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,196 @@
+//! Elementwise arithmetic, reductions and norms on whole grids.
+
+use std::ops::{Add, Div, Mul, Sub};
+
+use super::{
+    error::GridError,
+    grid::{CoordInt, Grid},
+    scalar::{GridValue, Scalar},
+};
+
+impl<T: Default + Clone, const D: usize> Grid<T, D> {
+    /// Checks that `other` has the same size as `self`.
+    pub fn check_shape<U: Default + Clone>(&self, other: &Grid<U, D>) -> Result<(), GridError> {
+        if self.size() == other.size() {
+            Ok(())
+        } else {
+            Err(GridError::ShapeMismatch {
+                expected: self.size().0.to_vec(),
+                found: other.size().0.to_vec(),
+            })
+        }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.as_mut_slice().fill(value);
+    }
+
+    pub fn map<U: Default + Clone, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U, D> {
+        let mut out = Grid::new(self.size(), self.delta());
+        for (dst, src) in out
+            .as_mut_slice()
+            .iter_mut()
+            .zip(self.as_slice().iter().map(f))
+        {
+            *dst = src;
+        }
+        out
+    }
+
+    pub fn zip_map<U, V, F>(&self, other: &Grid<U, D>, mut f: F) -> Result<Grid<V, D>, GridError>
+    where
+        U: Default + Clone,
+        V: Default + Clone,
+        F: FnMut(&T, &U) -> V,
+    {
+        self.check_shape(other)?;
+        let mut out = Grid::new(self.size(), self.delta());
+        for (dst, (a, b)) in out
+            .as_mut_slice()
+            .iter_mut()
+            .zip(self.as_slice().iter().zip(other.as_slice()))
+        {
+            *dst = f(a, b);
+        }
+        Ok(out)
+    }
+}
+
+impl<T: Default + Clone + Add<Output = T>, const D: usize> Grid<T, D> {
+    pub fn sum(&self) -> T {
+        self.as_slice()
+            .iter()
+            .cloned()
+            .fold(T::default(), |acc, v| acc + v)
+    }
+}
+
+impl<T: GridValue, const D: usize> Grid<T, D> {
+    /// `self += a * x`, cell by cell.
+    pub fn axpy(&mut self, a: T::Scalar, x: &Grid<T, D>) -> Result<(), GridError> {
+        self.check_shape(x)?;
+        for (y, x) in self.as_mut_slice().iter_mut().zip(x.as_slice()) {
+            *y = y.clone() + x.clone() * a;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Scalar, const D: usize> Grid<S, D> {
+    pub fn dot(&self, other: &Grid<S, D>) -> Result<S, GridError> {
+        self.check_shape(other)?;
+        Ok(self
+            .as_slice()
+            .iter()
+            .zip(other.as_slice())
+            .map(|(a, b)| *a * *b)
+            .sum())
+    }
+
+    /// Mean cell value, `NaN` for an empty grid.
+    pub fn mean(&self) -> S {
+        self.sum() / S::from_f64(self.as_slice().len() as f64)
+    }
+
+    pub fn min(&self) -> Option<S> {
+        self.argmin().map(|(_, value)| value)
+    }
+
+    pub fn max(&self) -> Option<S> {
+        self.argmax().map(|(_, value)| value)
+    }
+
+    /// Coordinate and value of the smallest cell, the first one on ties. NaNs are skipped.
+    pub fn argmin(&self) -> Option<(CoordInt<D>, S)> {
+        self.arg_best(|candidate, best| candidate < best)
+    }
+
+    /// Coordinate and value of the largest cell, the first one on ties. NaNs are skipped.
+    pub fn argmax(&self) -> Option<(CoordInt<D>, S)> {
+        self.arg_best(|candidate, best| candidate > best)
+    }
+
+    fn arg_best(&self, better: impl Fn(S, S) -> bool) -> Option<(CoordInt<D>, S)> {
+        let mut best: Option<(usize, S)> = None;
+        for (i, &value) in self.as_slice().iter().enumerate() {
+            if value.is_nan() {
+                continue;
+            }
+            if best.is_none_or(|(_, b)| better(value, b)) {
+                best = Some((i, value));
+            }
+        }
+        best.map(|(i, value)| (self.unflatten_index(i), value))
+    }
+
+    /// Sum of absolute cell values.
+    pub fn norm_l1(&self) -> S {
+        self.as_slice().iter().map(|v| v.abs()).sum()
+    }
+
+    /// Square root of the sum of squared cell values.
+    pub fn norm_l2(&self) -> S {
+        self.as_slice().iter().map(|v| *v * *v).sum::<S>().sqrt()
+    }
+
+    /// Largest absolute cell value.
+    pub fn norm_linf(&self) -> S {
+        self.as_slice()
+            .iter()
+            .fold(S::ZERO, |acc, v| acc.max(v.abs()))
+    }
+}
+
+impl<T: Default + Clone + Add<Output = T>, const D: usize> Add for &Grid<T, D> {
+    type Output = Result<Grid<T, D>, GridError>;
+
+    fn add(self, other: Self) -> Self::Output {
+        self.zip_map(other, |a, b| a.clone() + b.clone())
+    }
+}
+
+impl<T: Default + Clone + Sub<Output = T>, const D: usize> Sub for &Grid<T, D> {
+    type Output = Result<Grid<T, D>, GridError>;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self.zip_map(other, |a, b| a.clone() - b.clone())
+    }
+}
+
+impl<T: GridValue, const D: usize> Mul<T::Scalar> for &Grid<T, D> {
+    type Output = Grid<T, D>;
+
+    fn mul(self, scalar: T::Scalar) -> Grid<T, D> {
+        self.map(|v| v.clone() * scalar)
+    }
+}
+
+impl<T: GridValue, const D: usize> Mul<T::Scalar> for Grid<T, D> {
+    type Output = Grid<T, D>;
+
+    fn mul(mut self, scalar: T::Scalar) -> Grid<T, D> {
+        for v in self.as_mut_slice() {
+            *v = v.clone() * scalar;
+        }
+        self
+    }
+}
+
+impl<T: GridValue, const D: usize> Div<T::Scalar> for &Grid<T, D> {
+    type Output = Grid<T, D>;
+
+    fn div(self, scalar: T::Scalar) -> Grid<T, D> {
+        self.map(|v| v.clone() / scalar)
+    }
+}
+
+impl<T: GridValue, const D: usize> Div<T::Scalar> for Grid<T, D> {
+    type Output = Grid<T, D>;
+
+    fn div(mut self, scalar: T::Scalar) -> Grid<T, D> {
+        for v in self.as_mut_slice() {
+            *v = v.clone() / scalar;
+        }
+        self
+    }
+}
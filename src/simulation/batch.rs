@@ -0,0 +1,335 @@
+//! Headless runs of a scene file: the simulation is stepped to the end of the
+//! run while the scene's outputs are written on schedule, along with a
+//! `diagnostics.csv` table, and progress lines report an ETA as it goes.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use super::{
+    error::{BatchError, SceneError},
+    io::{
+        npy::NpzWriter,
+        render::FrameRecorder,
+        vtk::{Encoding, TimeSeries},
+    },
+    scalar::Scalar,
+    scene::{self, Output, OutputFormat, RunLength, Scene},
+    simulation::Simulation,
+    vector::Float,
+};
+
+/// Settings that override those of the scene file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    pub run: Option<RunLength>,
+    /// Directory for the outputs. Without an `[output]` table in the scene,
+    /// only the diagnostics are written there, every step.
+    pub output: Option<PathBuf>,
+    /// Minimum time between progress lines.
+    pub progress_interval: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            run: None,
+            output: None,
+            progress_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// What a finished run did.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub steps: u64,
+    pub time: Float,
+    /// Number of steps outputs were written for.
+    pub outputs: u64,
+    pub elapsed: Duration,
+}
+
+/// Integral quantities of the state, tracked to detect a diverging run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Diagnostics {
+    /// Total density times cell volume.
+    pub mass: Float,
+    pub kinetic_energy: Float,
+    pub max_speed: Float,
+}
+
+impl Diagnostics {
+    pub fn of<const D: usize, S: Scalar>(sim: &Simulation<D, S>) -> Self {
+        let volume = sim.delta().powi(D as i32);
+        let mass = sim
+            .densities
+            .as_slice()
+            .iter()
+            .map(|d| d.to_f64())
+            .sum::<Float>();
+        let speeds = sim.velocities.as_slice().iter().map(|v| v.norm().to_f64());
+        let (energy, max_speed) = speeds.fold((0.0, 0.0), |(energy, max): (Float, Float), s| {
+            (energy + 0.5 * s * s, max.max(s))
+        });
+        Diagnostics {
+            mass: mass * volume,
+            kinetic_energy: energy * volume,
+            max_speed,
+        }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.mass.is_finite() && self.kinetic_energy.is_finite() && self.max_speed.is_finite()
+    }
+}
+
+/// Loads a scene file of any dimension and runs it, writing progress lines
+/// to `progress`.
+pub fn run_file(
+    path: impl AsRef<Path>,
+    options: &Options,
+    progress: impl Write,
+) -> Result<Summary, BatchError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(SceneError::from)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    match scene::dimensions(&text)? {
+        1 => run_scene(
+            &Scene::<1>::parse(&text, base)?,
+            options,
+            progress,
+            |_, _| Ok(()),
+        ),
+        2 => {
+            let scene = Scene::<2>::parse(&text, base)?;
+            let mut recorder = None;
+            run_scene(&scene, options, progress, |sim, output| {
+                let recorder = match &mut recorder {
+                    Some(recorder) => recorder,
+                    None => recorder.insert(
+                        FrameRecorder::new(&output.directory, output.every)?
+                            .with_source(output.source.clone())
+                            .with_colormap(output.colormap),
+                    ),
+                };
+                recorder.record(sim).map(drop)
+            })
+        }
+        _ => run_scene(
+            &Scene::<3>::parse(&text, base)?,
+            options,
+            progress,
+            |_, _| Ok(()),
+        ),
+    }
+}
+
+/// Runs `scene` to the end of its run. `frame` is called on output steps if
+/// PNG output is requested, to draw the state.
+pub fn run_scene<const D: usize>(
+    scene: &Scene<D>,
+    options: &Options,
+    mut progress: impl Write,
+    mut frame: impl FnMut(&Simulation<D>, &Output) -> io::Result<()>,
+) -> Result<Summary, BatchError> {
+    let run = options.run.or(scene.run).ok_or(BatchError::NoRunLength)?;
+    let mut sim = scene.simulation();
+    let total = match run {
+        RunLength::Steps(steps) => steps,
+        // stop at the first step that reaches the end time
+        RunLength::EndTime(end) => (end / sim.params.dt - 1e-9).ceil().max(0.0) as u64,
+    };
+    let output = match (&scene.output, &options.output) {
+        (Some(output), directory) => Some(Output {
+            directory: directory
+                .clone()
+                .unwrap_or_else(|| output.directory.clone()),
+            ..output.clone()
+        }),
+        (None, Some(directory)) => Some(Output {
+            directory: directory.clone(),
+            every: 1,
+            formats: Vec::new(),
+            source: Default::default(),
+            colormap: Default::default(),
+        }),
+        (None, None) => None,
+    };
+    let mut writer = output.map(Writer::new).transpose()?;
+
+    let start = Instant::now();
+    let mut reported = start;
+    if let Some(writer) = &mut writer {
+        writer.write(&sim, Diagnostics::of(&sim), &mut frame)?;
+    }
+    while sim.steps < total {
+        scene.step(&mut sim);
+        let diagnostics = Diagnostics::of(&sim);
+        if !diagnostics.is_finite() {
+            return Err(BatchError::Diverged {
+                step: sim.steps,
+                time: sim.time,
+            });
+        }
+        if let Some(writer) = &mut writer {
+            if sim.steps.is_multiple_of(writer.output.every) {
+                writer.write(&sim, diagnostics, &mut frame)?;
+            }
+        }
+        if reported.elapsed() >= options.progress_interval && sim.steps < total {
+            reported = Instant::now();
+            let elapsed = start.elapsed();
+            let eta = elapsed.mul_f64((total - sim.steps) as f64 / sim.steps as f64);
+            // progress is best effort; a closed stderr must not stop the run
+            let _ = writeln!(
+                progress,
+                "step {}/{total} ({:.1}%)  t = {:.4}  elapsed {}  eta {}",
+                sim.steps,
+                100.0 * sim.steps as f64 / total as f64,
+                sim.time,
+                format_duration(elapsed),
+                format_duration(eta),
+            );
+        }
+    }
+
+    let elapsed = start.elapsed();
+    let _ = writeln!(
+        progress,
+        "finished {} steps (t = {:.4}) in {}",
+        sim.steps,
+        sim.time,
+        format_duration(elapsed)
+    );
+    Ok(Summary {
+        steps: sim.steps,
+        time: sim.time,
+        outputs: writer.map_or(0, |writer| writer.written),
+        elapsed,
+    })
+}
+
+/// `m:ss`, or `h:mm:ss` from an hour on.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+fn output_error(
+    path: &Path,
+    error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+) -> BatchError {
+    BatchError::Output {
+        path: path.to_path_buf(),
+        source: error.into(),
+    }
+}
+
+/// The files of one run's output directory.
+struct Writer {
+    output: Output,
+    diagnostics: BufWriter<File>,
+    series: Option<TimeSeries>,
+    written: u64,
+}
+
+impl Writer {
+    fn new(output: Output) -> Result<Self, BatchError> {
+        let dir = &output.directory;
+        std::fs::create_dir_all(dir).map_err(|e| output_error(dir, e))?;
+        let path = dir.join("diagnostics.csv");
+        let mut diagnostics = File::create(&path)
+            .map(BufWriter::new)
+            .map_err(|e| output_error(&path, e))?;
+        writeln!(diagnostics, "step,time,mass,kinetic_energy,max_speed")
+            .map_err(|e| output_error(&path, e))?;
+        let series = if output.formats.contains(&OutputFormat::Vti) {
+            let series = TimeSeries::new(dir, "state", Encoding::default());
+            Some(series.map_err(|e| output_error(dir, e))?)
+        } else {
+            None
+        };
+        Ok(Writer {
+            output,
+            diagnostics,
+            series,
+            written: 0,
+        })
+    }
+
+    fn write<const D: usize>(
+        &mut self,
+        sim: &Simulation<D>,
+        diagnostics: Diagnostics,
+        frame: &mut impl FnMut(&Simulation<D>, &Output) -> io::Result<()>,
+    ) -> Result<(), BatchError> {
+        let dir = &self.output.directory;
+        let Diagnostics {
+            mass,
+            kinetic_energy,
+            max_speed,
+        } = diagnostics;
+        let row = writeln!(
+            self.diagnostics,
+            "{},{},{mass},{kinetic_energy},{max_speed}",
+            sim.steps, sim.time
+        );
+        row.and_then(|_| self.diagnostics.flush())
+            .map_err(|e| output_error(&dir.join("diagnostics.csv"), e))?;
+
+        for format in &self.output.formats {
+            match format {
+                OutputFormat::Png => frame(sim, &self.output).map_err(|e| output_error(dir, e))?,
+                OutputFormat::Vti => {
+                    let series = self.series.as_mut().unwrap();
+                    series
+                        .write_step(sim.steps, sim.time, &sim.image_data())
+                        .map_err(|e| output_error(dir, e))?;
+                }
+                OutputFormat::Npz => {
+                    let path = dir.join(format!("state_{:06}.npz", sim.steps));
+                    write_npz(&path, sim).map_err(|e| output_error(&path, e))?;
+                }
+                OutputFormat::Checkpoint => {
+                    // replace the previous checkpoint only once the new one is complete
+                    let path = dir.join("checkpoint.bin");
+                    let partial = dir.join("checkpoint.bin.partial");
+                    sim.save_checkpoint(&partial)
+                        .map_err(|e| output_error(&path, e))?;
+                    std::fs::rename(&partial, &path).map_err(|e| output_error(&path, e))?;
+                }
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+}
+
+/// Every grid of `sim`, with the step, time and grid spacing as metadata.
+fn write_npz<const D: usize>(
+    path: &Path,
+    sim: &Simulation<D>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut npz = NpzWriter::create(path)?;
+    npz.add_grid("density", &sim.densities)?;
+    npz.add_grid("velocity", &sim.velocities)?;
+    npz.add_grid("pressure", &sim.pressure)?;
+    npz.add_grid("obstacles", &sim.boundary.obstacles)?;
+    for (name, field) in &sim.fields {
+        npz.add_grid(name, field)?;
+    }
+    npz.add_metadata("step", sim.steps as f64)?;
+    npz.add_metadata("time", sim.time)?;
+    npz.add_metadata("delta", sim.delta())?;
+    npz.finish()?.flush()?;
+    Ok(())
+}
@@ -0,0 +1,166 @@
+//! Boundary conditions on the faces of the domain and solid obstacles inside it.
+//!
+//! Cells are cell-centred: the wall of face `(axis, side)` lies half a cell
+//! beyond the outermost row of cells. The solver reads neighbours across a
+//! wall through `ghost_*` values that enforce the condition on that face.
+
+use super::{
+    grid::{CoordInt, Grid, Int},
+    scalar::Scalar,
+    vector::{Float, Vector},
+};
+
+/// Condition on one face of the domain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Boundary<const D: usize, S: Scalar = Float> {
+    /// Solid wall; the fluid is at rest against it.
+    NoSlip,
+    /// Solid wall the fluid slides along without friction.
+    FreeSlip,
+    /// Solid wall moving with the given velocity, such as a cavity lid.
+    Moving(Vector<D, S>),
+    /// Fluid enters with the given velocity.
+    Inflow(Vector<D, S>),
+    /// Fluid leaves freely; pressure is held at zero.
+    Outflow,
+    /// The face wraps around to the opposite one. Must be set on both.
+    Periodic,
+}
+
+/// Lower (`0`) and upper (`1`) face of an axis.
+pub type Side = usize;
+
+/// The condition on every face plus a mask of solid cells.
+pub struct BoundaryConfig<const D: usize, S: Scalar = Float> {
+    pub(crate) faces: [[Boundary<D, S>; 2]; D],
+    /// `true` for cells inside an obstacle, which act as no-slip walls.
+    pub obstacles: Grid<bool, D>,
+}
+
+/// What lies across a face of a fluid cell.
+pub(crate) enum Neighbour<'a, const D: usize, S: Scalar> {
+    Fluid(CoordInt<D>),
+    Solid,
+    Wall(&'a Boundary<D, S>),
+}
+
+impl<const D: usize, S: Scalar> BoundaryConfig<D, S> {
+    /// No-slip walls on every face and no obstacles.
+    pub fn new(size: CoordInt<D>, delta: Float) -> Self {
+        BoundaryConfig {
+            faces: [[Boundary::NoSlip; 2]; D],
+            obstacles: Grid::new(size, delta),
+        }
+    }
+
+    pub fn face(&self, axis: usize, side: Side) -> &Boundary<D, S> {
+        &self.faces[axis][side]
+    }
+
+    /// Sets one face. `Periodic` applies to both faces of the axis, and
+    /// replacing one face of a periodic axis makes the other a no-slip wall.
+    pub fn set_face(&mut self, axis: usize, side: Side, boundary: Boundary<D, S>) {
+        if boundary == Boundary::Periodic {
+            self.faces[axis] = [Boundary::Periodic; 2];
+            return;
+        }
+        if self.is_periodic(axis) {
+            self.faces[axis] = [Boundary::NoSlip; 2];
+        }
+        self.faces[axis][side] = boundary;
+    }
+
+    /// Sets both faces of `axis`.
+    pub fn set_axis(&mut self, axis: usize, boundary: Boundary<D, S>) {
+        self.faces[axis] = [boundary; 2];
+    }
+
+    pub fn is_periodic(&self, axis: usize) -> bool {
+        self.faces[axis][0] == Boundary::Periodic
+    }
+
+    pub fn is_solid(&self, coord: &CoordInt<D>) -> bool {
+        self.obstacles.get(coord).copied().unwrap_or(false)
+    }
+
+    /// The cell or wall one step along `axis` (`step` is `1` or `-1`).
+    pub(crate) fn neighbour(
+        &self,
+        coord: CoordInt<D>,
+        axis: usize,
+        step: Int,
+    ) -> Neighbour<'_, D, S> {
+        let size = self.obstacles.size().0[axis];
+        let mut next = coord.offset(axis, step);
+        if !(0..size).contains(&next.0[axis]) {
+            if !self.is_periodic(axis) {
+                return Neighbour::Wall(&self.faces[axis][(step > 0) as usize]);
+            }
+            next.0[axis] = next.0[axis].rem_euclid(size);
+        }
+        if self.is_solid(&next) {
+            Neighbour::Solid
+        } else {
+            Neighbour::Fluid(next)
+        }
+    }
+
+    /// Velocity across a face of `coord`, mirrored so that the condition
+    /// holds halfway between the cell and its ghost.
+    pub(crate) fn ghost_velocity(
+        &self,
+        velocity: &Grid<Vector<D, S>, D>,
+        coord: CoordInt<D>,
+        axis: usize,
+        step: Int,
+    ) -> Vector<D, S> {
+        let centre = velocity.as_slice()[velocity.flatten_index(&coord)];
+        let two = S::from_f64(2.0);
+        match self.neighbour(coord, axis, step) {
+            Neighbour::Fluid(next) => velocity.as_slice()[velocity.flatten_index(&next)],
+            Neighbour::Solid | Neighbour::Wall(Boundary::NoSlip) => -centre,
+            Neighbour::Wall(Boundary::FreeSlip) => {
+                let mut ghost = centre;
+                ghost.0[axis] = -ghost.0[axis];
+                ghost
+            }
+            Neighbour::Wall(Boundary::Moving(wall) | Boundary::Inflow(wall)) => {
+                *wall * two - centre
+            }
+            Neighbour::Wall(Boundary::Outflow | Boundary::Periodic) => centre,
+        }
+    }
+
+    /// A transported scalar across a face of `coord`; walls let nothing
+    /// through, so the ghost copies the cell.
+    pub(crate) fn ghost_scalar(
+        &self,
+        field: &Grid<S, D>,
+        coord: CoordInt<D>,
+        axis: usize,
+        step: Int,
+    ) -> S {
+        match self.neighbour(coord, axis, step) {
+            Neighbour::Fluid(next) => field.as_slice()[field.flatten_index(&next)],
+            _ => field.as_slice()[field.flatten_index(&coord)],
+        }
+    }
+
+    /// Pressure across a face of `coord`: zero at outflow faces, zero normal
+    /// gradient everywhere else.
+    pub(crate) fn ghost_pressure(
+        &self,
+        pressure: &Grid<S, D>,
+        coord: CoordInt<D>,
+        axis: usize,
+        step: Int,
+    ) -> S {
+        let centre = pressure.as_slice()[pressure.flatten_index(&coord)];
+        match self.neighbour(coord, axis, step) {
+            Neighbour::Fluid(next) => pressure.as_slice()[pressure.flatten_index(&next)],
+            Neighbour::Wall(Boundary::Outflow) => -centre,
+            _ => centre,
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::fmt;
+
+use std::path::PathBuf;
+
+use super::{grid::Int, vector::Float};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridError {
+    /// A coordinate lies outside the grid.
+    OutOfBounds { index: Vec<Int>, size: Vec<Int> },
+    /// Two grids that must line up cell by cell have different sizes.
+    ShapeMismatch { expected: Vec<Int>, found: Vec<Int> },
+    /// A coordinate or vector has the wrong number of components.
+    DimensionMismatch { expected: usize, found: usize },
+    /// A coordinate component does not fit in `Int`.
+    ComponentOverflow { value: usize },
+    /// An input or result is NaN or infinite.
+    NonFinite,
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::OutOfBounds { index, size } => {
+                write!(f, "index {index:?} out of bounds for grid of size {size:?}")
+            }
+            GridError::ShapeMismatch { expected, found } => {
+                write!(
+                    f,
+                    "grid shape mismatch: expected {expected:?}, found {found:?}"
+                )
+            }
+            GridError::DimensionMismatch { expected, found } => {
+                write!(
+                    f,
+                    "dimension mismatch: expected {expected} components, found {found}"
+                )
+            }
+            GridError::ComponentOverflow { value } => {
+                write!(f, "coordinate component {value} exceeds {}", Int::MAX)
+            }
+            GridError::NonFinite => write!(f, "non-finite value"),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(std::io::Error),
+    /// The data does not start with the checkpoint signature.
+    BadMagic,
+    /// The checkpoint was written by a newer or older, incompatible format.
+    UnsupportedVersion {
+        found: u32,
+        supported: u32,
+    },
+    /// The checkpoint holds a simulation of a different dimension `D`.
+    DimensionMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// The stored checksum does not match the contents.
+    ChecksumMismatch {
+        stored: u32,
+        computed: u32,
+    },
+    /// The contents are truncated or inconsistent.
+    Corrupt(String),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "checkpoint i/o error: {error}"),
+            CheckpointError::BadMagic => write!(f, "not a checkpoint file"),
+            CheckpointError::UnsupportedVersion { found, supported } => {
+                write!(
+                    f,
+                    "unsupported checkpoint version {found}, expected {supported}"
+                )
+            }
+            CheckpointError::DimensionMismatch { expected, found } => {
+                write!(
+                    f,
+                    "checkpoint is {found}-dimensional, expected {expected} dimensions"
+                )
+            }
+            CheckpointError::ChecksumMismatch { stored, computed } => {
+                write!(
+                    f,
+                    "checkpoint checksum mismatch: stored {stored:08x}, computed {computed:08x}"
+                )
+            }
+            CheckpointError::Corrupt(reason) => write!(f, "corrupt checkpoint: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CheckpointError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CheckpointError {
+    fn from(error: std::io::Error) -> Self {
+        CheckpointError::Io(error)
+    }
+}
+
+#[derive(Debug)]
+pub enum NpyError {
+    Io(std::io::Error),
+    /// The data is not a valid `.npy` file or `.npz` archive.
+    Format(String),
+    /// The array holds a different element type than the grid.
+    Dtype {
+        expected: String,
+        found: String,
+    },
+    /// The array shape does not fit a `dimensions`-dimensional grid of values
+    /// with `components` components each.
+    Shape {
+        dimensions: usize,
+        components: usize,
+        found: Vec<usize>,
+    },
+    /// The array is stored in Fortran (column-major) order.
+    FortranOrder,
+    /// The archive has no array of this name.
+    Missing(String),
+}
+
+impl fmt::Display for NpyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NpyError::Io(error) => write!(f, "npy i/o error: {error}"),
+            NpyError::Format(reason) => write!(f, "invalid npy data: {reason}"),
+            NpyError::Dtype { expected, found } => {
+                write!(f, "npy dtype mismatch: expected {expected}, found {found}")
+            }
+            NpyError::Shape {
+                dimensions,
+                components,
+                found,
+            } => {
+                write!(
+                    f,
+                    "array of shape {found:?} does not fit a {dimensions}-dimensional grid"
+                )?;
+                if *components > 1 {
+                    write!(f, " of {components}-component values")?;
+                }
+                Ok(())
+            }
+            NpyError::FortranOrder => write!(f, "Fortran-ordered arrays are not supported"),
+            NpyError::Missing(name) => write!(f, "no array named {name:?} in archive"),
+        }
+    }
+}
+
+impl std::error::Error for NpyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NpyError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for NpyError {
+    fn from(error: std::io::Error) -> Self {
+        NpyError::Io(error)
+    }
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(std::io::Error),
+    /// The data is not a well-formed image.
+    Format(String),
+    /// The image is valid but uses a feature the decoder does not handle.
+    Unsupported(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(error) => write!(f, "image i/o error: {error}"),
+            ImageError::Format(reason) => write!(f, "invalid image: {reason}"),
+            ImageError::Unsupported(feature) => write!(f, "unsupported image: {feature}"),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImageError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ImageError {
+    fn from(error: std::io::Error) -> Self {
+        ImageError::Io(error)
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    /// The file is not valid TOML.
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    /// A key is missing, unknown or has an unusable value. `key` is the
+    /// dotted path to it, such as `obstacle[1].radius`.
+    Invalid {
+        key: String,
+        line: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "scene i/o error: {error}"),
+            SceneError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            SceneError::Invalid { key, line, reason } => {
+                write!(f, "line {line}: `{key}`: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(error: std::io::Error) -> Self {
+        SceneError::Io(error)
+    }
+}
+
+#[derive(Debug)]
+pub enum BatchError {
+    Scene(SceneError),
+    /// Neither the scene nor the caller says when to stop.
+    NoRunLength,
+    /// An output file could not be written.
+    Output {
+        path: PathBuf,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The state became NaN or infinite.
+    Diverged {
+        step: u64,
+        time: Float,
+    },
+}
+
+impl BatchError {
+    /// Process exit status for the error: 2 for unusable input, 3 for a
+    /// diverged run and 1 for failures writing output.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            BatchError::Scene(_) | BatchError::NoRunLength => 2,
+            BatchError::Output { .. } => 1,
+            BatchError::Diverged { .. } => 3,
+        }
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Scene(error) => write!(f, "{error}"),
+            BatchError::NoRunLength => write!(f, "no run length: set [run] steps or end_time"),
+            BatchError::Output { path, source } => {
+                write!(f, "cannot write {}: {source}", path.display())
+            }
+            BatchError::Diverged { step, time } => {
+                write!(f, "simulation diverged at step {step} (t = {time})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BatchError::Scene(error) => Some(error),
+            BatchError::Output { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<SceneError> for BatchError {
+    fn from(error: SceneError) -> Self {
+        BatchError::Scene(error)
+    }
+}
@@ -1,11 +1,13 @@
-use std::{
-    iter::Product, ops::{Add, Div, Mul, Sub}, path::Iter, process::Output
+use super::{
+    error::GridError,
+    iter::{FaceNeighbours, MooreNeighbours},
+    scalar::{GridValue, Scalar},
+    stencil::{first_derivative, Accuracy, Stencil},
+    vector::{Float, Vector},
 };
 
-use super::vector::{Float, Vector};
-
 pub type Int = i32;
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct CoordInt<const D: usize>(pub [Int; D]);
 
 impl<const D: usize> Default for CoordInt<D> {
@@ -14,287 +16,499 @@ impl<const D: usize> Default for CoordInt<D> {
     }
 }
 
+impl<const D: usize> CoordInt<D> {
+    /// Collects exactly `D` components, failing on any other count or on a
+    /// component too large for `Int`.
+    pub fn try_from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Result<Self, GridError> {
+        let mut coord = CoordInt::default();
+        let mut found = 0;
+        for value in iter {
+            if found < D {
+                coord.0[found] =
+                    Int::try_from(value).map_err(|_| GridError::ComponentOverflow { value })?;
+            }
+            found += 1;
+        }
+        if found == D {
+            Ok(coord)
+        } else {
+            Err(GridError::DimensionMismatch { expected: D, found })
+        }
+    }
+
+    /// The coordinate one step along `axis`.
+    pub fn unit(axis: usize) -> Self {
+        let mut coord = Self::default();
+        coord.0[axis] = 1;
+        coord
+    }
+
+    /// This coordinate moved by `delta` cells along `axis`.
+    pub fn offset(mut self, axis: usize, delta: Int) -> Self {
+        self.0[axis] += delta;
+        self
+    }
+
+    /// The 2D neighbours sharing a face, ordered `-x, +x, -y, +y, ...`.
+    pub fn face_neighbours(self) -> FaceNeighbours<D> {
+        FaceNeighbours::new(self)
+    }
+
+    /// The 3^D - 1 neighbours sharing a face, edge or corner, in row-major
+    /// order of their offsets.
+    pub fn moore_neighbours(self) -> MooreNeighbours<D> {
+        MooreNeighbours::new(self)
+    }
+
+    /// This coordinate shifted by each of `offsets`, in order.
+    pub fn stencil(
+        self,
+        offsets: &[CoordInt<D>],
+    ) -> impl ExactSizeIterator<Item = CoordInt<D>> + '_ {
+        offsets.iter().map(move |&offset| self + offset)
+    }
+}
+
+impl<const D: usize> std::ops::Add for CoordInt<D> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        CoordInt(std::array::from_fn(|i| self.0[i] + other.0[i]))
+    }
+}
+
+impl<const D: usize> std::ops::Sub for CoordInt<D> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        CoordInt(std::array::from_fn(|i| self.0[i] - other.0[i]))
+    }
+}
+
+impl<const D: usize> std::ops::Neg for CoordInt<D> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        CoordInt(self.0.map(|c| -c))
+    }
+}
+
+impl<const D: usize> std::ops::AddAssign for CoordInt<D> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const D: usize> std::ops::SubAssign for CoordInt<D> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
 pub struct Grid<T, const D: usize> {
     vec: Vec<T>,
     size: CoordInt<D>,
     delta: Float,
 }
 
-fn capacity<const D: usize>(size: &CoordInt<D>) -> usize {
-    size.0.iter().copied().reduce(|a, b| a * b).unwrap_or(1) as usize
+/// Number of cells of a grid of `size`, or `None` if an extent is negative
+/// or the extents multiply past `Int::MAX`, counting empty axes as 1 so that
+/// the result does not depend on their order.
+pub(crate) fn capacity<const D: usize>(size: &CoordInt<D>) -> Option<usize> {
+    let mut cells: Int = 1;
+    for &extent in &size.0 {
+        if extent < 0 {
+            return None;
+        }
+        cells = cells.checked_mul(extent.max(1))?;
+    }
+    let empty = size.0.contains(&0);
+    Some(if empty { 0 } else { cells as usize })
+}
+
+/// Row-major offset of `index` in a grid of `size`.
+pub(crate) fn flatten<const D: usize>(size: &CoordInt<D>, index: &CoordInt<D>) -> usize {
+    index
+        .0
+        .iter()
+        .zip(size.0.iter())
+        .fold(0, |acc, (&i, &dim)| acc * dim as usize + i as usize)
+}
+
+pub(crate) fn unflatten<const D: usize>(size: &CoordInt<D>, mut index: usize) -> CoordInt<D> {
+    let mut coord = CoordInt::<D>::default();
+    for i in (0..D).rev() {
+        let dim = size.0[i] as usize;
+        coord.0[i] = (index % dim) as Int;
+        index /= dim;
+    }
+    coord
 }
 
 impl<T: Default + Clone, const D: usize> Grid<T, D> {
     pub fn new(size: CoordInt<D>, delta: Float) -> Self {
+        Self::filled(size, delta, T::default())
+    }
+
+    pub fn filled(size: CoordInt<D>, delta: Float, value: T) -> Self {
         Grid {
-            vec: vec![T::default(); capacity(&size)],
-            size: size,
-            delta: delta,
+            vec: vec![value; capacity(&size).expect("invalid grid size")],
+            size,
+            delta,
         }
     }
 
+    /// Wraps cells given in row-major order, as returned by `as_slice`.
+    ///
+    /// Fails with `ShapeMismatch` if `size` is negative or too large to
+    /// index, and with `DimensionMismatch` if `vec` has another length.
+    pub fn from_vec(size: CoordInt<D>, delta: Float, vec: Vec<T>) -> Result<Self, GridError> {
+        let Some(expected) = capacity(&size) else {
+            return Err(GridError::ShapeMismatch {
+                expected: size.0.map(|s| s.max(0)).to_vec(),
+                found: size.0.to_vec(),
+            });
+        };
+        if vec.len() != expected {
+            return Err(GridError::DimensionMismatch {
+                expected,
+                found: vec.len(),
+            });
+        }
+        Ok(Grid { vec, size, delta })
+    }
+
+    pub fn size(&self) -> CoordInt<D> {
+        self.size
+    }
+
+    pub fn delta(&self) -> Float {
+        self.delta
+    }
+
     pub fn flatten_index(&self, index: &CoordInt<D>) -> usize {
+        flatten(&self.size, index)
+    }
+
+    /// Inverse of `flatten_index`.
+    pub fn unflatten_index(&self, index: usize) -> CoordInt<D> {
+        unflatten(&self.size, index)
+    }
+
+    /// The cells in row-major order, as indexed by `flatten_index`.
+    pub fn as_slice(&self) -> &[T] {
+        &self.vec
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.vec
+    }
+
+    /// Whether `index` addresses a cell of the grid.
+    pub fn contains(&self, index: &CoordInt<D>) -> bool {
         index
             .0
             .iter()
             .zip(self.size.0.iter())
-            .fold(0, |acc, (&i, &dim)| acc * dim as usize + i as usize)
+            .all(|(&i, &dim)| 0 <= i && i < dim)
     }
 
     pub fn get(&self, index: &CoordInt<D>) -> Option<&T> {
-        if index
-            .0
-            .iter()
-            .zip(self.size.0.iter())
-            .any(|(&i, &dim)| i >= dim as Int || i < 0)
-        {
-            None
-        } else {
+        if self.contains(index) {
             self.vec.get(self.flatten_index(index))
+        } else {
+            None
+        }
+    }
+
+    /// Like `get`, but reports which index was out of bounds.
+    pub fn try_get(&self, index: &CoordInt<D>) -> Result<&T, GridError> {
+        self.get(index).ok_or_else(|| self.out_of_bounds(index))
+    }
+
+    pub(crate) fn check_index(&self, index: &CoordInt<D>) -> Result<(), GridError> {
+        self.try_get(index).map(|_| ())
+    }
+
+    fn out_of_bounds(&self, index: &CoordInt<D>) -> GridError {
+        GridError::OutOfBounds {
+            index: index.0.to_vec(),
+            size: self.size.0.to_vec(),
         }
     }
 
     pub fn get_mut(&mut self, index: &CoordInt<D>) -> Option<&mut T> {
+        if self.contains(index) {
+            let index = self.flatten_index(index);
+            self.vec.get_mut(index)
+        } else {
+            None
+        }
+    }
+
+    /// Cell at `index` without bounds checks.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be inside the grid, see `contains`. A coordinate outside
+    /// it is undefined behaviour even when its flat index happens to land on
+    /// another cell.
+    pub unsafe fn get_unchecked(&self, index: &CoordInt<D>) -> &T {
+        self.vec.get_unchecked(self.flatten_index(index))
+    }
+
+    /// Mutable cell at `index` without bounds checks.
+    ///
+    /// # Safety
+    ///
+    /// Same as `get_unchecked`.
+    pub unsafe fn get_unchecked_mut(&mut self, index: &CoordInt<D>) -> &mut T {
         let index = self.flatten_index(index);
-        self.vec.get_mut(index)
+        self.vec.get_unchecked_mut(index)
     }
 }
 
-impl<
-        T: Default + Clone + Add<Output = T> + Mul<Float, Output = T> + Product<Float>,
-        const D: usize,
-    > Grid<T, D>
-{
-    pub fn get_at(&self, pos: &Vector<D>) -> T {
-        // interpolate D-dimensionally between the 2^D closest points
-        let mut index = CoordInt::<D>::default();
-        let mut weights = [0.0; D];
-        for i in 0..D {
-            let coord = (pos.0[i] / self.delta)
-                .max(0.0)
-                .min(self.size.0[i] as Float - 1.0);
-            let lower = (coord.floor() as Int).min(self.size.0[i] - 2);
-
-            index.0[i] = lower;
-            weights[i] = coord - lower as Float;
-        }
+impl<T: Default + Clone, const D: usize> std::ops::Index<CoordInt<D>> for Grid<T, D> {
+    type Output = T;
 
-        let mut sum = T::default();
-        for i in 0..1 << D {
-            let mut index = index.clone();
-            for j in 0..D {
-                if i & (1 << j) != 0 {
-                    index.0[j] += 1;
-                }
-            }
-            let weight: Float = (0..D)
-                .map(|j| {
-                    if i & (1 << j) != 0 {
-                        weights[j]
-                    } else {
-                        1.0 - weights[j]
-                    }
-                })
-                .product();
-            sum = sum + self.get(&index).unwrap().clone() * weight;
+    /// Panics if `index` is outside the grid.
+    fn index(&self, index: CoordInt<D>) -> &T {
+        match self.get(&index) {
+            Some(value) => value,
+            None => panic!("{}", self.out_of_bounds(&index)),
         }
-        sum
     }
 }
 
-impl<const D: usize> Grid<Float, D> {
-    pub fn gradient(&self, coord: CoordInt<D>) -> Vector<D> {
-        let mut gradient = Vector::<D>::default();
-        let coord_val = self.get(&coord).expect("coord not in grid");
-
-        for i in 0..D {
-            let modify_coord = |add: bool| {
-                coord
-                    .0
-                    .iter()
-                    .zip((0..D).collect::<Vec<usize>>())
-                    .map(|(c, c_i)| {
-                        if c_i == i {
-                            if add {
-                                c + 1
-                            } else {
-                                c - 1
-                            }
-                        } else {
-                            *c
-                        }
-                    })
-                    .collect::<Vec<Int>>()
-                    .try_into()
-                    .unwrap()
-            };
-
-            gradient.0[i] = (self
-                .get(&CoordInt::<D>(modify_coord(true)))
-                .unwrap_or(coord_val)
-                - self
-                    .get(&CoordInt::<D>(modify_coord(false)))
-                    .unwrap_or(coord_val))
-                / (2.0 * self.delta);
+impl<T: Default + Clone, const D: usize> std::ops::IndexMut<CoordInt<D>> for Grid<T, D> {
+    fn index_mut(&mut self, index: CoordInt<D>) -> &mut T {
+        if !self.contains(&index) {
+            panic!("{}", self.out_of_bounds(&index));
         }
-        gradient
+        let index = self.flatten_index(&index);
+        &mut self.vec[index]
     }
 }
 
-impl<const D: usize> Grid<Vector<D>, D> {
-    pub fn divergence(&self, coord: CoordInt<D>) -> Float {
-        let mut divergence = 0.0;
-        let coord_val = self.get(&coord).expect("coord not in grid");
-        for i in 0..D {
-            let modify_coord = |add: bool| {
-                coord
-                    .0
-                    .iter()
-                    .zip((0..D).collect::<Vec<usize>>())
-                    .map(|(c, c_i)| {
-                        if c_i == i {
-                            if add {
-                                c + 1
-                            } else {
-                                c - 1
-                            }
-                        } else {
-                            *c
-                        }
-                    })
-                    .collect::<Vec<Int>>()
-                    .try_into()
-                    .unwrap()
-            };
-
-            divergence += (self
-                .get(&CoordInt::<D>(modify_coord(true)))
-                .unwrap_or(&coord_val)
-                .0[i]
-                - self
-                    .get(&CoordInt::<D>(modify_coord(false)))
-                    .unwrap_or(&coord_val)
-                    .0[i])
-                / (2.0 * self.delta);
+impl<T: GridValue, const D: usize> Grid<T, D> {
+    /// Interpolated value at `pos`; panics on an empty grid.
+    pub fn get_at(&self, pos: &Vector<D, T::Scalar>) -> T {
+        interpolate(self.size, self.delta, pos, |index| {
+            self.get(index).expect("grid is empty").clone()
+        })
+    }
+
+    /// Like `get_at`, but rejects non-finite positions and empty grids.
+    pub fn try_get_at(&self, pos: &Vector<D, T::Scalar>) -> Result<T, GridError> {
+        if !pos.all_finite() {
+            return Err(GridError::NonFinite);
         }
-        divergence
+        self.check_index(&CoordInt::default())?;
+        Ok(self.get_at(pos))
     }
 }
 
-impl<
-        T: Default
-            + Clone
-            + Mul<Float, Output = T>
-            + Sub<Output = T>
-            + Div<Float, Output = T>
-            + Add<Output = T>,
-        const D: usize,
-    > Grid<T, D>
+/// Multilinear interpolation between the 2^D cells surrounding `pos`.
+///
+/// Positions outside the grid are clamped to its edges; `sample` is only
+/// called with in-bounds coordinates.
+pub(crate) fn interpolate<T, F, const D: usize>(
+    size: CoordInt<D>,
+    delta: Float,
+    pos: &Vector<D, T::Scalar>,
+    sample: F,
+) -> T
 where
-    for<'a> &'a T: Add<Output = T>,
+    T: GridValue,
+    F: Fn(&CoordInt<D>) -> T,
 {
-    // laplace
-    pub fn laplace(&self, coord: CoordInt<D>) -> T {
-        let mut acc = T::default();
-        let coord_val = self.get(&coord).expect("coord not in grid");
-        for i in 0..D {
-            let modify_coord = |add: Int| {
-                coord
-                    .0
-                    .iter()
-                    .zip((0..D).collect::<Vec<usize>>())
-                    .map(|(c, c_i)| if c_i == i { c + add } else { *c })
-                    .collect::<Vec<Int>>()
-                    .try_into()
-                    .unwrap()
-            };
-
-            acc = acc
-                + self
-                    .get(&CoordInt::<D>(modify_coord(1)))
-                    .unwrap_or(coord_val)
-                    .clone()
-                + self
-                    .get(&CoordInt::<D>(modify_coord(-1)))
-                    .unwrap_or(coord_val)
-                    .clone();
+    let delta = T::Scalar::from_f64(delta);
+    // interpolate D-dimensionally between the 2^D closest points
+    let mut index = CoordInt::<D>::default();
+    let mut weights = [T::Scalar::ZERO; D];
+    for (i, weight) in weights.iter_mut().enumerate() {
+        let coord = (pos.0[i] / delta)
+            .max(T::Scalar::ZERO)
+            .min(T::Scalar::from_f64(size.0[i] as f64 - 1.0));
+        let lower = (coord.floor().to_f64() as Int).min(size.0[i] - 2).max(0);
+
+        index.0[i] = lower;
+        *weight = coord - T::Scalar::from_f64(lower as f64);
+    }
+
+    let mut sum = T::default();
+    for i in 0..1 << D {
+        let mut index = index;
+        for j in 0..D {
+            if i & (1 << j) != 0 {
+                // single-cell axes have weight zero on the upper corner
+                index.0[j] = (index.0[j] + 1).min(size.0[j] - 1);
+            }
         }
-        (acc - coord_val.clone() * 2.0 * D as Float) / (self.delta * self.delta)
+        let weight: T::Scalar = (0..D)
+            .map(|j| {
+                if i & (1 << j) != 0 {
+                    weights[j]
+                } else {
+                    T::Scalar::ONE - weights[j]
+                }
+            })
+            .product();
+        sum = sum + sample(&index) * weight;
     }
+    sum
 }
 
-impl<
-        T: Default + Clone + Add<Output = T> + Mul<Float, Output = T> + Product<Float>,
-        const D: usize,
-    > Grid<T, D>
-{
-    pub fn advect(&self, velocity: &Grid<Vector<D>, D>, coord: CoordInt<D>, dt: Float) -> T {
-        let velocity = velocity.get(&coord).expect("coord not in grid");
-        let new_pos = Vector::from_coord_int(coord, self.delta) - velocity.clone() * dt;
-        self.get_at(&new_pos)
+impl<S: Scalar, const D: usize> Grid<S, D> {
+    /// Second-order first derivative along `axis`, see `derivative_with`.
+    pub fn derivative(&self, coord: CoordInt<D>, axis: usize) -> S {
+        self.derivative_with(coord, axis, Accuracy::Second)
+    }
+
+    /// Central difference of the given accuracy along `axis`, one-sided at
+    /// the walls.
+    pub fn derivative_with(&self, coord: CoordInt<D>, axis: usize, accuracy: Accuracy) -> S {
+        first_derivative(self, coord, axis, accuracy, |v: &S| *v)
+    }
+
+    pub fn gradient(&self, coord: CoordInt<D>) -> Vector<D, S> {
+        self.gradient_with(coord, Accuracy::Second)
+    }
+
+    pub fn gradient_with(&self, coord: CoordInt<D>, accuracy: Accuracy) -> Vector<D, S> {
+        Vector(std::array::from_fn(|axis| {
+            self.derivative_with(coord, axis, accuracy)
+        }))
+    }
+
+    /// Like `gradient`, but rejects coordinates outside the grid and
+    /// non-finite results.
+    pub fn try_gradient(&self, coord: CoordInt<D>) -> Result<Vector<D, S>, GridError> {
+        self.check_index(&coord)?;
+        finite(self.gradient(coord))
     }
 }
 
-impl<const D: usize> TryFrom<Vec<usize>> for CoordInt<D> {
-    type Error = &'static str;
+impl<S: Scalar, const D: usize> Grid<Vector<D, S>, D> {
+    /// Derivative of one vector component along `axis`.
+    pub fn component_derivative(&self, coord: CoordInt<D>, component: usize, axis: usize) -> S {
+        self.component_derivative_with(coord, component, axis, Accuracy::Second)
+    }
 
-    fn try_from(vec: Vec<usize>) -> Result<Self, Self::Error> {
-        if vec.len() == D {
-            let arr: [usize; D] = vec.try_into().map_err(|_| "Size mismatch")?;
-            let arr: [Int; D] = arr.map(|x| x as Int);
-            Ok(CoordInt(arr))
-        } else {
-            Err("Size mismatch")
-        }
+    pub fn component_derivative_with(
+        &self,
+        coord: CoordInt<D>,
+        component: usize,
+        axis: usize,
+        accuracy: Accuracy,
+    ) -> S {
+        first_derivative(self, coord, axis, accuracy, |v: &Vector<D, S>| {
+            v.0[component]
+        })
+    }
+
+    pub fn divergence(&self, coord: CoordInt<D>) -> S {
+        self.divergence_with(coord, Accuracy::Second)
+    }
+
+    pub fn divergence_with(&self, coord: CoordInt<D>, accuracy: Accuracy) -> S {
+        (0..D)
+            .map(|i| self.component_derivative_with(coord, i, i, accuracy))
+            .sum()
+    }
+
+    /// Like `divergence`, but rejects coordinates outside the grid and
+    /// non-finite results.
+    pub fn try_divergence(&self, coord: CoordInt<D>) -> Result<S, GridError> {
+        self.check_index(&coord)?;
+        finite(self.divergence(coord))
     }
 }
 
-impl<const D: usize> FromIterator<usize> for CoordInt<D> {
-    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
-        let vec: Vec<usize> = iter.into_iter().collect();
-        vec.try_into().expect("Size mismatch")
+impl<S: Scalar> Grid<Vector<2, S>, 2> {
+    /// Scalar vorticity `dv/dx - du/dy`.
+    pub fn curl(&self, coord: CoordInt<2>) -> S {
+        self.component_derivative(coord, 1, 0) - self.component_derivative(coord, 0, 1)
     }
 }
 
-pub struct GridIter<'b, T: Clone + Default, const D: usize> {
-    grid: &'b Grid<T, D>,
-    coord: CoordInt<D>,
-    start: bool
+impl<S: Scalar> Grid<Vector<3, S>, 3> {
+    pub fn curl(&self, coord: CoordInt<3>) -> Vector<3, S> {
+        Vector([
+            self.component_derivative(coord, 2, 1) - self.component_derivative(coord, 1, 2),
+            self.component_derivative(coord, 0, 2) - self.component_derivative(coord, 2, 0),
+            self.component_derivative(coord, 1, 0) - self.component_derivative(coord, 0, 1),
+        ])
+    }
 }
 
-impl<'a, T: Clone + Default, const D: usize> Iterator for GridIter<'a, T, D> {
-    type Item = (CoordInt<D>, &'a T);
+impl<T: GridValue, const D: usize> Grid<T, D> {
+    /// Sum of the second differences along every axis, falling back to the
+    /// centre value for neighbours outside the grid.
+    pub fn laplace(&self, coord: CoordInt<D>) -> T {
+        (0..D).fold(T::default(), |acc, axis| {
+            acc + Stencil::second_difference(axis).apply(self, coord)
+        })
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        println!("coord: {:?}", self.coord);
-        if !self.start {
-            self.start = true;
-            return self.grid.get(&self.coord).map(|r| (self.coord, r));
-        }
+    pub fn advect(
+        &self,
+        velocity: &Grid<Vector<D, T::Scalar>, D>,
+        coord: CoordInt<D>,
+        dt: T::Scalar,
+    ) -> T {
+        let velocity = velocity.get(&coord).expect("coord not in grid");
+        let new_pos =
+            Vector::from_coord_int(coord, T::Scalar::from_f64(self.delta)) - *velocity * dt;
+        self.get_at(&new_pos)
+    }
 
-        self.coord.0[D - 1] += 1;
-        for i in (0..D).rev() {
-            if self.coord.0[i] >= self.grid.size.0[i] {
-                if i == 0 {
-                    return None;
-                }
-                self.coord.0[i] = 0;
-                self.coord.0[i - 1] += 1;
-            }
+    /// Like `laplace`, but rejects coordinates outside the grid and
+    /// non-finite results.
+    pub fn try_laplace(&self, coord: CoordInt<D>) -> Result<T, GridError> {
+        self.check_index(&coord)?;
+        finite(self.laplace(coord))
+    }
+
+    /// Like `advect`, but checks that `velocity` matches this grid, that
+    /// `coord` is inside both and that the inputs and result are finite.
+    pub fn try_advect(
+        &self,
+        velocity: &Grid<Vector<D, T::Scalar>, D>,
+        coord: CoordInt<D>,
+        dt: T::Scalar,
+    ) -> Result<T, GridError> {
+        self.check_shape(velocity)?;
+        if !velocity.try_get(&coord)?.all_finite() || !dt.all_finite() {
+            return Err(GridError::NonFinite);
         }
-        self.grid.get(&self.coord).map(|r| (self.coord, r))
+        finite(self.advect(velocity, coord, dt))
     }
 }
 
-impl<'a, T: Clone + Default, const D: usize> IntoIterator for &'a Grid<T, D> {
-    type Item = (CoordInt<D>, &'a T);
-    type IntoIter = GridIter<'a, T, D>;
+fn finite<T: GridValue>(value: T) -> Result<T, GridError> {
+    if value.all_finite() {
+        Ok(value)
+    } else {
+        Err(GridError::NonFinite)
+    }
+}
 
-    fn into_iter(self) -> Self::IntoIter {
-        GridIter {
-            grid: self,
-            coord: CoordInt::<D>::default(),
-            start: false
+/// Panics if the iterator does not yield exactly `D` components that fit in
+/// `Int`; use `CoordInt::try_from_iter` to handle that as an error.
+impl<const D: usize> FromIterator<usize> for CoordInt<D> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        match CoordInt::try_from_iter(iter) {
+            Ok(coord) => coord,
+            Err(err) => panic!("{err}"),
         }
     }
-}
\ No newline at end of file
+}
+
+impl<const D: usize> TryFrom<Vec<usize>> for CoordInt<D> {
+    type Error = GridError;
+
+    fn try_from(vec: Vec<usize>) -> Result<Self, Self::Error> {
+        CoordInt::try_from_iter(vec)
+    }
+}
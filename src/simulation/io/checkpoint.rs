@@ -0,0 +1,431 @@
+//! Versioned binary checkpoints of a whole [`Simulation`].
+//!
+//! All numbers are little-endian. A checkpoint is laid out as
+//!
+//! ```text
+//! magic      b"NSHCKPT\0"
+//! version    u32
+//! dimension  u32                        D
+//! scalar     u32                        bytes per stored scalar, 4 or 8
+//! steps      u64
+//! time       f64
+//! params     dt, viscosity, diffusion: f64, solver: u8, iterations: u32
+//! faces      D × 2 × (tag: u8, velocity: D × f64), lower face first
+//! sections   count: u32, then per section
+//!              name: u16 length + UTF-8, kind: u8, delta: f64,
+//!              size: D × u32, data in row-major order
+//! checksum   u32                        CRC-32 of everything before it
+//! ```
+//!
+//! Sections hold the grids by name: `densities`, `velocities`, `pressure`,
+//! `obstacles` and one `fields/<name>` per extra field. Scalars are stored at
+//! the precision of the simulation that wrote them and converted on load.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::crc32;
+use crate::simulation::{
+    boundary::{Boundary, BoundaryConfig},
+    error::CheckpointError,
+    grid::{capacity, CoordInt, Grid, Int},
+    scalar::Scalar,
+    simulation::{Parameters, Simulation},
+    solver::PressureSolver,
+    vector::{Float, Vector},
+};
+
+const MAGIC: &[u8; 8] = b"NSHCKPT\0";
+/// Format version written by this build, the only one it reads.
+pub const VERSION: u32 = 1;
+
+const FIELD_PREFIX: &str = "fields/";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Scalar = 0,
+    Vector = 1,
+    Mask = 2,
+}
+
+impl<const D: usize, S: Scalar> Simulation<D, S> {
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_checkpoint(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Fails if the file is not a checkpoint of this format version and
+    /// dimension, or if its checksum does not match.
+    pub fn load_checkpoint(path: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        Self::read_checkpoint(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_checkpoint<W: Write>(&self, mut writer: W) -> Result<(), CheckpointError> {
+        let mut out = Encoder::<S>::new();
+        out.bytes(MAGIC);
+        out.u32(VERSION);
+        out.u32(D as u32);
+        out.u32(std::mem::size_of::<S>() as u32);
+        out.u64(self.steps);
+        out.f64(self.time);
+
+        let params = &self.params;
+        out.f64(params.dt.to_f64());
+        out.f64(params.viscosity.to_f64());
+        out.f64(params.diffusion.to_f64());
+        out.u8(match params.pressure_solver {
+            PressureSolver::Jacobi => 0,
+            PressureSolver::GaussSeidel => 1,
+        });
+        out.u32(params.solver_iterations);
+
+        for axis in 0..D {
+            for side in 0..2 {
+                let (tag, velocity) = match *self.boundary.face(axis, side) {
+                    Boundary::NoSlip => (0, Vector::default()),
+                    Boundary::FreeSlip => (1, Vector::default()),
+                    Boundary::Moving(velocity) => (2, velocity),
+                    Boundary::Inflow(velocity) => (3, velocity),
+                    Boundary::Outflow => (4, Vector::default()),
+                    Boundary::Periodic => (5, Vector::default()),
+                };
+                out.u8(tag);
+                for component in velocity.0 {
+                    out.f64(component.to_f64());
+                }
+            }
+        }
+
+        out.u32(4 + self.fields.len() as u32);
+        out.section("densities", Kind::Scalar, &self.densities, |out, &v| {
+            out.scalar(v)
+        });
+        out.section("velocities", Kind::Vector, &self.velocities, |out, v| {
+            v.0.iter().for_each(|&c| out.scalar(c))
+        });
+        out.section("pressure", Kind::Scalar, &self.pressure, |out, &v| {
+            out.scalar(v)
+        });
+        out.section(
+            "obstacles",
+            Kind::Mask,
+            &self.boundary.obstacles,
+            |out, &v| out.u8(v as u8),
+        );
+        for (name, field) in &self.fields {
+            let name = format!("{FIELD_PREFIX}{name}");
+            out.section(&name, Kind::Scalar, field, |out, &v| out.scalar(v));
+        }
+
+        let checksum = crc32::checksum(&out.buf);
+        out.u32(checksum);
+        writer.write_all(&out.buf)?;
+        Ok(())
+    }
+
+    pub fn read_checkpoint<R: Read>(mut reader: R) -> Result<Self, CheckpointError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        let mut input = Decoder::new(&buf);
+        if input.take(MAGIC.len())? != MAGIC {
+            return Err(CheckpointError::BadMagic);
+        }
+        let version = input.u32()?;
+        if version != VERSION {
+            return Err(CheckpointError::UnsupportedVersion {
+                found: version,
+                supported: VERSION,
+            });
+        }
+
+        let body = buf
+            .len()
+            .checked_sub(4)
+            .ok_or_else(|| corrupt("truncated"))?;
+        let stored = u32::from_le_bytes(buf[body..].try_into().unwrap());
+        let computed = crc32::checksum(&buf[..body]);
+        if stored != computed {
+            return Err(CheckpointError::ChecksumMismatch { stored, computed });
+        }
+        let mut input = Decoder::new(&buf[..body]);
+        input.take(MAGIC.len() + 4)?;
+
+        let dimension = input.u32()? as usize;
+        if dimension != D {
+            return Err(CheckpointError::DimensionMismatch {
+                expected: D,
+                found: dimension,
+            });
+        }
+        input.width = match input.u32()? {
+            width @ (4 | 8) => width as usize,
+            width => return Err(corrupt(format!("unsupported scalar width {width}"))),
+        };
+        let steps = input.u64()?;
+        let time = input.f64()?;
+
+        let params = Parameters {
+            dt: S::from_f64(input.f64()?),
+            viscosity: S::from_f64(input.f64()?),
+            diffusion: S::from_f64(input.f64()?),
+            pressure_solver: match input.u8()? {
+                0 => PressureSolver::Jacobi,
+                1 => PressureSolver::GaussSeidel,
+                tag => return Err(corrupt(format!("unknown pressure solver {tag}"))),
+            },
+            solver_iterations: input.u32()?,
+        };
+
+        let mut faces = [[Boundary::NoSlip; 2]; D];
+        for (axis, pair) in faces.iter_mut().enumerate() {
+            for face in pair.iter_mut() {
+                let tag = input.u8()?;
+                let mut velocity = Vector::<D, S>::default();
+                for component in velocity.0.iter_mut() {
+                    *component = S::from_f64(input.f64()?);
+                }
+                *face = match tag {
+                    0 => Boundary::NoSlip,
+                    1 => Boundary::FreeSlip,
+                    2 => Boundary::Moving(velocity),
+                    3 => Boundary::Inflow(velocity),
+                    4 => Boundary::Outflow,
+                    5 => Boundary::Periodic,
+                    tag => return Err(corrupt(format!("unknown boundary {tag} on axis {axis}"))),
+                };
+            }
+            if (pair[0] == Boundary::Periodic) != (pair[1] == Boundary::Periodic) {
+                return Err(corrupt(format!("axis {axis} is periodic on one face only")));
+            }
+        }
+
+        let mut densities = None;
+        let mut velocities = None;
+        let mut pressure = None;
+        let mut obstacles = None;
+        let mut fields = std::collections::BTreeMap::new();
+        for _ in 0..input.u32()? {
+            let len = input.u16()? as usize;
+            let name = std::str::from_utf8(input.take(len)?)
+                .map_err(|_| corrupt("section name is not UTF-8"))?
+                .to_string();
+            let kind = match input.u8()? {
+                0 => Kind::Scalar,
+                1 => Kind::Vector,
+                2 => Kind::Mask,
+                kind => return Err(corrupt(format!("section {name:?} has unknown kind {kind}"))),
+            };
+            let expected = match name.as_str() {
+                "velocities" => Kind::Vector,
+                "obstacles" => Kind::Mask,
+                _ => Kind::Scalar,
+            };
+            if kind != expected {
+                return Err(corrupt(format!("section {name:?} has the wrong kind")));
+            }
+
+            match name.as_str() {
+                "densities" => densities = Some(input.grid(Decoder::scalar)?),
+                "pressure" => pressure = Some(input.grid(Decoder::scalar)?),
+                "velocities" => {
+                    velocities = Some(input.grid(|input| {
+                        let mut v = Vector::<D, S>::default();
+                        for component in v.0.iter_mut() {
+                            *component = input.scalar()?;
+                        }
+                        Ok(v)
+                    })?)
+                }
+                "obstacles" => obstacles = Some(input.grid(|input| Ok(input.u8()? != 0))?),
+                _ => match name.strip_prefix(FIELD_PREFIX) {
+                    Some(field) => {
+                        fields.insert(field.to_string(), input.grid(Decoder::scalar)?);
+                    }
+                    None => return Err(corrupt(format!("unknown section {name:?}"))),
+                },
+            }
+        }
+        if !input.is_empty() {
+            return Err(corrupt("trailing data after the last section"));
+        }
+
+        let missing = |name: &str| corrupt(format!("missing section {name:?}"));
+        let sim = Simulation {
+            densities: densities.ok_or_else(|| missing("densities"))?,
+            velocities: velocities.ok_or_else(|| missing("velocities"))?,
+            pressure: pressure.ok_or_else(|| missing("pressure"))?,
+            fields,
+            params,
+            boundary: BoundaryConfig {
+                faces,
+                obstacles: obstacles.ok_or_else(|| missing("obstacles"))?,
+            },
+            steps,
+            time,
+        };
+
+        let size = sim.size();
+        let mut sizes = [sim.velocities.size(), sim.pressure.size()]
+            .into_iter()
+            .chain([sim.boundary.obstacles.size()])
+            .chain(sim.fields.values().map(Grid::size));
+        if sizes.any(|other| other != size) {
+            return Err(corrupt("sections differ in size"));
+        }
+        Ok(sim)
+    }
+}
+
+fn corrupt(reason: impl Into<String>) -> CheckpointError {
+    CheckpointError::Corrupt(reason.into())
+}
+
+struct Encoder<S> {
+    buf: Vec<u8>,
+    scalar: std::marker::PhantomData<S>,
+}
+
+impl<S: Scalar> Encoder<S> {
+    fn new() -> Self {
+        Encoder {
+            buf: Vec::new(),
+            scalar: std::marker::PhantomData,
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn scalar(&mut self, value: S) {
+        if std::mem::size_of::<S>() == 4 {
+            self.bytes(&(value.to_f64() as f32).to_le_bytes());
+        } else {
+            self.f64(value.to_f64());
+        }
+    }
+
+    fn section<T: Default + Clone, const D: usize>(
+        &mut self,
+        name: &str,
+        kind: Kind,
+        grid: &Grid<T, D>,
+        mut value: impl FnMut(&mut Self, &T),
+    ) {
+        self.u16(name.len() as u16);
+        self.bytes(name.as_bytes());
+        self.u8(kind as u8);
+        self.f64(grid.delta());
+        for n in grid.size().0 {
+            self.u32(n as u32);
+        }
+        for v in grid.as_slice() {
+            value(self, v);
+        }
+    }
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+    /// Bytes per stored scalar.
+    width: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Decoder { buf, width: 8 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CheckpointError> {
+        if self.buf.len() < len {
+            return Err(corrupt("truncated"));
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CheckpointError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, CheckpointError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, CheckpointError> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, CheckpointError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, CheckpointError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Result<f64, CheckpointError> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    fn scalar<S: Scalar>(&mut self) -> Result<S, CheckpointError> {
+        let value = if self.width == 4 {
+            self.array().map(f32::from_le_bytes)? as f64
+        } else {
+            self.f64()?
+        };
+        Ok(S::from_f64(value))
+    }
+
+    /// Reads the spacing, size and data of a section.
+    fn grid<T: Default + Clone, const D: usize>(
+        &mut self,
+        mut value: impl FnMut(&mut Self) -> Result<T, CheckpointError>,
+    ) -> Result<Grid<T, D>, CheckpointError> {
+        let delta: Float = self.f64()?;
+        let mut size = CoordInt::<D>::default();
+        for n in size.0.iter_mut() {
+            *n = Int::try_from(self.u32()?).map_err(|_| corrupt("grid size overflows"))?;
+        }
+        let cells = capacity(&size).ok_or_else(|| corrupt("grid size overflows"))?;
+        // reject sizes the remaining bytes cannot hold before allocating
+        if cells > self.buf.len() {
+            return Err(corrupt("truncated"));
+        }
+        let data = (0..cells)
+            .map(|_| value(self))
+            .collect::<Result<Vec<_>, _>>()?;
+        Grid::from_vec(size, delta, data).map_err(|error| corrupt(error.to_string()))
+    }
+}
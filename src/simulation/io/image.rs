@@ -0,0 +1,221 @@
+//! 8-bit RGBA images, for rendering fields to files and for loading initial
+//! conditions from PNG, PGM or PPM files.
+//!
+//! Grids follow the window renderer: axis 0 runs left to right across the
+//! image and axis 1 top to bottom. Images are resampled bilinearly to the
+//! grid size, sampling at cell centres.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::{png, pnm, zlib::Compression};
+use crate::simulation::{
+    error::ImageError,
+    grid::{CoordInt, Grid},
+    scalar::Scalar,
+    simulation::Simulation,
+    vector::{Float, Vector},
+};
+
+/// The part of a pixel mapped onto a grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    /// Rec. 709 luma of the colour, ignoring alpha.
+    #[default]
+    Luminance,
+}
+
+impl Channel {
+    /// The value of this channel in `[0, 1]`.
+    pub fn value(&self, pixel: [u8; 4]) -> Float {
+        let [r, g, b, a] = pixel.map(|c| c as Float / 255.0);
+        match self {
+            Channel::Red => r,
+            Channel::Green => g,
+            Channel::Blue => b,
+            Channel::Alpha => a,
+            Channel::Luminance => 0.2126 * r + 0.7152 * g + 0.0722 * b,
+        }
+    }
+}
+
+/// An RGBA image stored row by row from the top left.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    /// A transparent black image.
+    pub fn new(width: usize, height: usize) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![[0; 4]; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[[u8; 4]] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    /// Panics if `(x, y)` lies outside the image.
+    pub fn set(&mut self, x: usize, y: usize, pixel: [u8; 4]) {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel ({x}, {y}) out of bounds"
+        );
+        self.pixels[y * self.width + x] = pixel;
+    }
+
+    /// Enlarges every pixel to a `factor`×`factor` block.
+    pub fn scaled(&self, factor: usize) -> Image {
+        let mut out = Image::new(self.width * factor, self.height * factor);
+        for y in 0..out.height {
+            for x in 0..out.width {
+                out.pixels[y * out.width + x] = self.pixels[y / factor * self.width + x / factor];
+            }
+        }
+        out
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>, compression: Compression) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_png(&mut writer, compression)?;
+        writer.flush()
+    }
+
+    pub fn write_png<W: Write>(&self, mut writer: W, compression: Compression) -> io::Result<()> {
+        writer.write_all(&png::encode(self, compression))
+    }
+
+    /// Reads a PNG, PGM or PPM file, telling them apart by their contents.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImageError> {
+        Self::read(File::open(path)?)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, ImageError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if png::is_png(&bytes) {
+            png::decode(&bytes)
+        } else if pnm::is_pnm(&bytes) {
+            pnm::decode(&bytes)
+        } else {
+            Err(ImageError::Unsupported(
+                "not a PNG, PGM or PPM file".to_string(),
+            ))
+        }
+    }
+
+    /// Bilinearly interpolates `channel` at the centre of cell `(i, j)` of a
+    /// grid of `size` cells stretched over the image.
+    fn sample(&self, channel: Channel, size: [usize; 2], i: usize, j: usize) -> Float {
+        if self.width == 0 || self.height == 0 {
+            return 0.0;
+        }
+        let axis = |index: usize, cells: usize, pixels: usize| {
+            let x = (index as Float + 0.5) * pixels as Float / cells as Float - 0.5;
+            let x = x.clamp(0.0, (pixels - 1) as Float);
+            let lo = x.floor() as usize;
+            (lo, (lo + 1).min(pixels - 1), x - lo as Float)
+        };
+        let (x0, x1, wx) = axis(i, size[0], self.width);
+        let (y0, y1, wy) = axis(j, size[1], self.height);
+        let at = |x: usize, y: usize| channel.value(self.pixels[y * self.width + x]);
+        let top = at(x0, y0) * (1.0 - wx) + at(x1, y0) * wx;
+        let bottom = at(x0, y1) * (1.0 - wx) + at(x1, y1) * wx;
+        top * (1.0 - wy) + bottom * wy
+    }
+
+    /// Resamples `channel` to a grid of `size` cells, with values in `[0, 1]`.
+    pub fn to_grid<S: Scalar>(
+        &self,
+        channel: Channel,
+        size: CoordInt<2>,
+        delta: Float,
+    ) -> Grid<S, 2> {
+        let cells = size.0.map(|n| n.max(0) as usize);
+        let mut grid = Grid::new(size, delta);
+        for (coord, value) in grid.indexed_iter_mut() {
+            let [i, j] = coord.0.map(|c| c as usize);
+            *value = S::from_f64(self.sample(channel, cells, i, j));
+        }
+        grid
+    }
+
+    /// Marks the cells where the resampled `channel` exceeds `threshold`.
+    pub fn to_mask(
+        &self,
+        channel: Channel,
+        threshold: Float,
+        size: CoordInt<2>,
+        delta: Float,
+    ) -> Grid<bool, 2> {
+        self.to_grid::<Float>(channel, size, delta)
+            .map(|&v| v > threshold)
+    }
+
+    /// Resamples the red and green channels to velocities along axes 0 and 1,
+    /// in steps of `max_speed / 127` from mid-grey, 128, at rest: 255 maps to
+    /// `max_speed`, and both 1 and 0 to `-max_speed`.
+    pub fn to_velocity<S: Scalar>(
+        &self,
+        max_speed: Float,
+        size: CoordInt<2>,
+        delta: Float,
+    ) -> Grid<Vector<2, S>, 2> {
+        let u = self.to_grid::<Float>(Channel::Red, size, delta);
+        let v = self.to_grid::<Float>(Channel::Green, size, delta);
+        let speed = |c: Float| S::from_f64(((c * 255.0 - 128.0) / 127.0).max(-1.0) * max_speed);
+        let mut grid = Grid::new(size, delta);
+        for ((velocity, &u), &v) in grid
+            .as_mut_slice()
+            .iter_mut()
+            .zip(u.as_slice())
+            .zip(v.as_slice())
+        {
+            *velocity = Vector([speed(u), speed(v)]);
+        }
+        grid
+    }
+}
+
+impl<S: Scalar> Simulation<2, S> {
+    /// Sets the densities to the luminance of `image`.
+    pub fn set_densities_from_image(&mut self, image: &Image) {
+        self.densities = image.to_grid(Channel::Luminance, self.size(), self.delta());
+    }
+
+    /// Sets the velocities from the red and green channels of `image`, as in
+    /// [`Image::to_velocity`].
+    pub fn set_velocities_from_image(&mut self, image: &Image, max_speed: Float) {
+        self.velocities = image.to_velocity(max_speed, self.size(), self.delta());
+    }
+
+    /// Makes the cells where `channel` exceeds `threshold` solid.
+    pub fn set_obstacles_from_image(&mut self, image: &Image, channel: Channel, threshold: Float) {
+        self.boundary.obstacles = image.to_mask(channel, threshold, self.size(), self.delta());
+    }
+}
@@ -0,0 +1,12 @@
+//! Reading and writing simulation state to files.
+
+pub mod checkpoint;
+pub(crate) mod crc32;
+pub mod image;
+pub mod npy;
+mod png;
+mod pnm;
+pub mod render;
+pub(crate) mod toml;
+pub mod vtk;
+pub mod zlib;
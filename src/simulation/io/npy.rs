@@ -0,0 +1,549 @@
+//! NumPy `.npy` arrays and `.npz` archives.
+//!
+//! A `Grid<T, D>` is stored as a C-ordered array of shape `size`, with a
+//! trailing axis of length `D` for vector values. `.npz` archives are plain
+//! (stored, uncompressed) zip files of `.npy` members, as written by
+//! `numpy.savez`; metadata such as the grid spacing goes in as 0-d arrays.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::crc32;
+use crate::simulation::{
+    error::NpyError,
+    grid::{capacity, CoordInt, Grid, Int},
+    iter::CoordRange,
+    scalar::Scalar,
+    vector::{Float, Vector},
+    view::GridRead,
+};
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// A grid value NumPy has a dtype for.
+pub trait NpyValue: Default + Clone {
+    /// NumPy type string of one component, such as `<f8`.
+    const DESCR: &'static str;
+    /// Bytes per component.
+    const WIDTH: usize;
+    /// Components per value; more than one adds a trailing array axis.
+    const COMPONENTS: usize = 1;
+
+    fn write_le(&self, out: &mut Vec<u8>);
+    /// Reads a value from exactly `WIDTH * COMPONENTS` bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_npy_value {
+    ($t:ty, $descr:literal) => {
+        impl NpyValue for $t {
+            const DESCR: &'static str = $descr;
+            const WIDTH: usize = std::mem::size_of::<$t>();
+
+            fn write_le(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                <$t>::from_le_bytes(bytes.try_into().unwrap())
+            }
+        }
+    };
+}
+
+impl_npy_value!(f32, "<f4");
+impl_npy_value!(f64, "<f8");
+impl_npy_value!(i32, "<i4");
+impl_npy_value!(u8, "|u1");
+
+impl NpyValue for bool {
+    const DESCR: &'static str = "|b1";
+    const WIDTH: usize = 1;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+}
+
+impl<const D: usize, S: Scalar + NpyValue> NpyValue for Vector<D, S> {
+    const DESCR: &'static str = S::DESCR;
+    const WIDTH: usize = S::WIDTH;
+    const COMPONENTS: usize = D;
+
+    fn write_le(&self, out: &mut Vec<u8>) {
+        self.0.iter().for_each(|c| c.write_le(out));
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        Vector(std::array::from_fn(|i| {
+            S::read_le(&bytes[i * S::WIDTH..(i + 1) * S::WIDTH])
+        }))
+    }
+}
+
+/// A parsed `.npy` file.
+struct Array {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+    data: Vec<u8>,
+}
+
+impl Array {
+    fn parse(bytes: &[u8]) -> Result<Self, NpyError> {
+        if bytes.len() < 10 || &bytes[..6] != MAGIC {
+            return Err(format_error("missing magic string"));
+        }
+        let (header_len, start) = match bytes[6] {
+            1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+            2 | 3 if bytes.len() >= 12 => {
+                let len = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+                (len as usize, 12)
+            }
+            major => return Err(format_error(format!("unsupported version {major}"))),
+        };
+        let header = bytes
+            .get(start..index(start, header_len as u64)?)
+            .ok_or_else(|| format_error("truncated header"))?;
+        let header = std::str::from_utf8(header).map_err(|_| format_error("header is not text"))?;
+
+        let descr = dict_value(header, "descr")?
+            .trim_matches(|c| c == '\'' || c == '"')
+            .to_string();
+        let fortran_order = match dict_value(header, "fortran_order")? {
+            "True" => true,
+            "False" => false,
+            other => return Err(format_error(format!("bad fortran_order {other}"))),
+        };
+        let shape = dict_value(header, "shape")?
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| format_error(format!("bad shape entry {s}")))
+            })
+            .collect::<Result<Vec<usize>, _>>()?;
+
+        Ok(Array {
+            descr,
+            fortran_order,
+            shape,
+            data: bytes[start + header_len..].to_vec(),
+        })
+    }
+
+    fn to_grid<T: NpyValue, const D: usize>(&self, delta: Float) -> Result<Grid<T, D>, NpyError> {
+        // `|` (not applicable) and `=` (native, little-endian here) match `<`
+        let base = |descr: &str| descr.trim_start_matches(['<', '|', '=']).to_string();
+        if base(&self.descr) != base(T::DESCR) || self.descr.starts_with('>') {
+            return Err(NpyError::Dtype {
+                expected: T::DESCR.to_string(),
+                found: self.descr.clone(),
+            });
+        }
+        if self.fortran_order {
+            return Err(NpyError::FortranOrder);
+        }
+        let dims = D + (T::COMPONENTS > 1) as usize;
+        if self.shape.len() != dims || (dims > D && self.shape[D] != T::COMPONENTS) {
+            return Err(NpyError::Shape {
+                dimensions: D,
+                components: T::COMPONENTS,
+                found: self.shape.clone(),
+            });
+        }
+
+        let mut size = CoordInt::<D>::default();
+        for (n, &len) in size.0.iter_mut().zip(&self.shape) {
+            *n = Int::try_from(len).map_err(|_| format_error("shape overflows"))?;
+        }
+        let value_width = T::WIDTH * T::COMPONENTS;
+        let bytes = capacity(&size)
+            .and_then(|cells| cells.checked_mul(value_width))
+            .ok_or_else(|| format_error("shape overflows"))?;
+        if self.data.len() != bytes {
+            return Err(format_error(format!(
+                "expected {bytes} data bytes, found {}",
+                self.data.len()
+            )));
+        }
+        let values = self
+            .data
+            .chunks_exact(value_width)
+            .map(T::read_le)
+            .collect();
+        Grid::from_vec(size, delta, values).map_err(|error| format_error(error.to_string()))
+    }
+
+    /// The single value of a 0-d or one-element numeric array.
+    fn scalar(&self) -> Option<f64> {
+        if self.shape.iter().any(|&n| n != 1) {
+            return None;
+        }
+        let bytes = &self.data;
+        Some(match self.descr.as_str() {
+            "<f8" => f64::read_le(bytes.get(..8)?),
+            "<f4" => f32::read_le(bytes.get(..4)?) as f64,
+            "<i4" => i32::read_le(bytes.get(..4)?) as f64,
+            "<i8" => i64::from_le_bytes(bytes.get(..8)?.try_into().ok()?) as f64,
+            "|u1" | "|b1" => *bytes.first()? as f64,
+            _ => return None,
+        })
+    }
+}
+
+/// The text of `key`'s value in a header dict such as
+/// `{'descr': '<f8', 'fortran_order': False, 'shape': (3, 4), }`.
+fn dict_value<'h>(header: &'h str, key: &str) -> Result<&'h str, NpyError> {
+    let missing = || format_error(format!("header has no {key:?}"));
+    let start = header
+        .find(&format!("'{key}'"))
+        .or_else(|| header.find(&format!("\"{key}\"")))
+        .ok_or_else(missing)?;
+    let rest = header[start + key.len() + 2..].trim_start();
+    let rest = rest.strip_prefix(':').ok_or_else(missing)?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')').map(|i| i + 1)
+    } else {
+        rest.find([',', '}'])
+    };
+    Ok(rest[..end.ok_or_else(missing)?].trim())
+}
+
+fn format_error(reason: impl Into<String>) -> NpyError {
+    NpyError::Format(reason.into())
+}
+
+/// `base + len` as an index into the file, failing where it would overflow.
+fn index(base: usize, len: u64) -> Result<usize, NpyError> {
+    usize::try_from(len)
+        .ok()
+        .and_then(|len| base.checked_add(len))
+        .ok_or_else(|| format_error("offset out of range"))
+}
+
+/// Encodes an array header plus `data` as a `.npy` file.
+fn encode(descr: &str, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    let shape = match shape {
+        [n] => format!("({n},)"),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}");
+    // pad with spaces so the data starts on a 64-byte boundary
+    let (version, prefix) = if header.len() + 11 <= u16::MAX as usize {
+        (1, 10)
+    } else {
+        (2, 12)
+    };
+    let padding = (64 - (prefix + header.len() + 1) % 64) % 64;
+    header.extend(std::iter::repeat_n(' ', padding));
+    header.push('\n');
+
+    let mut out = Vec::with_capacity(prefix + header.len() + data.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&[version, 0]);
+    if version == 1 {
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    } else {
+        out.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    }
+    out.extend_from_slice(header.as_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+/// The cells of `grid` in C order, the last axis fastest, as a `.npy` file.
+fn encode_grid<T: NpyValue, const D: usize>(grid: &impl GridRead<T, D>) -> Vec<u8> {
+    let mut shape = grid.size().0.map(|n| n.max(0) as usize).to_vec();
+    if T::COMPONENTS > 1 {
+        shape.push(T::COMPONENTS);
+    }
+    let cells = CoordRange::new(CoordInt::default(), grid.size());
+    let mut data = Vec::with_capacity(cells.len() * T::WIDTH * T::COMPONENTS);
+    for coord in cells {
+        grid.get(&coord).expect("coord in grid").write_le(&mut data);
+    }
+    encode(T::DESCR, &shape, &data)
+}
+
+/// Saves a grid, view or other field as a `.npy` file.
+pub fn save_npy<T: NpyValue, const D: usize>(
+    grid: &impl GridRead<T, D>,
+    path: impl AsRef<Path>,
+) -> Result<(), NpyError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_npy(grid, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_npy<T: NpyValue, const D: usize, W: Write>(
+    grid: &impl GridRead<T, D>,
+    mut writer: W,
+) -> Result<(), NpyError> {
+    writer.write_all(&encode_grid(grid))?;
+    Ok(())
+}
+
+impl<T: NpyValue, const D: usize> Grid<T, D> {
+    pub fn save_npy(&self, path: impl AsRef<Path>) -> Result<(), NpyError> {
+        save_npy(self, path)
+    }
+
+    pub fn write_npy<W: Write>(&self, writer: W) -> Result<(), NpyError> {
+        write_npy(self, writer)
+    }
+
+    /// Loads an array of shape `size` (plus `[D]` for vectors) and the dtype
+    /// of `T`. `.npy` files carry no spacing, so it is passed as `delta`.
+    pub fn load_npy(path: impl AsRef<Path>, delta: Float) -> Result<Self, NpyError> {
+        Self::read_npy(BufReader::new(File::open(path)?), delta)
+    }
+
+    pub fn read_npy<R: Read>(mut reader: R, delta: Float) -> Result<Self, NpyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Array::parse(&bytes)?.to_grid(delta)
+    }
+}
+
+/// Writes an `.npz` archive member by member.
+pub struct NpzWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    /// Central directory records of the members written so far.
+    directory: Vec<u8>,
+    entries: u16,
+}
+
+impl NpzWriter<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, NpyError> {
+        Ok(NpzWriter::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> NpzWriter<W> {
+    pub fn new(writer: W) -> Self {
+        NpzWriter {
+            writer,
+            offset: 0,
+            directory: Vec::new(),
+            entries: 0,
+        }
+    }
+
+    /// Adds `grid`, which may also be a view, as the array `name`.
+    pub fn add_grid<T: NpyValue, const D: usize>(
+        &mut self,
+        name: &str,
+        grid: &impl GridRead<T, D>,
+    ) -> Result<(), NpyError> {
+        self.add_file(name, &encode_grid(grid))
+    }
+
+    /// Adds `value` as the 0-d `float64` array `name`.
+    pub fn add_metadata(&mut self, name: &str, value: f64) -> Result<(), NpyError> {
+        self.add_file(name, &encode(f64::DESCR, &[], &value.to_le_bytes()))
+    }
+
+    fn add_file(&mut self, name: &str, data: &[u8]) -> Result<(), NpyError> {
+        let name = format!("{name}.npy");
+        let too_large = || format_error("archive exceeds the 4 GiB zip limit");
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(self.offset).map_err(|_| too_large())?;
+        self.entries = self.entries.checked_add(1).ok_or_else(too_large)?;
+        let crc = crc32::checksum(data);
+
+        // local file header: version 2.0, no flags, stored, 1980-01-01 00:00
+        let mut local = Vec::with_capacity(30 + name.len());
+        local.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        for field in [20u16, 0, 0, 0, 0x21] {
+            local.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            local.extend_from_slice(&field.to_le_bytes());
+        }
+        local.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        local.extend_from_slice(&0u16.to_le_bytes());
+        local.extend_from_slice(name.as_bytes());
+
+        let central = &mut self.directory;
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        for field in [20u16, 20, 0, 0, 0, 0x21] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        // name, extra and comment lengths, disk, internal and external attributes
+        for field in [name.len() as u16, 0, 0, 0, 0] {
+            central.extend_from_slice(&field.to_le_bytes());
+        }
+        central.extend_from_slice(&0u32.to_le_bytes());
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+
+        self.writer.write_all(&local)?;
+        self.writer.write_all(data)?;
+        self.offset += (local.len() + data.len()) as u64;
+        Ok(())
+    }
+
+    /// Writes the zip directory and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, NpyError> {
+        let offset = u32::try_from(self.offset)
+            .map_err(|_| format_error("archive exceeds the 4 GiB zip limit"))?;
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        for field in [0u16, 0, self.entries, self.entries] {
+            end.extend_from_slice(&field.to_le_bytes());
+        }
+        end.extend_from_slice(&(self.directory.len() as u32).to_le_bytes());
+        end.extend_from_slice(&offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes());
+
+        self.writer.write_all(&self.directory)?;
+        self.writer.write_all(&end)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// The arrays of an `.npz` archive, read into memory.
+pub struct Npz {
+    arrays: BTreeMap<String, Array>,
+}
+
+impl Npz {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, NpyError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads an archive of uncompressed members, as written by `numpy.savez`;
+    /// `numpy.savez_compressed` archives are rejected.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, NpyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let u16_at = |at: usize| -> Result<usize, NpyError> {
+            let field = bytes
+                .get(at..index(at, 2)?)
+                .ok_or_else(|| format_error("truncated zip"))?;
+            Ok(u16::from_le_bytes(field.try_into().unwrap()) as usize)
+        };
+        let u32_at = |at: usize| -> Result<u64, NpyError> {
+            let field = bytes
+                .get(at..index(at, 4)?)
+                .ok_or_else(|| format_error("truncated zip"))?;
+            Ok(u32::from_le_bytes(field.try_into().unwrap()) as u64)
+        };
+        let u64_at = |at: usize| -> Result<u64, NpyError> {
+            let field = bytes
+                .get(at..index(at, 8)?)
+                .ok_or_else(|| format_error("truncated zip"))?;
+            Ok(u64::from_le_bytes(field.try_into().unwrap()))
+        };
+
+        let end = (0..bytes.len().saturating_sub(21))
+            .rev()
+            .find(|&at| bytes[at..at + 4] == 0x0605_4b50u32.to_le_bytes())
+            .ok_or_else(|| format_error("not a zip archive"))?;
+        let entries = u16_at(end + 10)?;
+        let mut at = u32_at(end + 16)? as usize;
+
+        let mut arrays = BTreeMap::new();
+        for _ in 0..entries {
+            if u32_at(at)? != 0x0201_4b50 {
+                return Err(format_error("bad zip directory entry"));
+            }
+            let method = u16_at(at + 10)?;
+            let crc = u32_at(at + 16)? as u32;
+            let mut compressed = u32_at(at + 20)?;
+            let mut uncompressed = u32_at(at + 24)?;
+            let name_len = u16_at(at + 28)?;
+            let extra_len = u16_at(at + 30)?;
+            let comment_len = u16_at(at + 32)?;
+            let mut offset = u32_at(at + 42)?;
+            let name = bytes
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(|| format_error("truncated zip"))?;
+            let name = String::from_utf8_lossy(name).into_owned();
+
+            // zip64 sizes and offsets replace the 32-bit fields set to 0xffffffff
+            let mut extra = at + 46 + name_len;
+            let extra_end = extra + extra_len;
+            while extra + 4 <= extra_end {
+                let (id, len) = (u16_at(extra)?, u16_at(extra + 2)?);
+                if id == 1 {
+                    let mut field = extra + 4;
+                    for value in [&mut uncompressed, &mut compressed, &mut offset] {
+                        if *value == 0xffff_ffff {
+                            *value = u64_at(field)?;
+                            field += 8;
+                        }
+                    }
+                }
+                extra += 4 + len;
+            }
+            at = extra_end + comment_len;
+
+            if method != 0 || compressed != uncompressed {
+                return Err(format_error(format!("{name} is compressed")));
+            }
+            let local = index(0, offset)?;
+            if u32_at(local)? != 0x0403_4b50 {
+                return Err(format_error(format!("bad local header for {name}")));
+            }
+            let start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+            let data = bytes
+                .get(start..index(start, compressed)?)
+                .ok_or_else(|| format_error(format!("{name} is truncated")))?;
+            if crc32::checksum(data) != crc {
+                return Err(format_error(format!("checksum mismatch in {name}")));
+            }
+            let key = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+            arrays.insert(key, Array::parse(data)?);
+        }
+        Ok(Npz { arrays })
+    }
+
+    /// Array names, without the `.npy` extension.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.arrays.keys().map(String::as_str)
+    }
+
+    /// The array `name` as a grid with spacing `delta`.
+    pub fn grid<T: NpyValue, const D: usize>(
+        &self,
+        name: &str,
+        delta: Float,
+    ) -> Result<Grid<T, D>, NpyError> {
+        self.arrays
+            .get(name)
+            .ok_or_else(|| NpyError::Missing(name.to_string()))?
+            .to_grid(delta)
+    }
+
+    /// The value of a single-element numeric array, such as one written by
+    /// `NpzWriter::add_metadata`.
+    pub fn metadata(&self, name: &str) -> Option<f64> {
+        self.arrays.get(name)?.scalar()
+    }
+}
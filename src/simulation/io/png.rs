@@ -0,0 +1,263 @@
+//! PNG encoding of 8-bit RGB and RGBA images, and decoding of
+//! non-interlaced PNGs of any colour type and bit depth.
+
+use crate::simulation::error::ImageError;
+
+use super::{
+    crc32::{self, Crc32},
+    image::Image,
+    zlib::{self, Compression},
+};
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&crc.finish().to_be_bytes());
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reverses `filter` on `row` in place.
+fn unfilter_row(filter: u8, row: &mut [u8], above: &[u8], bpp: usize) -> Result<(), ImageError> {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = above[i];
+        let c = if i >= bpp { above[i - bpp] } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return Err(format_error(format!("unknown filter type {filter}"))),
+        };
+        row[i] = row[i].wrapping_add(predictor);
+    }
+    Ok(())
+}
+
+/// Appends `row` filtered with `filter` (0 none, 1 sub, 2 up, 3 average,
+/// 4 Paeth), given the unfiltered row above and `bpp` bytes per pixel.
+fn filter_row(out: &mut Vec<u8>, filter: u8, row: &[u8], above: &[u8], bpp: usize) {
+    out.push(filter);
+    for (i, &x) in row.iter().enumerate() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = above[i];
+        let c = if i >= bpp { above[i - bpp] } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            _ => paeth(a, b, c),
+        };
+        out.push(x.wrapping_sub(predictor));
+    }
+}
+
+/// Encodes `image` as RGB, or as RGBA if any pixel is not opaque.
+pub(crate) fn encode(image: &Image, compression: Compression) -> Vec<u8> {
+    let opaque = image.pixels().iter().all(|p| p[3] == 255);
+    let bpp = if opaque { 3 } else { 4 };
+    let stride = image.width() * bpp;
+
+    let mut raw = Vec::with_capacity((stride + 1) * image.height());
+    let mut above = vec![0; stride];
+    let mut row = Vec::with_capacity(stride);
+    let mut candidate = Vec::with_capacity(stride + 1);
+    for y in 0..image.height() {
+        row.clear();
+        for pixel in &image.pixels()[y * image.width()..(y + 1) * image.width()] {
+            row.extend_from_slice(&pixel[..bpp]);
+        }
+        if compression == Compression::Stored {
+            filter_row(&mut raw, 0, &row, &above, bpp);
+        } else {
+            // pick the filter with the smallest sum of absolute residuals
+            let mut best = (u64::MAX, 0);
+            for filter in 0..5 {
+                candidate.clear();
+                filter_row(&mut candidate, filter, &row, &above, bpp);
+                let cost = candidate[1..]
+                    .iter()
+                    .map(|&r| (r as i8).unsigned_abs() as u64)
+                    .sum::<u64>();
+                if cost < best.0 {
+                    best = (cost, filter);
+                }
+            }
+            filter_row(&mut raw, best.1, &row, &above, bpp);
+        }
+        std::mem::swap(&mut above, &mut row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(image.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height() as u32).to_be_bytes());
+    // 8 bits per channel, truecolour with or without alpha, no interlacing
+    header.extend_from_slice(&[8, if opaque { 2 } else { 6 }, 0, 0, 0]);
+
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib::compress(&raw, compression));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn format_error(reason: impl Into<String>) -> ImageError {
+    ImageError::Format(reason.into())
+}
+
+pub(crate) fn is_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(SIGNATURE)
+}
+
+/// Decodes a PNG to RGBA, reducing 16-bit channels to their high byte.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Image, ImageError> {
+    if !is_png(bytes) {
+        return Err(format_error("missing PNG signature"));
+    }
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut idat = Vec::new();
+    let mut at = SIGNATURE.len();
+    loop {
+        let len = bytes
+            .get(at..at + 4)
+            .ok_or_else(|| format_error("missing IEND chunk"))?;
+        let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+        let body = bytes
+            .get(at + 4..at + 8 + len)
+            .ok_or_else(|| format_error("truncated chunk"))?;
+        let crc = bytes
+            .get(at + 8 + len..at + 12 + len)
+            .ok_or_else(|| format_error("truncated chunk"))?;
+        let (kind, data) = body.split_at(4);
+        if crc32::checksum(body) != u32::from_be_bytes(crc.try_into().unwrap()) {
+            let kind = String::from_utf8_lossy(kind);
+            return Err(format_error(format!("CRC mismatch in {kind} chunk")));
+        }
+        at += 12 + len;
+
+        match kind {
+            b"IHDR" if data.len() == 13 => header = Some(data.to_vec()),
+            b"PLTE" => {
+                palette = data
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                    .collect()
+            }
+            b"tRNS" => {
+                for (entry, &alpha) in palette.iter_mut().zip(data) {
+                    entry[3] = alpha;
+                }
+            }
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            // ancillary chunks (lowercase first letter) may be skipped
+            _ if kind[0].is_ascii_lowercase() => {}
+            _ => {
+                let kind = String::from_utf8_lossy(kind);
+                return Err(ImageError::Unsupported(format!("critical chunk {kind}")));
+            }
+        }
+    }
+
+    let header = header.ok_or_else(|| format_error("missing IHDR chunk"))?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap()) as usize;
+    let (depth, color_type, interlace) = (header[8] as usize, header[9], header[12]);
+    if width == 0 || height == 0 {
+        return Err(format_error("zero image width or height"));
+    }
+    if interlace != 0 {
+        return Err(ImageError::Unsupported("interlaced PNG".to_string()));
+    }
+    let channels = match (color_type, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (2 | 6, 8 | 16) => 3 + (color_type == 6) as usize,
+        (4, 8 | 16) => 2,
+        _ => {
+            return Err(format_error(format!(
+                "invalid colour type {color_type} at bit depth {depth}"
+            )))
+        }
+    };
+    if color_type == 3 && palette.is_empty() {
+        return Err(format_error("missing PLTE chunk"));
+    }
+
+    let stride = width
+        .checked_mul(channels * depth)
+        .ok_or_else(|| format_error(format!("image width {width} too large")))?
+        .div_ceil(8);
+    let bpp = (channels * depth).div_ceil(8);
+    let expected = (stride + 1)
+        .checked_mul(height)
+        .ok_or_else(|| format_error(format!("image size {width}x{height} too large")))?;
+    let raw = zlib::decompress(&idat, expected).map_err(format_error)?;
+    if raw.len() != expected {
+        return Err(format_error("image data has the wrong length"));
+    }
+
+    let mut image = Image::new(width, height);
+    let mut above = vec![0; stride];
+    let mut row = vec![0; stride];
+    for y in 0..height {
+        let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        row.copy_from_slice(&line[1..]);
+        unfilter_row(line[0], &mut row, &above, bpp)?;
+
+        for x in 0..width {
+            // samples of this pixel, scaled to 8 bits unless they index the palette
+            let sample = |c: usize| -> u8 {
+                match depth {
+                    8 => row[x * channels + c],
+                    16 => row[2 * (x * channels + c)],
+                    _ => {
+                        let bit = (x * channels + c) * depth;
+                        let value = (row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1);
+                        if color_type == 3 {
+                            value
+                        } else {
+                            (value as usize * 255 / ((1 << depth) - 1)) as u8
+                        }
+                    }
+                }
+            };
+            let pixel = match color_type {
+                0 => [sample(0), sample(0), sample(0), 255],
+                2 => [sample(0), sample(1), sample(2), 255],
+                3 => *palette
+                    .get(sample(0) as usize)
+                    .ok_or_else(|| format_error("palette index out of range"))?,
+                4 => [sample(0), sample(0), sample(0), sample(1)],
+                _ => [sample(0), sample(1), sample(2), sample(3)],
+            };
+            image.set(x, y, pixel);
+        }
+        std::mem::swap(&mut above, &mut row);
+    }
+    Ok(image)
+}
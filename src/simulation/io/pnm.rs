@@ -0,0 +1,97 @@
+//! Decoding of Netpbm graymaps and pixmaps: plain (P2, P3) and raw (P5, P6),
+//! with any maximum value up to 65535.
+
+use crate::simulation::error::ImageError;
+
+use super::image::Image;
+
+fn format_error(reason: impl Into<String>) -> ImageError {
+    ImageError::Format(reason.into())
+}
+
+pub(crate) fn is_pnm(bytes: &[u8]) -> bool {
+    matches!(bytes, [b'P', b'2' | b'3' | b'5' | b'6', ..])
+}
+
+/// Reads whitespace-separated ASCII numbers, skipping `#` comments.
+struct Tokens<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Tokens<'_> {
+    fn number(&mut self) -> Result<u32, ImageError> {
+        loop {
+            match self.bytes.get(self.at) {
+                Some(b'#') => {
+                    while self.bytes.get(self.at).is_some_and(|&b| b != b'\n') {
+                        self.at += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.at += 1,
+                _ => break,
+            }
+        }
+        let start = self.at;
+        while self.bytes.get(self.at).is_some_and(u8::is_ascii_digit) {
+            self.at += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.at])
+            .unwrap()
+            .parse()
+            .map_err(|_| format_error(format!("expected a number at byte {start}")))
+    }
+}
+
+/// Decodes a PGM or PPM to opaque RGBA, scaling samples to 8 bits.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Image, ImageError> {
+    if !is_pnm(bytes) {
+        return Err(format_error("missing P2, P3, P5 or P6 magic number"));
+    }
+    let kind = bytes[1];
+    let channels = if matches!(kind, b'3' | b'6') { 3 } else { 1 };
+    let mut tokens = Tokens { bytes, at: 2 };
+    let width = tokens.number()? as usize;
+    let height = tokens.number()? as usize;
+    let maxval = tokens.number()?;
+    if !(1..=65535).contains(&maxval) {
+        return Err(format_error(format!("maximum value {maxval} out of range")));
+    }
+    let samples = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or_else(|| format_error(format!("image size {width}x{height} too large")))?;
+
+    let values: Vec<u32> = if matches!(kind, b'2' | b'3') {
+        (0..samples)
+            .map(|_| tokens.number())
+            .collect::<Result<_, _>>()?
+    } else {
+        // a single whitespace byte separates the header from the raster
+        let start = tokens.at + 1;
+        let width = if maxval < 256 { 1 } else { 2 };
+        let raster = samples
+            .checked_mul(width)
+            .and_then(|len| bytes.get(start..start.checked_add(len)?))
+            .ok_or_else(|| format_error("truncated raster"))?;
+        raster
+            .chunks_exact(width)
+            .map(|s| s.iter().fold(0, |acc, &b| acc << 8 | b as u32))
+            .collect()
+    };
+    if let Some(v) = values.iter().find(|&&v| v > maxval) {
+        return Err(format_error(format!("sample {v} exceeds maximum {maxval}")));
+    }
+
+    let scale = |v: u32| ((v * 255 + maxval / 2) / maxval) as u8;
+    let mut image = Image::new(width, height);
+    for (i, pixel) in values.chunks_exact(channels).enumerate() {
+        let [r, g, b] = if channels == 1 {
+            [scale(pixel[0]); 3]
+        } else {
+            [scale(pixel[0]), scale(pixel[1]), scale(pixel[2])]
+        };
+        image.set(i % width, i / width, [r, g, b, 255]);
+    }
+    Ok(image)
+}
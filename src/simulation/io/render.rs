@@ -0,0 +1,233 @@
+//! Headless rendering of scalar fields through a colormap, and recording of
+//! numbered PNG frames while a simulation runs.
+//!
+//! Images follow the window renderer: grid axis 0 runs left to right and
+//! axis 1 top to bottom, one pixel per cell before scaling.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use super::{image::Image, zlib::Compression};
+use crate::simulation::{
+    grid::{CoordInt, Grid, Int},
+    scalar::Scalar,
+    simulation::Simulation,
+    vector::Float,
+    view::GridRead,
+};
+
+/// Maps values in `[0, 1]` to colours.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    Grayscale,
+    /// Perceptually uniform, dark blue through green to yellow.
+    #[default]
+    Viridis,
+    /// Perceptually uniform, black through red to pale yellow.
+    Inferno,
+    /// Diverging blue to red through grey, for signed fields.
+    Coolwarm,
+}
+
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 81, 139],
+    [44, 113, 142],
+    [33, 144, 141],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+const INFERNO: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [31, 12, 72],
+    [85, 15, 109],
+    [136, 34, 106],
+    [186, 54, 85],
+    [227, 89, 51],
+    [249, 140, 10],
+    [249, 201, 50],
+    [252, 255, 164],
+];
+const COOLWARM: [[u8; 3]; 5] = [
+    [59, 76, 192],
+    [141, 176, 254],
+    [221, 221, 221],
+    [244, 154, 123],
+    [180, 4, 38],
+];
+
+impl Colormap {
+    /// The colour of `t`, clamped to `[0, 1]`; NaN maps to black.
+    pub fn color(&self, t: Float) -> [u8; 3] {
+        if t.is_nan() {
+            return [0; 3];
+        }
+        let t = t.clamp(0.0, 1.0);
+        let stops: &[[u8; 3]] = match self {
+            Colormap::Grayscale => &[[0; 3], [255; 3]],
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Inferno => &INFERNO,
+            Colormap::Coolwarm => &COOLWARM,
+        };
+        let x = t * (stops.len() - 1) as Float;
+        let i = (x.floor() as usize).min(stops.len() - 2);
+        let w = x - i as Float;
+        std::array::from_fn(|c| {
+            let (a, b) = (stops[i][c] as Float, stops[i + 1][c] as Float);
+            (a + (b - a) * w).round() as u8
+        })
+    }
+}
+
+/// Renders a 2D field, mapping `range` (or the finite minimum and maximum
+/// of the field if `None`) onto the colormap.
+pub fn render<S: Scalar>(
+    field: &impl GridRead<S, 2>,
+    colormap: Colormap,
+    range: Option<(Float, Float)>,
+) -> Image {
+    let size = field.size().0.map(|n| n.max(0) as usize);
+    let value = |x: usize, y: usize| {
+        field
+            .get(&CoordInt([x as Int, y as Int]))
+            .map_or(Float::NAN, |v| v.to_f64())
+    };
+    let (lo, hi) = range.unwrap_or_else(|| {
+        let values = (0..size[1]).flat_map(|y| (0..size[0]).map(move |x| (x, y)));
+        values
+            .map(|(x, y)| value(x, y))
+            .filter(|v| v.is_finite())
+            .fold((Float::INFINITY, Float::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            })
+    });
+    let span = if hi > lo { hi - lo } else { 1.0 };
+
+    let mut image = Image::new(size[0], size[1]);
+    for y in 0..size[1] {
+        for x in 0..size[0] {
+            let [r, g, b] = colormap.color((value(x, y) - lo) / span);
+            image.set(x, y, [r, g, b, 255]);
+        }
+    }
+    image
+}
+
+/// The field a [`FrameRecorder`] draws.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FrameSource {
+    #[default]
+    Density,
+    Pressure,
+    /// Magnitude of the velocity.
+    Speed,
+    /// One of the simulation's extra fields, by name.
+    Field(String),
+}
+
+/// Writes every `every`-th step of a 2D simulation as a numbered PNG,
+/// `<prefix>000000.png`, `<prefix>000001.png`, ..., ready for a video encoder.
+pub struct FrameRecorder {
+    dir: PathBuf,
+    every: u64,
+    prefix: String,
+    source: FrameSource,
+    colormap: Colormap,
+    range: Option<(Float, Float)>,
+    scale: usize,
+    compression: Compression,
+}
+
+impl FrameRecorder {
+    /// Records density frames into `dir`, creating it if needed.
+    ///
+    /// Panics if `every` is zero.
+    pub fn new(dir: impl Into<PathBuf>, every: u64) -> io::Result<Self> {
+        assert!(every > 0, "frame interval must be positive");
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(FrameRecorder {
+            dir,
+            every,
+            prefix: "frame_".to_string(),
+            source: FrameSource::default(),
+            colormap: Colormap::default(),
+            range: None,
+            scale: 1,
+            compression: Compression::default(),
+        })
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn with_source(mut self, source: FrameSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    /// Fixes the value range of the colormap, so that frames are comparable;
+    /// by default each frame is scaled to its own minimum and maximum.
+    pub fn with_range(mut self, lo: Float, hi: Float) -> Self {
+        self.range = Some((lo, hi));
+        self
+    }
+
+    /// Draws each cell as a `scale`×`scale` block.
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes a frame if the step count is a multiple of the interval, and
+    /// returns its path.
+    pub fn record<S: Scalar>(&self, sim: &Simulation<2, S>) -> io::Result<Option<PathBuf>> {
+        if !sim.steps.is_multiple_of(self.every) {
+            return Ok(None);
+        }
+        let image = match &self.source {
+            FrameSource::Density => render(&sim.densities, self.colormap, self.range),
+            FrameSource::Pressure => render(&sim.pressure, self.colormap, self.range),
+            FrameSource::Speed => {
+                let speed: Grid<S, 2> = sim.velocities.map(|v| v.norm());
+                render(&speed, self.colormap, self.range)
+            }
+            FrameSource::Field(name) => {
+                let field = sim.fields.get(name).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("no field named {name:?}"))
+                })?;
+                render(field, self.colormap, self.range)
+            }
+        };
+        let image = if self.scale > 1 {
+            image.scaled(self.scale)
+        } else {
+            image
+        };
+        let frame = sim.steps / self.every;
+        let path = self.dir.join(format!("{}{frame:06}.png", self.prefix));
+        image.save_png(&path, self.compression)?;
+        Ok(Some(path))
+    }
+}
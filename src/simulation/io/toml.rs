@@ -0,0 +1,457 @@
+//! A parser for the subset of TOML 1.0 used by scene files: bare, quoted and
+//! dotted keys, `[tables]`, `[[arrays of tables]]`, basic and literal strings
+//! (single- or multi-line), decimal, hexadecimal, octal and binary integers,
+//! floats, booleans, arrays and inline tables. Dates and times are rejected
+//! with an error, as scenes have no use for them.
+//!
+//! Every value remembers the line it was defined on, so that errors found
+//! while interpreting the document can point back into the file.
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Entry>),
+    Table(Table),
+}
+
+impl Value {
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Float(_) => "a float",
+            Value::Boolean(_) => "a boolean",
+            Value::Array(_) => "an array",
+            Value::Table(_) => "a table",
+        }
+    }
+}
+
+/// A value and the line it was defined on, counting from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Entry {
+    pub line: usize,
+    pub value: Value,
+}
+
+pub(crate) type Table = BTreeMap<String, Entry>;
+
+/// A syntax error and the line it was found on.
+pub(crate) type SyntaxError = (usize, String);
+
+pub(crate) fn parse(text: &str) -> Result<Table, SyntaxError> {
+    let mut parser = Parser {
+        text,
+        at: 0,
+        line: 1,
+    };
+    let mut root = Table::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut defined: Vec<Vec<String>> = Vec::new();
+    loop {
+        parser.skip_blank_lines();
+        let Some(c) = parser.peek() else {
+            return Ok(root);
+        };
+        let line = parser.line;
+        if c == '[' {
+            parser.at += 1;
+            let array = parser.eat('[');
+            parser.skip_spaces();
+            let path = parser.key()?;
+            parser.skip_spaces();
+            parser.expect(']')?;
+            if array {
+                parser.expect(']')?;
+                let (last, parents) = path.split_last().unwrap();
+                let table = table_at(&mut root, parents, line)?;
+                let entry = table.entry(last.clone()).or_insert(Entry {
+                    line,
+                    value: Value::Array(Vec::new()),
+                });
+                match &mut entry.value {
+                    Value::Array(items)
+                        if items.iter().all(|i| matches!(i.value, Value::Table(_))) =>
+                    {
+                        items.push(Entry {
+                            line,
+                            value: Value::Table(Table::new()),
+                        })
+                    }
+                    _ => {
+                        return Err((
+                            line,
+                            format!("`{}` is not an array of tables", path.join(".")),
+                        ))
+                    }
+                }
+            } else {
+                if defined.contains(&path) {
+                    return Err((line, format!("table `{}` defined twice", path.join("."))));
+                }
+                defined.push(path.clone());
+                table_at(&mut root, &path, line)?;
+            }
+            current = path;
+        } else {
+            let path = parser.key()?;
+            parser.skip_spaces();
+            parser.expect('=')?;
+            parser.skip_spaces();
+            let value = parser.value()?;
+            let table = table_at(&mut root, &current, line)?;
+            insert(table, &path, Entry { line, value })?;
+        }
+        parser.end_of_line()?;
+    }
+}
+
+/// The table at `path`, creating missing tables and descending into the
+/// last element of arrays of tables.
+fn table_at<'a>(
+    mut table: &'a mut Table,
+    path: &[String],
+    line: usize,
+) -> Result<&'a mut Table, SyntaxError> {
+    for key in path {
+        let entry = table.entry(key.clone()).or_insert(Entry {
+            line,
+            value: Value::Table(Table::new()),
+        });
+        table = match &mut entry.value {
+            Value::Table(table) => table,
+            Value::Array(items) => match items.last_mut().map(|i| &mut i.value) {
+                Some(Value::Table(table)) => table,
+                _ => return Err((line, format!("`{key}` is not a table"))),
+            },
+            _ => return Err((line, format!("`{key}` is not a table"))),
+        };
+    }
+    Ok(table)
+}
+
+fn insert(table: &mut Table, path: &[String], entry: Entry) -> Result<(), SyntaxError> {
+    let line = entry.line;
+    let (last, parents) = path.split_last().unwrap();
+    let table = table_at(table, parents, line)?;
+    if table.contains_key(last) {
+        return Err((line, format!("key `{}` defined twice", path.join("."))));
+    }
+    table.insert(last.clone(), entry);
+    Ok(())
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.at..].chars().next()
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, SyntaxError> {
+        Err((self.line, message.into()))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.at += c.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SyntaxError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            match self.peek() {
+                Some(found) => self.error(format!("expected `{c}`, found `{found}`")),
+                None => self.error(format!("expected `{c}`, found end of file")),
+            }
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.at += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.at += self.peek().unwrap().len_utf8();
+            }
+        }
+    }
+
+    /// Skips whitespace, comments and newlines.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            if self.eat('\r') || self.eat('\n') {
+                self.line += (self.text.as_bytes()[self.at - 1] == b'\n') as usize;
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), SyntaxError> {
+        self.skip_spaces();
+        self.skip_comment();
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.at += 1;
+                self.line += 1;
+                Ok(())
+            }
+            Some(c) => self.error(format!("expected the end of the line, found `{c}`")),
+        }
+    }
+
+    /// A dotted key such as `a."b c".d`.
+    fn key(&mut self) -> Result<Vec<String>, SyntaxError> {
+        let mut path = vec![self.simple_key()?];
+        loop {
+            self.skip_spaces();
+            if !self.eat('.') {
+                return Ok(path);
+            }
+            self.skip_spaces();
+            path.push(self.simple_key()?);
+        }
+    }
+
+    fn simple_key(&mut self) -> Result<String, SyntaxError> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.at;
+                let bare = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+                while self.peek().is_some_and(bare) {
+                    self.at += 1;
+                }
+                if start == self.at {
+                    return self.error("expected a key");
+                }
+                Ok(self.text[start..self.at].to_string())
+            }
+        }
+    }
+
+    /// Opens a string delimited by `quote`, returning whether it is the
+    /// three-quote multi-line form. A newline right after the opening
+    /// delimiter of a multi-line string is not part of it.
+    fn open_string(&mut self, quote: char) -> Result<bool, SyntaxError> {
+        self.expect(quote)?;
+        if !self.text[self.at..].starts_with(&format!("{quote}{quote}")) {
+            return Ok(false);
+        }
+        self.at += 2;
+        self.eat('\r');
+        if self.eat('\n') {
+            self.line += 1;
+        }
+        Ok(true)
+    }
+
+    /// Closes a multi-line string if `quote`, just consumed, starts its
+    /// closing delimiter, pushing the up to two quotes allowed before it.
+    fn close_string(&mut self, quote: char, out: &mut String) -> bool {
+        let rest = &self.text[self.at..];
+        let run = rest.chars().take_while(|&c| c == quote).count();
+        if run < 2 {
+            return false;
+        }
+        let extra = (run - 2).min(2);
+        out.extend(std::iter::repeat_n(quote, extra));
+        self.at += 2 + extra;
+        true
+    }
+
+    fn basic_string(&mut self) -> Result<String, SyntaxError> {
+        let multiline = self.open_string('"')?;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return self.error("unterminated string");
+            };
+            self.at += c.len_utf8();
+            match c {
+                '"' if !multiline || self.close_string('"', &mut out) => return Ok(out),
+                '\n' if multiline => {
+                    self.line += 1;
+                    out.push(c);
+                }
+                '\n' => return self.error("unterminated string"),
+                // a backslash ending a line trims all whitespace after it
+                '\\' if multiline
+                    && self.text[self.at..]
+                        .trim_start_matches([' ', '\t'])
+                        .starts_with(['\r', '\n']) =>
+                {
+                    while let Some(w @ (' ' | '\t' | '\r' | '\n')) = self.peek() {
+                        self.at += 1;
+                        self.line += (w == '\n') as usize;
+                    }
+                }
+                '\\' => {
+                    let escape = self.peek();
+                    self.at += escape.map_or(0, char::len_utf8);
+                    out.push(match escape {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some(e @ ('u' | 'U')) => {
+                            let digits = if e == 'u' { 4 } else { 8 };
+                            let hex = self.text.get(self.at..self.at + digits).unwrap_or("");
+                            self.at += digits;
+                            match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+                                Some(c) => c,
+                                None => return self.error(format!("invalid escape `\\{e}{hex}`")),
+                            }
+                        }
+                        Some(e) => return self.error(format!("invalid escape `\\{e}`")),
+                        None => return self.error("unterminated string"),
+                    });
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, SyntaxError> {
+        let multiline = self.open_string('\'')?;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return self.error("unterminated string");
+            };
+            self.at += c.len_utf8();
+            match c {
+                '\'' if !multiline || self.close_string('\'', &mut out) => return Ok(out),
+                '\n' if multiline => {
+                    self.line += 1;
+                    out.push(c);
+                }
+                '\n' => return self.error("unterminated string"),
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, SyntaxError> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => self.error("expected a value"),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, SyntaxError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            let line = self.line;
+            items.push(Entry {
+                line,
+                value: self.value()?,
+            });
+            self.skip_blank_lines();
+            if !self.eat(',') {
+                self.skip_blank_lines();
+                self.expect(']')?;
+                return Ok(Value::Array(items));
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, SyntaxError> {
+        self.expect('{')?;
+        let mut table = Table::new();
+        self.skip_spaces();
+        if self.eat('}') {
+            return Ok(Value::Table(table));
+        }
+        loop {
+            self.skip_spaces();
+            let line = self.line;
+            let path = self.key()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.value()?;
+            insert(&mut table, &path, Entry { line, value })?;
+            self.skip_spaces();
+            if !self.eat(',') {
+                self.expect('}')?;
+                return Ok(Value::Table(table));
+            }
+        }
+    }
+
+    /// A boolean, integer or float.
+    fn scalar(&mut self) -> Result<Value, SyntaxError> {
+        let start = self.at;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || "+-._".contains(c)) {
+            self.at += 1;
+        }
+        let token = &self.text[start..self.at];
+        let digits = token.replace('_', "");
+        let unsigned = digits.trim_start_matches(['+', '-']);
+        let radix = [("0x", 16), ("0o", 8), ("0b", 2)]
+            .into_iter()
+            .find_map(|(prefix, radix)| Some((digits.strip_prefix(prefix)?, radix)));
+        let value = match token {
+            "true" => Some(Value::Boolean(true)),
+            "false" => Some(Value::Boolean(false)),
+            _ if unsigned == "inf" || unsigned == "nan" => digits.parse().ok().map(Value::Float),
+            _ if !unsigned.starts_with(|c: char| c.is_ascii_digit()) => None,
+            // `from_str_radix` would also take a sign after the prefix
+            _ if radix.is_some() => radix
+                .filter(|(rest, radix)| {
+                    !rest.is_empty() && rest.chars().all(|c| c.is_digit(*radix))
+                })
+                .and_then(|(rest, radix)| i64::from_str_radix(rest, radix).ok())
+                .map(Value::Integer),
+            _ if digits.contains(['.', 'e', 'E']) => digits.parse().ok().map(Value::Float),
+            _ => digits.parse().ok().map(Value::Integer),
+        };
+        let date = token.len() >= 10 && token.as_bytes()[4] == b'-' && token.as_bytes()[7] == b'-';
+        match value {
+            None if date => self.error("dates and times are not supported"),
+            Some(_) if self.peek() == Some(':') => self.error("dates and times are not supported"),
+            Some(value) => Ok(value),
+            None if token.is_empty() => self.error(format!(
+                "expected a value, found `{}`",
+                self.peek().unwrap()
+            )),
+            None => self.error(format!("invalid value `{token}`")),
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Export to VTK XML ImageData (`.vti`) and time-series collections (`.pvd`)
+//! for ParaView.
+//!
+//! Cell centres become the points of the image, so a grid of size `n` spans
+//! the extent `0..=n-1` with the grid spacing. VTK images are always three
+//! dimensional and store the x axis fastest: grid axis `i` maps to VTK axis
+//! `i`, missing axes have a single point, and vectors are padded with zeros
+//! to three components.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::simulation::{
+    error::GridError,
+    grid::{CoordInt, Int},
+    scalar::Scalar,
+    simulation::Simulation,
+    vector::{Float, Vector},
+    view::GridRead,
+};
+
+/// How array data is stored in a `.vti` file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Human-readable values inside each `DataArray`.
+    Ascii,
+    /// Raw little-endian bytes in an `AppendedData` block; smaller and faster.
+    #[default]
+    Appended,
+}
+
+enum Field<'a, const D: usize, S: Scalar> {
+    Scalar(&'a dyn GridRead<S, D>),
+    Vector(&'a dyn GridRead<Vector<D, S>, D>),
+}
+
+/// A set of named point fields on one image, written as a `.vti` file.
+pub struct ImageData<'a, const D: usize, S: Scalar = Float> {
+    size: CoordInt<D>,
+    delta: Float,
+    origin: [Float; D],
+    fields: Vec<(String, Field<'a, D, S>)>,
+}
+
+impl<'a, const D: usize, S: Scalar> ImageData<'a, D, S> {
+    /// An empty image with its first cell centre at the origin.
+    ///
+    /// Panics if `D` is larger than 3.
+    pub fn new(size: CoordInt<D>, delta: Float) -> Self {
+        assert!(D <= 3, "VTK images have at most 3 dimensions, not {D}");
+        ImageData {
+            size,
+            delta,
+            origin: [0.0; D],
+            fields: Vec::new(),
+        }
+    }
+
+    /// Moves the first cell centre to `origin`.
+    pub fn with_origin(mut self, origin: [Float; D]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Adds a grid, view or other field; fails if it does not have the size
+    /// of the image.
+    pub fn add_scalar(
+        &mut self,
+        name: &str,
+        grid: &'a dyn GridRead<S, D>,
+    ) -> Result<(), GridError> {
+        self.check_size(grid.size())?;
+        self.fields.push((name.to_string(), Field::Scalar(grid)));
+        Ok(())
+    }
+
+    /// Fails if `grid` does not have the size of the image.
+    pub fn add_vector(
+        &mut self,
+        name: &str,
+        grid: &'a dyn GridRead<Vector<D, S>, D>,
+    ) -> Result<(), GridError> {
+        self.check_size(grid.size())?;
+        self.fields.push((name.to_string(), Field::Vector(grid)));
+        Ok(())
+    }
+
+    fn check_size(&self, size: CoordInt<D>) -> Result<(), GridError> {
+        if size != self.size {
+            return Err(GridError::ShapeMismatch {
+                expected: self.size.0.to_vec(),
+                found: size.0.to_vec(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, encoding: Encoding) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer, encoding)?;
+        writer.flush()
+    }
+
+    pub fn write<W: Write>(&self, mut w: W, encoding: Encoding) -> io::Result<()> {
+        let scalar_type = match std::mem::size_of::<S>() {
+            4 => "Float32",
+            _ => "Float64",
+        };
+        let extent = (0..3)
+            .map(|i| format!("0 {}", self.size.0.get(i).map_or(0, |&n| (n - 1).max(0))))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let origin = (0..3)
+            .map(|i| self.origin.get(i).copied().unwrap_or(0.0).to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let spacing = vec![self.delta.to_string(); 3].join(" ");
+
+        writeln!(w, r#"<?xml version="1.0"?>"#)?;
+        w.write_all(
+            concat!(
+                r#"<VTKFile type="ImageData" version="1.0" byte_order="LittleEndian""#,
+                r#" header_type="UInt64">"#,
+                "\n"
+            )
+            .as_bytes(),
+        )?;
+        writeln!(
+            w,
+            r#"  <ImageData WholeExtent="{extent}" Origin="{origin}" Spacing="{spacing}">"#
+        )?;
+        writeln!(w, r#"    <Piece Extent="{extent}">"#)?;
+        write!(w, "      <PointData")?;
+        let first = |vector: bool| {
+            self.fields
+                .iter()
+                .find(|(_, field)| matches!(field, Field::Vector(_)) == vector)
+        };
+        if let Some((name, _)) = first(false) {
+            write!(w, r#" Scalars="{}""#, escape(name))?;
+        }
+        if let Some((name, _)) = first(true) {
+            write!(w, r#" Vectors="{}""#, escape(name))?;
+        }
+        writeln!(w, ">")?;
+
+        let mut appended = Vec::new();
+        for (name, field) in &self.fields {
+            let (components, values) = self.values(field);
+            write!(
+                w,
+                r#"        <DataArray type="{scalar_type}" Name="{}""#,
+                escape(name)
+            )?;
+            write!(w, r#" NumberOfComponents="{components}""#)?;
+            match encoding {
+                Encoding::Ascii => {
+                    writeln!(w, r#" format="ascii">"#)?;
+                    let row = components * self.size.0.first().map_or(1, |&n| n.max(1) as usize);
+                    for line in values.chunks(row) {
+                        let line = line.iter().map(|v| format!("{v:?}")).collect::<Vec<_>>();
+                        writeln!(w, "          {}", line.join(" "))?;
+                    }
+                    writeln!(w, "        </DataArray>")?;
+                }
+                Encoding::Appended => {
+                    writeln!(w, r#" format="appended" offset="{}"/>"#, appended.len())?;
+                    let bytes = values.len() * std::mem::size_of::<S>();
+                    appended.extend_from_slice(&(bytes as u64).to_le_bytes());
+                    for value in values.iter().map(|v| v.to_f64()) {
+                        if std::mem::size_of::<S>() == 4 {
+                            appended.extend_from_slice(&(value as f32).to_le_bytes());
+                        } else {
+                            appended.extend_from_slice(&value.to_le_bytes());
+                        }
+                    }
+                }
+            }
+        }
+        writeln!(w, "      </PointData>")?;
+        writeln!(w, "    </Piece>")?;
+        writeln!(w, "  </ImageData>")?;
+        if encoding == Encoding::Appended {
+            writeln!(w, r#"  <AppendedData encoding="raw">"#)?;
+            write!(w, "   _")?;
+            w.write_all(&appended)?;
+            writeln!(w)?;
+            writeln!(w, "  </AppendedData>")?;
+        }
+        writeln!(w, "</VTKFile>")
+    }
+
+    /// Number of components and the values of `field` in VTK point order,
+    /// x fastest.
+    fn values(&self, field: &Field<'_, D, S>) -> (usize, Vec<S>) {
+        let cells = self
+            .size
+            .0
+            .iter()
+            .map(|&n| n.max(0) as usize)
+            .product::<usize>();
+        let components = match field {
+            Field::Scalar(_) => 1,
+            Field::Vector(_) => 3,
+        };
+        let mut values = Vec::with_capacity(cells * components);
+        for mut index in 0..cells {
+            let mut coord = CoordInt::<D>::default();
+            for (c, &n) in coord.0.iter_mut().zip(&self.size.0) {
+                *c = (index % n as usize) as Int;
+                index /= n as usize;
+            }
+            match field {
+                Field::Scalar(grid) => values.push(*grid.get(&coord).expect("coord in image")),
+                Field::Vector(grid) => {
+                    let v = grid.get(&coord).expect("coord in image");
+                    values.extend((0..3).map(|i| v.0.get(i).copied().unwrap_or(S::ZERO)));
+                }
+            }
+        }
+        (components, values)
+    }
+}
+
+impl<const D: usize, S: Scalar> Simulation<D, S> {
+    /// The density, velocity, pressure and extra fields as one image.
+    pub fn image_data(&self) -> ImageData<'_, D, S> {
+        let mut image = ImageData::new(self.size(), self.delta());
+        let fields = [("density", &self.densities), ("pressure", &self.pressure)]
+            .into_iter()
+            .chain(self.fields.iter().map(|(name, grid)| (name.as_str(), grid)));
+        for (name, grid) in fields {
+            image
+                .add_scalar(name, grid)
+                .expect("simulation grids share one size");
+        }
+        image
+            .add_vector("velocity", &self.velocities)
+            .expect("simulation grids share one size");
+        image
+    }
+}
+
+/// A `.pvd` collection listing one `.vti` file per time step.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Collection {
+    datasets: Vec<(Float, PathBuf)>,
+}
+
+impl Collection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the file for time `time`, relative to the `.pvd` file.
+    pub fn push(&mut self, time: Float, file: impl Into<PathBuf>) {
+        self.datasets.push((time, file.into()));
+    }
+
+    pub fn datasets(&self) -> &[(Float, PathBuf)] {
+        &self.datasets
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0"?>"#)?;
+        writeln!(w, r#"<VTKFile type="Collection" version="1.0">"#)?;
+        writeln!(w, "  <Collection>")?;
+        for (time, file) in &self.datasets {
+            writeln!(
+                w,
+                r#"    <DataSet timestep="{time}" part="0" file="{}"/>"#,
+                escape(&file.to_string_lossy())
+            )?;
+        }
+        writeln!(w, "  </Collection>")?;
+        writeln!(w, "</VTKFile>")
+    }
+}
+
+/// Writes `<name>_<step>.vti` files into a directory and keeps the `.pvd`
+/// collection listing them up to date.
+pub struct TimeSeries {
+    dir: PathBuf,
+    name: String,
+    encoding: Encoding,
+    collection: Collection,
+}
+
+impl TimeSeries {
+    /// Creates `dir` if it does not exist.
+    pub fn new(dir: impl Into<PathBuf>, name: &str, encoding: Encoding) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(TimeSeries {
+            dir,
+            name: name.to_string(),
+            encoding,
+            collection: Collection::new(),
+        })
+    }
+
+    /// Writes the image for one step and rewrites `<name>.pvd`, so that an
+    /// interrupted run still leaves a readable series. Returns the `.vti` path.
+    pub fn write_step<const D: usize, S: Scalar>(
+        &mut self,
+        step: u64,
+        time: Float,
+        image: &ImageData<'_, D, S>,
+    ) -> io::Result<PathBuf> {
+        let file = format!("{}_{step:06}.vti", self.name);
+        let path = self.dir.join(&file);
+        image.save(&path, self.encoding)?;
+        self.collection.push(time, file);
+        self.collection.save(self.collection_path())?;
+        Ok(path)
+    }
+
+    pub fn collection(&self) -> &Collection {
+        &self.collection
+    }
+
+    pub fn collection_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.pvd", self.name))
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,440 @@
+//! zlib streams (RFC 1950) around deflate data (RFC 1951), as used by PNG.
+//!
+//! Compression emits either stored blocks or LZ77 matches coded with the
+//! fixed Huffman table; that gives most of the gain on smooth fields without
+//! building dynamic tables. Decompression handles all three block types.
+
+/// How image data is deflated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Stored blocks only; fastest, no size reduction.
+    Stored,
+    /// LZ77 matches with fixed Huffman codes.
+    #[default]
+    Deflate,
+}
+
+const WINDOW: usize = 1 << 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+/// Base lengths of the length codes 257..=285, and their extra bits.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// Base distances of the distance codes 0..=29, and their extra bits.
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before `b` may overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Writes bits least significant first, as deflate packs them.
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> Self {
+        BitWriter {
+            out,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, len: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += len;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which deflate stores most significant bit first.
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.write(0, 8 - self.count);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.out
+    }
+}
+
+fn write_literal(w: &mut BitWriter, symbol: u16) {
+    match symbol {
+        0..=143 => w.write_code(0x30 + symbol as u32, 8),
+        144..=255 => w.write_code(0x190 + (symbol as u32 - 144), 9),
+        256..=279 => w.write_code(symbol as u32 - 256, 7),
+        _ => w.write_code(0xc0 + (symbol as u32 - 280), 8),
+    }
+}
+
+fn write_match(w: &mut BitWriter, len: usize, dist: usize) {
+    let code = LENGTH_BASE
+        .iter()
+        .rposition(|&base| base as usize <= len)
+        .unwrap();
+    write_literal(w, 257 + code as u16);
+    w.write(
+        (len - LENGTH_BASE[code] as usize) as u32,
+        LENGTH_EXTRA[code] as u32,
+    );
+    let code = DIST_BASE
+        .iter()
+        .rposition(|&base| base as usize <= dist)
+        .unwrap();
+    w.write_code(code as u32, 5);
+    w.write(
+        (dist - DIST_BASE[code] as usize) as u32,
+        DIST_EXTRA[code] as u32,
+    );
+}
+
+fn hash(data: &[u8]) -> usize {
+    let key = u32::from_le_bytes([data[0], data[1], data[2], 0]);
+    (key.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Raw deflate data of one fixed-Huffman block.
+fn deflate_fixed(data: &[u8], out: Vec<u8>) -> Vec<u8> {
+    let mut w = BitWriter::new(out);
+    w.write(1, 1); // final block
+    w.write(1, 2); // fixed Huffman codes
+
+    // most recent position of each hash, and the previous one with the same hash
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let insert = |pos: usize, head: &mut [usize], prev: &mut [usize]| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(&data[pos..]);
+            prev[pos % WINDOW] = head[h];
+            head[h] = pos;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let max = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash(&data[pos..])];
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || pos - candidate > WINDOW - 1 {
+                    break;
+                }
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + max])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best.0 {
+                    best = (len, pos - candidate);
+                    if len == max {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+            }
+        }
+
+        if best.0 >= MIN_MATCH {
+            write_match(&mut w, best.0, best.1);
+            for p in pos..pos + best.0 {
+                insert(p, &mut head, &mut prev);
+            }
+            pos += best.0;
+        } else {
+            write_literal(&mut w, data[pos] as u16);
+            insert(pos, &mut head, &mut prev);
+            pos += 1;
+        }
+    }
+    write_literal(&mut w, 256);
+    w.finish()
+}
+
+/// Raw deflate data of stored blocks of up to 65535 bytes each.
+fn deflate_stored(data: &[u8], mut out: Vec<u8>) -> Vec<u8> {
+    let mut chunks = data.chunks(u16::MAX as usize).peekable();
+    if chunks.peek().is_none() {
+        // an empty stream still needs one final block
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        out.push(last as u8);
+        let len = chunk.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out
+}
+
+/// Wraps `data` in a zlib stream.
+pub(crate) fn compress(data: &[u8], compression: Compression) -> Vec<u8> {
+    let mut out = match compression {
+        // 32K window, with the compression-level hint set to fastest or default
+        Compression::Stored => deflate_stored(data, vec![0x78, 0x01]),
+        Compression::Deflate => deflate_fixed(data, vec![0x78, 0x9c]),
+    };
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Reads bits least significant first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> Result<u32, String> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or("unexpected end of deflate data")?;
+        let bit = (byte >> self.bit) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+        Ok(bit as u32)
+    }
+
+    fn bits(&mut self, len: u32) -> Result<u32, String> {
+        (0..len).try_fold(0, |acc, i| Ok(acc | self.bit()? << i))
+    }
+
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+/// A canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    /// Number of codes of each length.
+    counts: [u16; 16],
+    /// Symbols ordered by code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols = (0..lengths.len() as u16)
+            .filter(|&s| lengths[s as usize] != 0)
+            .collect::<Vec<_>>();
+        symbols.sort_by_key(|&s| lengths[s as usize]);
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, r: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= r.bit()? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_tables(r: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    const ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+    let literals = r.bits(5)? as usize + 257;
+    let distances = r.bits(5)? as usize + 1;
+    let code_lengths = r.bits(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for &i in &ORDER[..code_lengths] {
+        lengths[i] = r.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths);
+
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (value, repeat) = match code.decode(r)? {
+            len @ 0..=15 => (len as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or("repeat with no previous length")?;
+                (previous, 3 + r.bits(2)?)
+            }
+            17 => (0, 3 + r.bits(3)?),
+            _ => (0, 11 + r.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literals + distances {
+        return Err("code lengths overrun".to_string());
+    }
+    let (literal, distance) = lengths.split_at(literals);
+    Ok((Huffman::new(literal), Huffman::new(distance)))
+}
+
+/// Fails once `extra` more bytes would take `out` past `limit`.
+fn check_limit(out: &[u8], extra: usize, limit: usize) -> Result<(), String> {
+    if extra > limit - out.len() {
+        return Err(format!("decompressed data exceeds {limit} bytes"));
+    }
+    Ok(())
+}
+
+/// Unwraps a zlib stream, checking its Adler-32 checksum.
+///
+/// Stops with an error as soon as the output would exceed `limit` bytes, so a
+/// small corrupt or hostile stream cannot expand without bound.
+pub(crate) fn decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let [cmf, flg, ..] = *data else {
+        return Err("zlib stream too short".to_string());
+    };
+    if cmf & 0x0f != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) || flg & 0x20 != 0 {
+        return Err("bad zlib header".to_string());
+    }
+
+    let mut r = BitReader {
+        data: &data[2..],
+        pos: 0,
+        bit: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = r.bit()? == 1;
+        match r.bits(2)? {
+            0 => {
+                r.align();
+                let header = r
+                    .data
+                    .get(r.pos..r.pos + 4)
+                    .ok_or("truncated stored block")?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                if len != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err("corrupt stored block length".to_string());
+                }
+                let start = r.pos + 4;
+                let block = r
+                    .data
+                    .get(start..start + len as usize)
+                    .ok_or("truncated stored block")?;
+                check_limit(&out, block.len(), limit)?;
+                out.extend_from_slice(block);
+                r.pos = start + len as usize;
+            }
+            kind @ (1 | 2) => {
+                let (literal, distance) = if kind == 1 {
+                    fixed_tables()
+                } else {
+                    dynamic_tables(&mut r)?
+                };
+                loop {
+                    let symbol = literal.decode(&mut r)? as usize;
+                    if symbol < 256 {
+                        check_limit(&out, 1, limit)?;
+                        out.push(symbol as u8);
+                        continue;
+                    }
+                    if symbol == 256 {
+                        break;
+                    }
+                    let code = symbol - 257;
+                    if code >= 29 {
+                        return Err("invalid length code".to_string());
+                    }
+                    let len =
+                        LENGTH_BASE[code] as usize + r.bits(LENGTH_EXTRA[code] as u32)? as usize;
+                    let code = distance.decode(&mut r)? as usize;
+                    if code >= 30 {
+                        return Err("invalid distance code".to_string());
+                    }
+                    let dist = DIST_BASE[code] as usize + r.bits(DIST_EXTRA[code] as u32)? as usize;
+                    if dist > out.len() {
+                        return Err("distance too far back".to_string());
+                    }
+                    check_limit(&out, len, limit)?;
+                    let start = out.len() - dist;
+                    for i in 0..len {
+                        out.push(out[start + i]);
+                    }
+                }
+            }
+            _ => return Err("invalid block type".to_string()),
+        }
+        if last {
+            break;
+        }
+    }
+
+    r.align();
+    let trailer = r
+        .data
+        .get(r.pos..r.pos + 4)
+        .ok_or("missing Adler-32 checksum")?;
+    if u32::from_be_bytes(trailer.try_into().unwrap()) != adler32(&out) {
+        return Err("Adler-32 checksum mismatch".to_string());
+    }
+    Ok(out)
+}
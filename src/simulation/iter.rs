@@ -0,0 +1,497 @@
+//! Iterators over the cells of a `Grid`, whole or restricted to a region.
+
+use std::{
+    iter::{Enumerate, Zip},
+    slice, vec,
+};
+
+use super::grid::{flatten, unflatten, CoordInt, Grid, Int};
+
+/// Row-major iterator over the coordinates of the box `[lo, hi)`.
+#[derive(Clone, Debug)]
+pub struct CoordRange<const D: usize> {
+    lo: CoordInt<D>,
+    extent: CoordInt<D>,
+    front: usize,
+    back: usize,
+}
+
+impl<const D: usize> CoordRange<D> {
+    pub fn new(lo: CoordInt<D>, hi: CoordInt<D>) -> Self {
+        let mut extent = CoordInt::<D>::default();
+        for i in 0..D {
+            extent.0[i] = (hi.0[i] - lo.0[i]).max(0);
+        }
+        let len = extent.0.iter().map(|&e| e as usize).product();
+        CoordRange {
+            lo,
+            extent,
+            front: 0,
+            back: len,
+        }
+    }
+
+    fn coord_at(&self, index: usize) -> CoordInt<D> {
+        unflatten(&self.extent, index) + self.lo
+    }
+}
+
+impl<const D: usize> Iterator for CoordRange<D> {
+    type Item = CoordInt<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.coord_at(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<const D: usize> DoubleEndedIterator for CoordRange<D> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.coord_at(self.back))
+    }
+}
+
+impl<const D: usize> ExactSizeIterator for CoordRange<D> {}
+
+/// Iterator over the face neighbours of a cell, see `CoordInt::face_neighbours`.
+#[derive(Clone, Debug)]
+pub struct FaceNeighbours<const D: usize> {
+    centre: CoordInt<D>,
+    front: usize,
+    back: usize,
+}
+
+impl<const D: usize> FaceNeighbours<D> {
+    pub(crate) fn new(centre: CoordInt<D>) -> Self {
+        FaceNeighbours {
+            centre,
+            front: 0,
+            back: 2 * D,
+        }
+    }
+
+    fn neighbour_at(&self, index: usize) -> CoordInt<D> {
+        let step = if index.is_multiple_of(2) { -1 } else { 1 };
+        self.centre.offset(index / 2, step)
+    }
+}
+
+impl<const D: usize> Iterator for FaceNeighbours<D> {
+    type Item = CoordInt<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.neighbour_at(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<const D: usize> DoubleEndedIterator for FaceNeighbours<D> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.neighbour_at(self.back))
+    }
+}
+
+impl<const D: usize> ExactSizeIterator for FaceNeighbours<D> {}
+
+/// Iterator over the Moore neighbourhood of a cell, see
+/// `CoordInt::moore_neighbours`.
+#[derive(Clone, Debug)]
+pub struct MooreNeighbours<const D: usize> {
+    offsets: CoordRange<D>,
+    centre: CoordInt<D>,
+}
+
+impl<const D: usize> MooreNeighbours<D> {
+    pub(crate) fn new(centre: CoordInt<D>) -> Self {
+        MooreNeighbours {
+            offsets: CoordRange::new(CoordInt([-1; D]), CoordInt([2; D])),
+            centre,
+        }
+    }
+}
+
+impl<const D: usize> Iterator for MooreNeighbours<D> {
+    type Item = CoordInt<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offsets.next()?;
+        if offset == CoordInt::default() {
+            return self.next();
+        }
+        Some(self.centre + offset)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // the zero offset sits in the middle of the 3^D box and is skipped
+        let centre = (3usize.pow(D as u32) - 1) / 2;
+        let skipped = (self.offsets.front..self.offsets.back).contains(&centre) as usize;
+        let len = self.offsets.len() - skipped;
+        (len, Some(len))
+    }
+}
+
+impl<const D: usize> DoubleEndedIterator for MooreNeighbours<D> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let offset = self.offsets.next_back()?;
+        if offset == CoordInt::default() {
+            return self.next_back();
+        }
+        Some(self.centre + offset)
+    }
+}
+
+impl<const D: usize> ExactSizeIterator for MooreNeighbours<D> {}
+
+/// Iterator over `(coord, &value)` for every cell of a grid.
+pub struct GridIter<'a, T, const D: usize> {
+    inner: Zip<CoordRange<D>, slice::Iter<'a, T>>,
+}
+
+impl<'a, T, const D: usize> Iterator for GridIter<'a, T, D> {
+    type Item = (CoordInt<D>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, const D: usize> DoubleEndedIterator for GridIter<'_, T, D> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<T, const D: usize> ExactSizeIterator for GridIter<'_, T, D> {}
+
+/// Iterator over `(coord, &mut value)` for every cell of a grid.
+pub type IndexedIterMut<'a, T, const D: usize> = Zip<CoordRange<D>, slice::IterMut<'a, T>>;
+
+/// Iterator over `(coord, &value)` for the cells of an axis-aligned box.
+pub struct RegionIter<'a, T, const D: usize> {
+    grid: &'a Grid<T, D>,
+    coords: CoordRange<D>,
+}
+
+impl<'a, T: Default + Clone, const D: usize> Iterator for RegionIter<'a, T, D> {
+    type Item = (CoordInt<D>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let grid = self.grid;
+        self.coords
+            .next()
+            .map(|coord| (coord, &grid.as_slice()[grid.flatten_index(&coord)]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.coords.size_hint()
+    }
+}
+
+impl<T: Default + Clone, const D: usize> DoubleEndedIterator for RegionIter<'_, T, D> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let grid = self.grid;
+        self.coords
+            .next_back()
+            .map(|coord| (coord, &grid.as_slice()[grid.flatten_index(&coord)]))
+    }
+}
+
+impl<T: Default + Clone, const D: usize> ExactSizeIterator for RegionIter<'_, T, D> {}
+
+/// A run of consecutive cells along the last axis: every row of the grid
+/// through a region is one or more runs.
+type Run<'a, T, const D: usize> = (CoordInt<D>, Enumerate<slice::IterMut<'a, T>>);
+
+/// Iterator over `(coord, &mut value)` for the cells of a region, visiting
+/// only those cells.
+///
+/// The region is split up front into disjoint rows of the backing slice, in
+/// row-major order, so the iterator walks them from either end.
+pub struct RegionIterMut<'a, T, const D: usize> {
+    rows: vec::IntoIter<(CoordInt<D>, &'a mut [T])>,
+    front: Option<Run<'a, T, D>>,
+    back: Option<Run<'a, T, D>>,
+    remaining: usize,
+}
+
+impl<'a, T, const D: usize> RegionIterMut<'a, T, D> {
+    /// The cells of `runs`, each a first cell and a number of cells along the
+    /// last axis, which must be disjoint and in row-major order.
+    pub(crate) fn new(
+        grid: &'a mut Grid<T, D>,
+        runs: impl IntoIterator<Item = (CoordInt<D>, usize)>,
+    ) -> Self
+    where
+        T: Default + Clone,
+    {
+        let size = grid.size();
+        let mut rest = grid.as_mut_slice();
+        let mut offset = 0;
+        let mut rows = Vec::new();
+        let mut remaining = 0;
+        for (first, len) in runs {
+            let start = flatten(&size, &first);
+            let (_, tail) = std::mem::take(&mut rest).split_at_mut(start - offset);
+            let (row, tail) = tail.split_at_mut(len);
+            rows.push((first, row));
+            rest = tail;
+            offset = start + len;
+            remaining += len;
+        }
+        RegionIterMut {
+            rows: rows.into_iter(),
+            front: None,
+            back: None,
+            remaining,
+        }
+    }
+
+    fn cell(
+        (first, _): &Run<'a, T, D>,
+        (k, value): (usize, &'a mut T),
+    ) -> (CoordInt<D>, &'a mut T) {
+        (first.offset(D - 1, k as Int), value)
+    }
+}
+
+impl<'a, T, const D: usize> Iterator for RegionIterMut<'a, T, D> {
+    type Item = (CoordInt<D>, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(run) = &mut self.front {
+                if let Some(cell) = run.1.next() {
+                    self.remaining -= 1;
+                    return Some(Self::cell(run, cell));
+                }
+            }
+            match self.rows.next() {
+                Some((first, row)) => self.front = Some((first, row.iter_mut().enumerate())),
+                None => {
+                    let run = self.back.as_mut()?;
+                    let cell = run.1.next()?;
+                    self.remaining -= 1;
+                    return Some(Self::cell(run, cell));
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const D: usize> DoubleEndedIterator for RegionIterMut<'_, T, D> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(run) = &mut self.back {
+                if let Some(cell) = run.1.next_back() {
+                    self.remaining -= 1;
+                    return Some(Self::cell(run, cell));
+                }
+            }
+            match self.rows.next_back() {
+                Some((first, row)) => self.back = Some((first, row.iter_mut().enumerate())),
+                None => {
+                    let run = self.front.as_mut()?;
+                    let cell = run.1.next_back()?;
+                    self.remaining -= 1;
+                    return Some(Self::cell(run, cell));
+                }
+            }
+        }
+    }
+}
+
+impl<T, const D: usize> ExactSizeIterator for RegionIterMut<'_, T, D> {}
+
+fn on_boundary<const D: usize>(size: &CoordInt<D>, coord: &CoordInt<D>) -> bool {
+    (0..D).any(|i| coord.0[i] == 0 || coord.0[i] == size.0[i] - 1)
+}
+
+/// Walks a whole-grid iterator and keeps the cells on its boundary.
+///
+/// The number of kept cells is known up front, which gives the exact length.
+pub struct RegionFilter<I, const D: usize> {
+    inner: I,
+    size: CoordInt<D>,
+    remaining: usize,
+}
+
+impl<I, V, const D: usize> Iterator for RegionFilter<I, D>
+where
+    I: Iterator<Item = (CoordInt<D>, V)>,
+{
+    type Item = (CoordInt<D>, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let size = self.size;
+        let item = self.inner.find(|(coord, _)| on_boundary(&size, coord));
+        self.remaining -= 1;
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<I, V, const D: usize> DoubleEndedIterator for RegionFilter<I, D>
+where
+    I: DoubleEndedIterator<Item = (CoordInt<D>, V)>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let size = self.size;
+        let item = self.inner.rfind(|(coord, _)| on_boundary(&size, coord));
+        self.remaining -= 1;
+        item
+    }
+}
+
+impl<I, V, const D: usize> ExactSizeIterator for RegionFilter<I, D> where
+    I: Iterator<Item = (CoordInt<D>, V)>
+{
+}
+
+impl<T: Default + Clone, const D: usize> Grid<T, D> {
+    pub fn iter(&self) -> GridIter<'_, T, D> {
+        GridIter {
+            inner: self.coords().zip(self.as_slice().iter()),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    pub fn indexed_iter_mut(&mut self) -> IndexedIterMut<'_, T, D> {
+        self.coords().zip(self.as_mut_slice().iter_mut())
+    }
+
+    pub fn values(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn coords(&self) -> CoordRange<D> {
+        CoordRange::new(CoordInt::default(), self.size())
+    }
+
+    fn clip(&self, lo: CoordInt<D>, hi: CoordInt<D>) -> (CoordInt<D>, CoordInt<D>) {
+        let size = self.size();
+        (
+            CoordInt(std::array::from_fn(|i| lo.0[i].clamp(0, size.0[i]))),
+            CoordInt(std::array::from_fn(|i| hi.0[i].clamp(0, size.0[i]))),
+        )
+    }
+
+    /// Cells of the box `[lo, hi)`, clipped to the grid.
+    pub fn region(&self, lo: CoordInt<D>, hi: CoordInt<D>) -> RegionIter<'_, T, D> {
+        let (lo, hi) = self.clip(lo, hi);
+        RegionIter {
+            grid: self,
+            coords: CoordRange::new(lo, hi),
+        }
+    }
+
+    /// Cells with no coordinate on the first or last layer of its axis.
+    pub fn interior(&self) -> RegionIter<'_, T, D> {
+        self.region(CoordInt([1; D]), CoordInt(self.size().0.map(|s| s - 1)))
+    }
+
+    /// Cells with at least one coordinate on the first or last layer of its axis.
+    pub fn boundary(&self) -> RegionFilter<GridIter<'_, T, D>, D> {
+        let size = self.size();
+        let volume =
+            |extent: [Int; D]| extent.iter().map(|&e| e.max(0) as usize).product::<usize>();
+        RegionFilter {
+            remaining: volume(size.0) - volume(size.0.map(|s| s - 2)),
+            inner: self.iter(),
+            size,
+        }
+    }
+
+    pub fn region_mut(&mut self, lo: CoordInt<D>, hi: CoordInt<D>) -> RegionIterMut<'_, T, D> {
+        let (lo, mut hi) = self.clip(lo, hi);
+        let row = (hi.0[D - 1] - lo.0[D - 1]).max(0) as usize;
+        hi.0[D - 1] = lo.0[D - 1] + 1;
+        let rows = CoordRange::new(lo, hi).filter(|_| row > 0);
+        RegionIterMut::new(self, rows.map(|first| (first, row)))
+    }
+
+    pub fn interior_mut(&mut self) -> RegionIterMut<'_, T, D> {
+        let hi = CoordInt(self.size().0.map(|s| s - 1));
+        self.region_mut(CoordInt([1; D]), hi)
+    }
+
+    pub fn boundary_mut(&mut self) -> RegionIterMut<'_, T, D> {
+        // rows on the boundary of the other axes lie on it whole, the others
+        // only with their two ends
+        let size = self.size();
+        let last = size.0[D - 1];
+        let mut hi = size;
+        hi.0[D - 1] = last.min(1);
+        let runs = CoordRange::new(CoordInt::default(), hi).flat_map(move |first| {
+            let whole =
+                last <= 2 || (0..D - 1).any(|i| first.0[i] == 0 || first.0[i] == size.0[i] - 1);
+            if whole {
+                [Some((first, last as usize)), None]
+            } else {
+                [Some((first, 1)), Some((first.offset(D - 1, last - 1), 1))]
+            }
+        });
+        RegionIterMut::new(self, runs.flatten())
+    }
+}
+
+impl<'a, T: Default + Clone, const D: usize> IntoIterator for &'a Grid<T, D> {
+    type Item = (CoordInt<D>, &'a T);
+    type IntoIter = GridIter<'a, T, D>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Default + Clone, const D: usize> IntoIterator for &'a mut Grid<T, D> {
+    type Item = (CoordInt<D>, &'a mut T);
+    type IntoIter = IndexedIterMut<'a, T, D>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.indexed_iter_mut()
+    }
+}
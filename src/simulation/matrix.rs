@@ -0,0 +1,271 @@
+use approx::{AbsDiffEq, RelativeEq, UlpsEq};
+
+use super::{
+    grid::{CoordInt, Grid},
+    scalar::{GridValue, Scalar},
+    vector::{Float, Vector},
+};
+
+/// A `D`×`D` matrix stored row by row, `self.0[row][column]`.
+#[derive(Debug, Clone, Copy)]
+pub struct Matrix<const D: usize, S: Scalar = Float>(pub [[S; D]; D]);
+
+impl<const D: usize, S: Scalar> Default for Matrix<D, S> {
+    fn default() -> Self {
+        Self([[S::ZERO; D]; D])
+    }
+}
+
+impl<const D: usize, S: Scalar> Matrix<D, S> {
+    pub fn identity() -> Self {
+        let mut m = Self::default();
+        for i in 0..D {
+            m.0[i][i] = S::ONE;
+        }
+        m
+    }
+
+    pub fn from_rows(rows: [Vector<D, S>; D]) -> Self {
+        Matrix(rows.map(|row| row.0))
+    }
+
+    pub fn row(&self, i: usize) -> Vector<D, S> {
+        Vector(self.0[i])
+    }
+
+    pub fn column(&self, j: usize) -> Vector<D, S> {
+        Vector(std::array::from_fn(|i| self.0[i][j]))
+    }
+
+    pub fn transpose(&self) -> Self {
+        Matrix(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.0[j][i])
+        }))
+    }
+
+    pub fn trace(&self) -> S {
+        (0..D).map(|i| self.0[i][i]).sum()
+    }
+
+    /// Determinant by Gaussian elimination with partial pivoting.
+    pub fn determinant(&self) -> S {
+        let mut a = self.0;
+        let mut det = S::ONE;
+        for col in 0..D {
+            let pivot = (col..D)
+                .max_by(|&r1, &r2| {
+                    a[r1][col]
+                        .abs()
+                        .partial_cmp(&a[r2][col].abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap();
+            if a[pivot][col] == S::ZERO {
+                return S::ZERO;
+            }
+            if pivot != col {
+                a.swap(pivot, col);
+                det = -det;
+            }
+            det *= a[col][col];
+            let pivot_row = a[col];
+            for row in a.iter_mut().skip(col + 1) {
+                let factor = row[col] / pivot_row[col];
+                for (value, p) in row.iter_mut().zip(pivot_row).skip(col) {
+                    *value -= factor * p;
+                }
+            }
+        }
+        det
+    }
+
+    /// `(A + Aᵀ) / 2`; for a velocity gradient, the strain-rate tensor.
+    pub fn symmetric_part(&self) -> Self {
+        (*self + self.transpose()) / S::from_f64(2.0)
+    }
+
+    /// `(A - Aᵀ) / 2`; for a velocity gradient, the rotation tensor.
+    pub fn antisymmetric_part(&self) -> Self {
+        (*self - self.transpose()) / S::from_f64(2.0)
+    }
+
+    /// Frobenius inner product `Σ aᵢⱼ bᵢⱼ`.
+    pub fn double_dot(&self, other: &Self) -> S {
+        (0..D).map(|i| self.row(i).dot(&other.row(i))).sum()
+    }
+}
+
+impl<const D: usize, S: Scalar> GridValue for Matrix<D, S> {
+    type Scalar = S;
+
+    fn all_finite(&self) -> bool {
+        self.0.iter().flatten().all(|v| v.is_finite())
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::Index<(usize, usize)> for Matrix<D, S> {
+    type Output = S;
+
+    fn index(&self, (row, column): (usize, usize)) -> &S {
+        &self.0[row][column]
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::IndexMut<(usize, usize)> for Matrix<D, S> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut S {
+        &mut self.0[row][column]
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::Add for Matrix<D, S> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Matrix(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.0[i][j] + other.0[i][j])
+        }))
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::Sub for Matrix<D, S> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Matrix(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.0[i][j] - other.0[i][j])
+        }))
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::Neg for Matrix<D, S> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Matrix(self.0.map(|row| row.map(|v| -v)))
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::Mul<S> for Matrix<D, S> {
+    type Output = Self;
+
+    fn mul(self, scalar: S) -> Self {
+        Matrix(self.0.map(|row| row.map(|v| v * scalar)))
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::Div<S> for Matrix<D, S> {
+    type Output = Self;
+
+    fn div(self, scalar: S) -> Self {
+        Matrix(self.0.map(|row| row.map(|v| v / scalar)))
+    }
+}
+
+macro_rules! impl_scalar_matrix_mul {
+    ($t:ty) => {
+        impl<const D: usize> std::ops::Mul<Matrix<D, $t>> for $t {
+            type Output = Matrix<D, $t>;
+
+            fn mul(self, matrix: Matrix<D, $t>) -> Matrix<D, $t> {
+                matrix * self
+            }
+        }
+    };
+}
+
+impl_scalar_matrix_mul!(f32);
+impl_scalar_matrix_mul!(f64);
+
+impl<const D: usize, S: Scalar> std::ops::Mul for Matrix<D, S> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Matrix(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.row(i).dot(&other.column(j)))
+        }))
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::Mul<Vector<D, S>> for Matrix<D, S> {
+    type Output = Vector<D, S>;
+
+    fn mul(self, vector: Vector<D, S>) -> Vector<D, S> {
+        Vector(std::array::from_fn(|i| self.row(i).dot(&vector)))
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::AddAssign for Matrix<D, S> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::SubAssign for Matrix<D, S> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const D: usize, S: Scalar> std::cmp::PartialEq for Matrix<D, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl<const D: usize, S: Scalar> AbsDiffEq for Matrix<D, S> {
+    type Epsilon = S;
+
+    fn default_epsilon() -> Self::Epsilon {
+        S::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        (0..D).all(|i| self.row(i).abs_diff_eq(&other.row(i), epsilon))
+    }
+}
+
+impl<const D: usize, S: Scalar> RelativeEq for Matrix<D, S> {
+    fn default_max_relative() -> Self::Epsilon {
+        S::default_max_relative()
+    }
+
+    fn relative_eq(
+        &self,
+        other: &Self,
+        epsilon: Self::Epsilon,
+        max_relative: Self::Epsilon,
+    ) -> bool {
+        (0..D).all(|i| {
+            self.row(i)
+                .relative_eq(&other.row(i), epsilon, max_relative)
+        })
+    }
+}
+
+impl<const D: usize, S: Scalar> UlpsEq for Matrix<D, S> {
+    fn default_max_ulps() -> u32 {
+        S::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: Self::Epsilon, max_ulps: u32) -> bool {
+        (0..D).all(|i| self.row(i).ulps_eq(&other.row(i), epsilon, max_ulps))
+    }
+}
+
+impl<S: Scalar, const D: usize> Grid<Vector<D, S>, D> {
+    /// Velocity gradient `J[i][j] = ∂uᵢ/∂xⱼ` at one cell.
+    pub fn jacobian_at(&self, coord: CoordInt<D>) -> Matrix<D, S> {
+        Matrix(std::array::from_fn(|i| {
+            std::array::from_fn(|j| self.component_derivative(coord, i, j))
+        }))
+    }
+
+    /// Velocity gradient of every cell.
+    pub fn jacobian(&self) -> Grid<Matrix<D, S>, D> {
+        let mut out = Grid::new(self.size(), self.delta());
+        for (coord, value) in out.indexed_iter_mut() {
+            *value = self.jacobian_at(coord);
+        }
+        out
+    }
+}
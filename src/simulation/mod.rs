@@ -1,4 +1,24 @@
+mod algebra;
+pub mod batch;
+pub mod boundary;
+pub mod error;
 pub mod grid;
+pub mod io;
+pub mod iter;
+pub mod matrix;
+pub mod parallel;
+pub mod scalar;
+pub mod scene;
+#[cfg(feature = "serde")]
+mod serialize;
+#[allow(clippy::module_inception)]
+pub mod simulation;
+pub mod solver;
+pub mod sparse_grid;
+pub mod stencil;
+#[allow(clippy::module_inception)]
 mod tests;
+pub mod validation;
 pub mod vector;
-pub mod simulation;
+pub mod vector_field;
+pub mod view;
@@ -0,0 +1,103 @@
+//! Whole-grid operators evaluated on several threads.
+//!
+//! The grid is cut into slabs along its first axis, which are contiguous in
+//! memory, and each thread fills the matching slab of a preallocated output
+//! grid. Every cell is computed by the same per-cell operator as the serial
+//! path, so results do not depend on the thread count.
+
+use std::{num::NonZeroUsize, thread};
+
+use super::{
+    grid::{unflatten, CoordInt, Grid},
+    scalar::{GridValue, Scalar},
+    vector::Vector,
+};
+
+/// Number of threads the platform suggests, falling back to one.
+pub fn available_threads() -> usize {
+    thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1)
+}
+
+/// Fills `out` with `f(coord)` for every cell, splitting the work over `threads` threads.
+///
+/// Panics if `out` does not have the same size as `size`.
+pub fn fill_parallel<U, F, const D: usize>(
+    out: &mut Grid<U, D>,
+    size: CoordInt<D>,
+    threads: usize,
+    f: F,
+) where
+    U: Default + Clone + Send,
+    F: Fn(CoordInt<D>) -> U + Sync,
+{
+    assert_eq!(out.size(), size, "output grid size mismatch");
+    let len = out.as_slice().len();
+    if len == 0 {
+        return;
+    }
+
+    let rows = size.0.first().copied().unwrap_or(1).max(1) as usize;
+    let row_len = len / rows;
+    let rows_per_slab = rows.div_ceil(threads.clamp(1, rows));
+
+    let f = &f;
+    thread::scope(|scope| {
+        for (slab, chunk) in out
+            .as_mut_slice()
+            .chunks_mut(rows_per_slab * row_len)
+            .enumerate()
+        {
+            let offset = slab * rows_per_slab * row_len;
+            scope.spawn(move || {
+                for (i, cell) in chunk.iter_mut().enumerate() {
+                    *cell = f(unflatten(&size, offset + i));
+                }
+            });
+        }
+    });
+}
+
+impl<S: Scalar, const D: usize> Grid<S, D> {
+    pub fn gradient_field(&self, out: &mut Grid<Vector<D, S>, D>, threads: usize) {
+        fill_parallel(out, self.size(), threads, |coord| self.gradient(coord));
+    }
+}
+
+impl<S: Scalar, const D: usize> Grid<Vector<D, S>, D> {
+    pub fn divergence_field(&self, out: &mut Grid<S, D>, threads: usize) {
+        fill_parallel(out, self.size(), threads, |coord| self.divergence(coord));
+    }
+}
+
+impl<S: Scalar> Grid<Vector<2, S>, 2> {
+    pub fn curl_field(&self, out: &mut Grid<S, 2>, threads: usize) {
+        fill_parallel(out, self.size(), threads, |coord| self.curl(coord));
+    }
+}
+
+impl<S: Scalar> Grid<Vector<3, S>, 3> {
+    pub fn curl_field(&self, out: &mut Grid<Vector<3, S>, 3>, threads: usize) {
+        fill_parallel(out, self.size(), threads, |coord| self.curl(coord));
+    }
+}
+
+impl<T: GridValue + Send + Sync, const D: usize> Grid<T, D> {
+    pub fn laplace_field(&self, out: &mut Grid<T, D>, threads: usize) {
+        fill_parallel(out, self.size(), threads, |coord| self.laplace(coord));
+    }
+
+    pub fn advect_field(
+        &self,
+        velocity: &Grid<Vector<D, T::Scalar>, D>,
+        dt: T::Scalar,
+        out: &mut Grid<T, D>,
+        threads: usize,
+    ) {
+        assert_eq!(velocity.size(), self.size(), "velocity grid size mismatch");
+        fill_parallel(out, self.size(), threads, |coord| {
+            self.advect(velocity, coord, dt)
+        });
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    fmt::Debug,
+    iter::{Product, Sum},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use approx::{AbsDiffEq, RelativeEq, UlpsEq};
+
+/// Floating-point type the simulation computes in.
+///
+/// Implemented for `f32`, for speed, and `f64`, for validation runs.
+pub trait Scalar:
+    Copy
+    + Default
+    + Debug
+    + PartialOrd
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Sum
+    + Product
+    + AbsDiffEq<Epsilon = Self>
+    + RelativeEq
+    + UlpsEq
+    + GridValue<Scalar = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn floor(self) -> Self;
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn is_nan(self) -> bool;
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_scalar {
+    ($t:ty) => {
+        impl Scalar for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn floor(self) -> Self {
+                <$t>::floor(self)
+            }
+
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+
+            fn max(self, other: Self) -> Self {
+                <$t>::max(self, other)
+            }
+
+            fn min(self, other: Self) -> Self {
+                <$t>::min(self, other)
+            }
+
+            fn is_nan(self) -> bool {
+                <$t>::is_nan(self)
+            }
+
+            fn is_finite(self) -> bool {
+                <$t>::is_finite(self)
+            }
+        }
+
+        impl GridValue for $t {
+            type Scalar = $t;
+
+            fn all_finite(&self) -> bool {
+                <$t>::is_finite(*self)
+            }
+        }
+    };
+}
+
+impl_scalar!(f32);
+impl_scalar!(f64);
+
+/// A cell value that can be interpolated and differentiated: a scalar, or a
+/// vector-like type scaled by one.
+pub trait GridValue:
+    Default
+    + Clone
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Self::Scalar, Output = Self>
+    + Div<Self::Scalar, Output = Self>
+{
+    type Scalar: Scalar;
+
+    /// Whether every component is neither NaN nor infinite.
+    fn all_finite(&self) -> bool;
+}
@@ -0,0 +1,802 @@
+//! Scene files: a TOML description of the domain, fluid, boundaries,
+//! obstacles, emitters, forces, solver and output schedule of a simulation.
+//!
+//! ```toml
+//! dimensions = 2
+//! size = [64, 64]
+//! spacing = 0.015625
+//!
+//! [fluid]
+//! dt = 0.01
+//! viscosity = 0.001
+//!
+//! [solver]
+//! pressure = "gauss-seidel"
+//! iterations = 40
+//!
+//! [boundary]
+//! x = "periodic"
+//! "y+" = { moving = [1.0, 0.0] }
+//!
+//! [[obstacle]]
+//! shape = "sphere"
+//! centre = [0.5, 0.5]
+//! radius = 0.1
+//!
+//! [[emitter]]
+//! shape = "box"
+//! min = [0.4, 0.0]
+//! max = [0.6, 0.1]
+//! density = 1.0
+//! fields = { temperature = 2.0 }
+//!
+//! [[force]]
+//! acceleration = [0.0, -0.5]
+//! field = "temperature"
+//!
+//! [run]
+//! end_time = 5.0
+//!
+//! [output]
+//! directory = "out"
+//! every = 10
+//! formats = ["png", "vti"]
+//! ```
+//!
+//! Positions and lengths are in world units, with cell `i` centred at
+//! `(i + 0.5) * spacing`. Faces are named by their axis, `x`, `y` or `z`, to
+//! set both at once, or as `x-` and `x+` for the lower and upper face alone;
+//! unnamed faces are no-slip walls. Relative paths are resolved against the
+//! directory of the scene file.
+//!
+//! Scene files are TOML 1.0 except for dates and times, which no key takes
+//! and which are rejected as syntax errors.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    boundary::Boundary,
+    error::SceneError,
+    grid::{CoordInt, Grid, Int},
+    io::{
+        image::{Channel, Image},
+        render::{Colormap, FrameSource},
+        toml::{self, Entry, Table, Value},
+    },
+    scalar::Scalar,
+    simulation::{Parameters, Simulation},
+    solver::PressureSolver,
+    vector::{Float, Vector},
+};
+
+const AXES: [&str; 3] = ["x", "y", "z"];
+
+/// A region of the domain.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape<const D: usize> {
+    /// Axis-aligned box between two opposite corners.
+    Box {
+        min: Vector<D>,
+        max: Vector<D>,
+    },
+    Sphere {
+        centre: Vector<D>,
+        radius: Float,
+    },
+}
+
+impl<const D: usize> Shape<D> {
+    pub fn contains(&self, point: &Vector<D>) -> bool {
+        match self {
+            Shape::Box { min, max } => (0..D).all(|i| min[i] <= point[i] && point[i] <= max[i]),
+            Shape::Sphere { centre, radius } => (*point - *centre).norm() <= *radius,
+        }
+    }
+
+    /// The cells whose centres lie inside the shape.
+    pub fn mask(&self, size: CoordInt<D>, spacing: Float) -> Grid<bool, D> {
+        let mut mask = Grid::new(size, spacing);
+        for (coord, inside) in mask.indexed_iter_mut() {
+            let centre = Vector(coord.0.map(|i| (i as Float + 0.5) * spacing));
+            *inside = self.contains(&centre);
+        }
+        mask
+    }
+}
+
+/// Solid cells of a scene.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Obstacle<const D: usize> {
+    Shape(Shape<D>),
+    /// The cells where `channel` of the image, stretched over the domain,
+    /// exceeds `threshold`. 2D scenes only.
+    Image {
+        image: Image,
+        channel: Channel,
+        threshold: Float,
+    },
+}
+
+/// Adds density and extra fields at a constant rate inside a shape, and
+/// optionally holds the velocity there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Emitter<const D: usize, S: Scalar = Float> {
+    pub shape: Shape<D>,
+    /// Density added per unit time.
+    pub density: S,
+    /// Extra fields added per unit time, by name.
+    pub fields: BTreeMap<String, S>,
+    pub velocity: Option<Vector<D, S>>,
+}
+
+/// A body force accelerating all fluid cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Force<const D: usize, S: Scalar = Float> {
+    pub acceleration: Vector<D, S>,
+    /// Scales the acceleration in each cell by `density` or an extra field,
+    /// as for buoyancy.
+    pub field: Option<String>,
+}
+
+/// When a batch run stops.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RunLength {
+    Steps(u64),
+    /// Simulated time to reach.
+    EndTime(Float),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A rendered frame; 2D scenes only.
+    Png,
+    /// VTK image data.
+    Vti,
+    /// NumPy archive of every field.
+    Npz,
+    Checkpoint,
+}
+
+/// What a batch run writes, and how often.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Output {
+    pub directory: PathBuf,
+    /// Steps between outputs.
+    pub every: u64,
+    pub formats: Vec<OutputFormat>,
+    /// The field drawn into PNG frames.
+    pub source: FrameSource,
+    pub colormap: Colormap,
+}
+
+/// A parsed scene file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scene<const D: usize, S: Scalar = Float> {
+    pub size: CoordInt<D>,
+    pub spacing: Float,
+    pub params: Parameters<S>,
+    /// Lower and upper face condition of each axis.
+    pub faces: [[Boundary<D, S>; 2]; D],
+    pub obstacles: Vec<Obstacle<D>>,
+    pub emitters: Vec<Emitter<D, S>>,
+    pub forces: Vec<Force<D, S>>,
+    pub run: Option<RunLength>,
+    pub output: Option<Output>,
+}
+
+/// The `dimensions` of a scene, to pick the `D` to parse it with.
+pub fn dimensions(text: &str) -> Result<usize, SceneError> {
+    let table = toml::parse(text).map_err(syntax_error)?;
+    let mut root = Reader::new(&table, String::new(), 1);
+    let dimensions = root.integer("dimensions")?;
+    match dimensions {
+        Some(d @ 1..=3) => Ok(d as usize),
+        Some(_) => Err(root.invalid("dimensions", "expected 1, 2 or 3")),
+        None => Err(root.missing("dimensions")),
+    }
+}
+
+fn syntax_error((line, message): toml::SyntaxError) -> SceneError {
+    SceneError::Syntax { line, message }
+}
+
+impl<const D: usize, S: Scalar> Scene<D, S> {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parses a scene, resolving relative paths against `base`.
+    pub fn parse(text: &str, base: &Path) -> Result<Self, SceneError> {
+        let table = toml::parse(text).map_err(syntax_error)?;
+        let mut root = Reader::new(&table, String::new(), 1);
+
+        let dimensions = root.integer("dimensions")?;
+        match dimensions {
+            Some(d) if d == D as i64 => {}
+            Some(d) => return Err(root.invalid("dimensions", format!("expected {D}, found {d}"))),
+            None => return Err(root.missing("dimensions")),
+        }
+        let size = root
+            .array("size", D, |v| match v {
+                Value::Integer(n) if *n > 0 && *n <= Int::MAX as i64 => Ok(*n as Int),
+                _ => Err("a positive integer"),
+            })?
+            .ok_or_else(|| root.missing("size"))?;
+        let size = CoordInt(size.try_into().unwrap());
+        let spacing = root
+            .float("spacing")?
+            .ok_or_else(|| root.missing("spacing"))?;
+        if !(spacing > 0.0 && spacing.is_finite()) {
+            return Err(root.invalid("spacing", "must be positive"));
+        }
+
+        let mut params = Parameters::default();
+        if let Some(mut fluid) = root.table("fluid")? {
+            if let Some(dt) = fluid.float("dt")? {
+                if !(dt > 0.0 && dt.is_finite()) {
+                    return Err(fluid.invalid("dt", "must be positive"));
+                }
+                params.dt = S::from_f64(dt);
+            }
+            for (key, value) in [
+                ("viscosity", &mut params.viscosity),
+                ("diffusion", &mut params.diffusion),
+            ] {
+                if let Some(v) = fluid.float(key)? {
+                    if !(v >= 0.0 && v.is_finite()) {
+                        return Err(fluid.invalid(key, "must not be negative"));
+                    }
+                    *value = S::from_f64(v);
+                }
+            }
+            fluid.finish()?;
+        }
+        if let Some(mut solver) = root.table("solver")? {
+            match solver.string("pressure")? {
+                Some("jacobi") => params.pressure_solver = PressureSolver::Jacobi,
+                Some("gauss-seidel") => params.pressure_solver = PressureSolver::GaussSeidel,
+                Some(other) => {
+                    return Err(solver.invalid(
+                        "pressure",
+                        format!("unknown solver `{other}`; expected `jacobi` or `gauss-seidel`"),
+                    ))
+                }
+                None => {}
+            }
+            if let Some(iterations) = solver.integer("iterations")? {
+                if !(1..=u32::MAX as i64).contains(&iterations) {
+                    return Err(solver.invalid("iterations", "must be a positive integer"));
+                }
+                params.solver_iterations = iterations as u32;
+            }
+            solver.finish()?;
+        }
+
+        let mut faces = [[Boundary::NoSlip; 2]; D];
+        if let Some(mut boundary) = root.table("boundary")? {
+            // keys sort axis first (`x` < `x+` < `x-`), so single faces win
+            let mut set_by = [[None; 2]; D];
+            for (key, entry) in boundary.entries() {
+                let (name, suffix) = key.split_at(key.len().min(1));
+                let axis = AXES[..D].iter().position(|&a| a == name);
+                let sides: &[usize] = match suffix {
+                    "" => &[0, 1],
+                    "-" => &[0],
+                    "+" => &[1],
+                    _ => &[],
+                };
+                let Some(axis) = axis.filter(|_| !sides.is_empty()) else {
+                    let names = AXES[..D].join("`, `");
+                    return Err(boundary.invalid(
+                        key,
+                        format!("unknown face; expected `{names}`, or one with `-` or `+`"),
+                    ));
+                };
+                let condition = boundary.boundary(key, entry)?;
+                for &side in sides {
+                    faces[axis][side] = condition;
+                    set_by[axis][side] = Some(key);
+                }
+            }
+            for axis in 0..D {
+                let periodic = faces[axis].map(|face| face == Boundary::Periodic);
+                if periodic[0] != periodic[1] {
+                    // blame the single face that broke the pair
+                    let key = set_by[axis]
+                        .into_iter()
+                        .flatten()
+                        .find(|key: &&str| key.len() == 2)
+                        .unwrap_or(AXES[axis]);
+                    return Err(boundary.invalid(key, "periodic faces must come in pairs"));
+                }
+            }
+            boundary.finish()?;
+        }
+
+        let mut obstacles = Vec::new();
+        for mut obstacle in root.tables("obstacle")? {
+            obstacles.push(match obstacle.string("shape")? {
+                Some("image") => {
+                    if D != 2 {
+                        return Err(obstacle.invalid("shape", "image masks need a 2D scene"));
+                    }
+                    let path = obstacle
+                        .string("path")?
+                        .ok_or_else(|| obstacle.missing("path"))?;
+                    let image = Image::load(base.join(path))
+                        .map_err(|error| obstacle.invalid("path", error.to_string()))?;
+                    let channel = match obstacle.string("channel")? {
+                        None | Some("luminance") => Channel::Luminance,
+                        Some("red") => Channel::Red,
+                        Some("green") => Channel::Green,
+                        Some("blue") => Channel::Blue,
+                        Some("alpha") => Channel::Alpha,
+                        Some(other) => {
+                            return Err(
+                                obstacle.invalid("channel", format!("unknown channel `{other}`"))
+                            )
+                        }
+                    };
+                    let threshold = obstacle.float("threshold")?.unwrap_or(0.5);
+                    Obstacle::Image {
+                        image,
+                        channel,
+                        threshold,
+                    }
+                }
+                _ => Obstacle::Shape(obstacle.shape()?),
+            });
+            obstacle.finish()?;
+        }
+
+        let mut emitters = Vec::new();
+        for mut emitter in root.tables("emitter")? {
+            let shape = emitter.shape()?;
+            let density = emitter.float("density")?.unwrap_or(0.0);
+            if !density.is_finite() {
+                return Err(emitter.invalid("density", "must be finite"));
+            }
+            let velocity = emitter.vector("velocity")?.map(cast);
+            let mut fields = BTreeMap::new();
+            if let Some(mut rates) = emitter.table("fields")? {
+                for (name, _) in rates.entries() {
+                    let rate = rates.float(name)?.unwrap();
+                    if !rate.is_finite() {
+                        return Err(rates.invalid(name, "must be finite"));
+                    }
+                    fields.insert(name.to_string(), S::from_f64(rate));
+                }
+            }
+            emitter.finish()?;
+            emitters.push(Emitter {
+                shape,
+                density: S::from_f64(density),
+                fields,
+                velocity,
+            });
+        }
+
+        let mut forces = Vec::new();
+        for mut force in root.tables("force")? {
+            let acceleration = force
+                .vector("acceleration")?
+                .ok_or_else(|| force.missing("acceleration"))?;
+            let field = force.string("field")?;
+            if let Some(name) = field {
+                if name != "density" && !emitters.iter().any(|e| e.fields.contains_key(name)) {
+                    return Err(
+                        force.invalid("field", format!("no emitter adds a field named `{name}`"))
+                    );
+                }
+            }
+            force.finish()?;
+            forces.push(Force {
+                acceleration: cast(acceleration),
+                field: field.map(str::to_string),
+            });
+        }
+
+        let mut run = None;
+        if let Some(mut length) = root.table("run")? {
+            let steps = length.integer("steps")?;
+            let end_time = length.float("end_time")?;
+            run = Some(match (steps, end_time) {
+                (Some(steps), None) if steps > 0 => RunLength::Steps(steps as u64),
+                (Some(_), None) => return Err(length.invalid("steps", "must be positive")),
+                (None, Some(t)) if t > 0.0 && t.is_finite() => RunLength::EndTime(t),
+                (None, Some(_)) => return Err(length.invalid("end_time", "must be positive")),
+                (Some(_), Some(_)) => {
+                    return Err(length.invalid("end_time", "conflicts with `steps`"))
+                }
+                (None, None) => return Err(length.missing("steps")),
+            });
+            length.finish()?;
+        }
+
+        let mut output = None;
+        if let Some(mut out) = root.table("output")? {
+            let directory = base.join(out.string("directory")?.unwrap_or("output"));
+            let every = out.integer("every")?.unwrap_or(1);
+            if every < 1 {
+                return Err(out.invalid("every", "must be positive"));
+            }
+            let formats = out
+                .array("formats", 0, |v| match v {
+                    Value::String(s) if s == "png" => Ok(OutputFormat::Png),
+                    Value::String(s) if s == "vti" => Ok(OutputFormat::Vti),
+                    Value::String(s) if s == "npz" => Ok(OutputFormat::Npz),
+                    Value::String(s) if s == "checkpoint" => Ok(OutputFormat::Checkpoint),
+                    _ => Err("one of `png`, `vti`, `npz` or `checkpoint`"),
+                })?
+                .ok_or_else(|| out.missing("formats"))?;
+            if D != 2 && formats.contains(&OutputFormat::Png) {
+                return Err(out.invalid("formats", "`png` output needs a 2D scene"));
+            }
+            let source = match out.string("field")?.unwrap_or("density") {
+                "density" => FrameSource::Density,
+                "pressure" => FrameSource::Pressure,
+                "speed" => FrameSource::Speed,
+                name => FrameSource::Field(name.to_string()),
+            };
+            let colormap = match out.string("colormap")? {
+                Some("grayscale") => Colormap::Grayscale,
+                None | Some("viridis") => Colormap::Viridis,
+                Some("inferno") => Colormap::Inferno,
+                Some("coolwarm") => Colormap::Coolwarm,
+                Some(other) => {
+                    return Err(out.invalid("colormap", format!("unknown colormap `{other}`")))
+                }
+            };
+            out.finish()?;
+            output = Some(Output {
+                directory,
+                every: every as u64,
+                formats,
+                source,
+                colormap,
+            });
+        }
+        root.finish()?;
+
+        Ok(Scene {
+            size,
+            spacing,
+            params,
+            faces,
+            obstacles,
+            emitters,
+            forces,
+            run,
+            output,
+        })
+    }
+
+    /// A fluid at rest in the scene's domain, with its parameters, boundaries
+    /// and obstacles, and an empty extra field for each one an emitter adds.
+    pub fn simulation(&self) -> Simulation<D, S> {
+        let mut sim = Simulation::new(self.size, self.spacing);
+        sim.params = self.params;
+        sim.boundary.faces = self.faces;
+        for obstacle in &self.obstacles {
+            let mask = match obstacle {
+                Obstacle::Shape(shape) => shape.mask(self.size, self.spacing),
+                Obstacle::Image {
+                    image,
+                    channel,
+                    threshold,
+                } => {
+                    let size = CoordInt([self.size.0[0], self.size.0[1]]);
+                    let mask = image.to_mask(*channel, *threshold, size, self.spacing);
+                    Grid::from_vec(self.size, self.spacing, mask.as_slice().to_vec()).unwrap()
+                }
+            };
+            for (solid, &inside) in sim
+                .boundary
+                .obstacles
+                .as_mut_slice()
+                .iter_mut()
+                .zip(mask.as_slice())
+            {
+                *solid |= inside;
+            }
+        }
+        for name in self.emitters.iter().flat_map(|e| e.fields.keys()) {
+            sim.fields
+                .entry(name.clone())
+                .or_insert_with(|| Grid::new(self.size, self.spacing));
+        }
+        sim
+    }
+
+    /// Runs the emitters and forces over one time step `params.dt`. Solid
+    /// cells are left alone.
+    pub fn apply_sources(&self, sim: &mut Simulation<D, S>) {
+        let dt = sim.params.dt;
+        let solid = sim.boundary.obstacles.as_slice();
+        for emitter in &self.emitters {
+            let mask = emitter.shape.mask(sim.size(), sim.delta());
+            let cells = mask.as_slice().iter().enumerate();
+            for (i, _) in cells.filter(|&(i, &inside)| inside && !solid[i]) {
+                sim.densities.as_mut_slice()[i] += emitter.density * dt;
+                for (name, &rate) in &emitter.fields {
+                    if let Some(field) = sim.fields.get_mut(name) {
+                        field.as_mut_slice()[i] += rate * dt;
+                    }
+                }
+                if let Some(velocity) = emitter.velocity {
+                    sim.velocities.as_mut_slice()[i] = velocity;
+                }
+            }
+        }
+        for force in &self.forces {
+            let scale = match force.field.as_deref() {
+                None => None,
+                Some("density") => Some(sim.densities.as_slice()),
+                Some(name) => match sim.fields.get(name) {
+                    Some(field) => Some(field.as_slice()),
+                    None => continue,
+                },
+            };
+            let velocities = sim.velocities.as_mut_slice().iter_mut().enumerate();
+            for (i, velocity) in velocities.filter(|&(i, _)| !solid[i]) {
+                let s = scale.map_or(S::ONE, |field| field[i]);
+                *velocity += force.acceleration * (s * dt);
+            }
+        }
+    }
+
+    /// Applies the sources, then advances `sim` by one step.
+    pub fn step(&self, sim: &mut Simulation<D, S>) {
+        self.apply_sources(sim);
+        sim.step();
+    }
+}
+
+fn cast<const D: usize, S: Scalar>(v: Vector<D>) -> Vector<D, S> {
+    Vector(v.0.map(S::from_f64))
+}
+
+/// Typed access to one table of a scene, recording which keys were read so
+/// that misspelt ones are reported.
+struct Reader<'a> {
+    table: &'a Table,
+    /// Dotted path of the table, empty at the root.
+    path: String,
+    line: usize,
+    used: Vec<&'a str>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(table: &'a Table, path: String, line: usize) -> Self {
+        Reader {
+            table,
+            path,
+            line,
+            used: Vec::new(),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{key}", self.path)
+        }
+    }
+
+    fn invalid(&self, key: &str, reason: impl Into<String>) -> SceneError {
+        SceneError::Invalid {
+            key: self.key(key),
+            line: self.table.get(key).map_or(self.line, |entry| entry.line),
+            reason: reason.into(),
+        }
+    }
+
+    fn missing(&self, key: &str) -> SceneError {
+        self.invalid(key, "missing key")
+    }
+
+    fn get(&mut self, key: &str) -> Option<&'a Entry> {
+        let (key, entry) = self.table.get_key_value(key)?;
+        self.used.push(key);
+        Some(entry)
+    }
+
+    /// Every key of the table, all marked as read.
+    fn entries(&mut self) -> Vec<(&'a str, &'a Entry)> {
+        let table = self.table;
+        table
+            .iter()
+            .map(|(key, entry)| {
+                self.used.push(key);
+                (key.as_str(), entry)
+            })
+            .collect()
+    }
+
+    fn expected(&self, key: &str, expected: &str, found: &Value) -> SceneError {
+        self.invalid(
+            key,
+            format!("expected {expected}, found {}", found.type_name()),
+        )
+    }
+
+    fn float(&mut self, key: &str) -> Result<Option<Float>, SceneError> {
+        match self.get(key).map(|entry| &entry.value) {
+            None => Ok(None),
+            Some(Value::Float(v)) => Ok(Some(*v)),
+            Some(Value::Integer(v)) => Ok(Some(*v as Float)),
+            Some(other) => Err(self.expected(key, "a number", other)),
+        }
+    }
+
+    fn integer(&mut self, key: &str) -> Result<Option<i64>, SceneError> {
+        match self.get(key).map(|entry| &entry.value) {
+            None => Ok(None),
+            Some(Value::Integer(v)) => Ok(Some(*v)),
+            Some(other) => Err(self.expected(key, "an integer", other)),
+        }
+    }
+
+    fn string(&mut self, key: &str) -> Result<Option<&'a str>, SceneError> {
+        match self.get(key).map(|entry| &entry.value) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(other) => Err(self.expected(key, "a string", other)),
+        }
+    }
+
+    /// An array of `len` elements, or of any length if `len` is zero.
+    fn array<T>(
+        &mut self,
+        key: &str,
+        len: usize,
+        element: impl Fn(&Value) -> Result<T, &'static str>,
+    ) -> Result<Option<Vec<T>>, SceneError> {
+        let items = match self.get(key).map(|entry| &entry.value) {
+            None => return Ok(None),
+            Some(Value::Array(items)) => items,
+            Some(other) => return Err(self.expected(key, "an array", other)),
+        };
+        if len > 0 && items.len() != len {
+            return Err(self.invalid(
+                key,
+                format!("expected {len} elements, found {}", items.len()),
+            ));
+        }
+        let values = items.iter().enumerate().map(|(i, item)| {
+            element(&item.value).map_err(|expected| SceneError::Invalid {
+                key: format!("{}[{i}]", self.key(key)),
+                line: item.line,
+                reason: format!("expected {expected}"),
+            })
+        });
+        values.collect::<Result<_, _>>().map(Some)
+    }
+
+    /// An array of `D` finite numbers.
+    fn vector<const D: usize>(&mut self, key: &str) -> Result<Option<Vector<D>>, SceneError> {
+        let values = self.array(key, D, |v| match v {
+            Value::Float(v) if v.is_finite() => Ok(*v),
+            Value::Integer(v) => Ok(*v as Float),
+            _ => Err("a finite number"),
+        })?;
+        Ok(values.map(|v| Vector(v.try_into().unwrap())))
+    }
+
+    fn table(&mut self, key: &str) -> Result<Option<Reader<'a>>, SceneError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Entry {
+                line,
+                value: Value::Table(table),
+            }) => Ok(Some(Reader::new(table, self.key(key), *line))),
+            Some(entry) => Err(self.expected(key, "a table", &entry.value)),
+        }
+    }
+
+    /// The tables of a `[[key]]` array, named `key[0]`, `key[1]`, ...
+    fn tables(&mut self, key: &str) -> Result<Vec<Reader<'a>>, SceneError> {
+        let items = match self.get(key).map(|entry| &entry.value) {
+            None => return Ok(Vec::new()),
+            Some(Value::Array(items)) => items,
+            Some(other) => return Err(self.expected(key, "an array of tables", other)),
+        };
+        let tables = items.iter().enumerate().map(|(i, item)| match &item.value {
+            Value::Table(table) => Ok(Reader::new(
+                table,
+                format!("{}[{i}]", self.key(key)),
+                item.line,
+            )),
+            other => Err(self.expected(key, "an array of tables", other)),
+        });
+        tables.collect()
+    }
+
+    /// A `box` or `sphere` given by `shape` and its parameters.
+    fn shape<const D: usize>(&mut self) -> Result<Shape<D>, SceneError> {
+        match self.string("shape")? {
+            Some("box") => {
+                let min = self.vector("min")?.ok_or_else(|| self.missing("min"))?;
+                let max = self.vector("max")?.ok_or_else(|| self.missing("max"))?;
+                if (0..D).any(|i| min[i] >= max[i]) {
+                    return Err(self.invalid("max", "must exceed `min` on every axis"));
+                }
+                Ok(Shape::Box { min, max })
+            }
+            Some("sphere") => {
+                let centre = self
+                    .vector("centre")?
+                    .ok_or_else(|| self.missing("centre"))?;
+                let radius = self
+                    .float("radius")?
+                    .ok_or_else(|| self.missing("radius"))?;
+                if !(radius > 0.0 && radius.is_finite()) {
+                    return Err(self.invalid("radius", "must be positive"));
+                }
+                Ok(Shape::Sphere { centre, radius })
+            }
+            Some(other) => Err(self.invalid(
+                "shape",
+                format!("unknown shape `{other}`; expected `box` or `sphere`"),
+            )),
+            None => Err(self.missing("shape")),
+        }
+    }
+
+    /// A face condition: a name, or `{ moving = [...] }` or `{ inflow = [...] }`.
+    fn boundary<const D: usize, S: Scalar>(
+        &mut self,
+        key: &str,
+        entry: &'a Entry,
+    ) -> Result<Boundary<D, S>, SceneError> {
+        match &entry.value {
+            Value::String(name) => match name.as_str() {
+                "no-slip" => Ok(Boundary::NoSlip),
+                "free-slip" => Ok(Boundary::FreeSlip),
+                "outflow" => Ok(Boundary::Outflow),
+                "periodic" => Ok(Boundary::Periodic),
+                "moving" | "inflow" => Err(self.invalid(
+                    key,
+                    format!("`{name}` needs a velocity, as in `{{ {name} = [...] }}`"),
+                )),
+                _ => Err(self.invalid(
+                    key,
+                    format!(
+                        "unknown boundary `{name}`; expected `no-slip`, `free-slip`, \
+                         `outflow`, `periodic`, `moving` or `inflow`"
+                    ),
+                )),
+            },
+            Value::Table(_) => {
+                let mut face = self.table(key)?.unwrap();
+                let moving = face.vector("moving")?;
+                let inflow = face.vector("inflow")?;
+                face.finish()?;
+                match (moving, inflow) {
+                    (Some(v), None) => Ok(Boundary::Moving(cast(v))),
+                    (None, Some(v)) => Ok(Boundary::Inflow(cast(v))),
+                    _ => Err(self.invalid(key, "expected one of `moving` or `inflow`")),
+                }
+            }
+            other => Err(self.expected(key, "a string or an inline table", other)),
+        }
+    }
+
+    /// Fails on the first key that was never read.
+    fn finish(self) -> Result<(), SceneError> {
+        match self
+            .table
+            .keys()
+            .find(|key| !self.used.contains(&key.as_str()))
+        {
+            Some(key) => Err(self.invalid(key, "unknown key")),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! `serde` support for the core types, enabled by the `serde` feature.
+//!
+//! serde only implements its traits for arrays of fixed lengths, so the
+//! const-generic wrappers are written by hand: `CoordInt` and `Vector` as
+//! tuples of `D` elements, `Matrix` as a tuple of rows, `Grid` as a struct
+//! of size, spacing and row-major data, and `BoundaryConfig` as a struct of
+//! per-axis face pairs and the obstacle mask.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, IgnoredAny, SeqAccess, Visitor},
+    ser::{SerializeStruct, SerializeTuple},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{
+    boundary::{Boundary, BoundaryConfig},
+    grid::{CoordInt, Grid},
+    matrix::Matrix,
+    scalar::Scalar,
+    vector::{Float, Vector},
+};
+
+fn serialize_array<T: Serialize, Ser: Serializer>(
+    values: &[T],
+    serializer: Ser,
+) -> Result<Ser::Ok, Ser::Error> {
+    let mut tuple = serializer.serialize_tuple(values.len())?;
+    for value in values {
+        tuple.serialize_element(value)?;
+    }
+    tuple.end()
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+    type Value = [T; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of {N} elements")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::with_capacity(N);
+        while values.len() < N {
+            match seq.next_element()? {
+                Some(value) => values.push(value),
+                None => return Err(de::Error::invalid_length(values.len(), &self)),
+            }
+        }
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(N + 1, &self));
+        }
+        Ok(values
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("exactly N elements were read")))
+    }
+}
+
+fn deserialize_array<'de, T: Deserialize<'de>, De: Deserializer<'de>, const N: usize>(
+    deserializer: De,
+) -> Result<[T; N], De::Error> {
+    deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
+
+impl<const D: usize> Serialize for CoordInt<D> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serialize_array(&self.0, serializer)
+    }
+}
+
+impl<'de, const D: usize> Deserialize<'de> for CoordInt<D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        deserialize_array(deserializer).map(CoordInt)
+    }
+}
+
+impl<const D: usize, S: Scalar + Serialize> Serialize for Vector<D, S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serialize_array(&self.0, serializer)
+    }
+}
+
+impl<'de, const D: usize, S: Scalar + Deserialize<'de>> Deserialize<'de> for Vector<D, S> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        deserialize_array(deserializer).map(Vector)
+    }
+}
+
+impl<const D: usize, S: Scalar + Serialize> Serialize for Matrix<D, S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serialize_array(&self.0.map(Vector), serializer)
+    }
+}
+
+impl<'de, const D: usize, S: Scalar + Deserialize<'de>> Deserialize<'de> for Matrix<D, S> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let rows: [Vector<D, S>; D] = deserialize_array(deserializer)?;
+        Ok(Matrix::from_rows(rows))
+    }
+}
+
+impl<T: Default + Clone + Serialize, const D: usize> Serialize for Grid<T, D> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let mut grid = serializer.serialize_struct("Grid", 3)?;
+        grid.serialize_field("size", &self.size())?;
+        grid.serialize_field("delta", &self.delta())?;
+        grid.serialize_field("data", self.as_slice())?;
+        grid.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Grid")]
+struct GridRepr<T, const D: usize> {
+    size: CoordInt<D>,
+    delta: Float,
+    data: Vec<T>,
+}
+
+impl<'de, T: Default + Clone + Deserialize<'de>, const D: usize> Deserialize<'de> for Grid<T, D> {
+    /// Fails if `data` does not hold exactly one value per cell of `size`.
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let repr = GridRepr::<T, D>::deserialize(deserializer)?;
+        Grid::from_vec(repr.size, repr.delta, repr.data).map_err(de::Error::custom)
+    }
+}
+
+impl<const D: usize, S: Scalar + Serialize> Serialize for BoundaryConfig<D, S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        struct Faces<'a, const D: usize, S: Scalar>(&'a [[Boundary<D, S>; 2]; D]);
+
+        impl<const D: usize, S: Scalar + Serialize> Serialize for Faces<'_, D, S> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                serialize_array(self.0, serializer)
+            }
+        }
+
+        let mut config = serializer.serialize_struct("BoundaryConfig", 2)?;
+        config.serialize_field("faces", &Faces(&self.faces))?;
+        config.serialize_field("obstacles", &self.obstacles)?;
+        config.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "BoundaryConfig")]
+#[serde(bound(deserialize = "S: Scalar + Deserialize<'de>"))]
+struct BoundaryConfigRepr<const D: usize, S: Scalar> {
+    #[serde(deserialize_with = "deserialize_array")]
+    faces: [[Boundary<D, S>; 2]; D],
+    obstacles: Grid<bool, D>,
+}
+
+impl<'de, const D: usize, S: Scalar + Deserialize<'de>> Deserialize<'de> for BoundaryConfig<D, S> {
+    /// Fails if only one face of an axis is periodic.
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let repr = BoundaryConfigRepr::<D, S>::deserialize(deserializer)?;
+        for (axis, [lower, upper]) in repr.faces.iter().enumerate() {
+            if (*lower == Boundary::Periodic) != (*upper == Boundary::Periodic) {
+                return Err(de::Error::custom(format_args!(
+                    "axis {axis} is periodic on one face only"
+                )));
+            }
+        }
+        Ok(BoundaryConfig {
+            faces: repr.faces,
+            obstacles: repr.obstacles,
+        })
+    }
+}
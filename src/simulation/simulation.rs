@@ -1,6 +1,144 @@
-use super::{grid::Grid, vector::{Float, Vector}};
+use std::collections::BTreeMap;
 
-struct Simulation<const D: usize>{
-    pub densities: Grid<Float, D>,
-    pub velocities: Grid<Vector<D>, D>,
-}
\ No newline at end of file
+use super::{
+    boundary::BoundaryConfig,
+    grid::{CoordInt, Grid},
+    scalar::Scalar,
+    solver::{advect, diffuse, project, PressureSolver},
+    vector::{Float, Vector},
+};
+
+/// Fluid properties and solver settings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parameters<S: Scalar = Float> {
+    /// Time step.
+    pub dt: S,
+    /// Kinematic viscosity of the fluid.
+    pub viscosity: S,
+    /// Diffusion coefficient of the density and extra fields.
+    pub diffusion: S,
+    pub pressure_solver: PressureSolver,
+    /// Sweeps per implicit solve, for pressure as well as diffusion.
+    pub solver_iterations: u32,
+}
+
+impl<S: Scalar> Default for Parameters<S> {
+    fn default() -> Self {
+        Parameters {
+            dt: S::from_f64(0.1),
+            viscosity: S::ZERO,
+            diffusion: S::ZERO,
+            pressure_solver: PressureSolver::default(),
+            solver_iterations: 40,
+        }
+    }
+}
+
+/// Simulation state, in single (`S = f32`) or double (`S = f64`) precision.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Simulation<const D: usize, S: Scalar = Float> {
+    pub densities: Grid<S, D>,
+    pub velocities: Grid<Vector<D, S>, D>,
+    /// Pressure of the last projection, reused as the next initial guess.
+    pub pressure: Grid<S, D>,
+    /// Further scalars carried along with the density, such as temperature.
+    pub fields: BTreeMap<String, Grid<S, D>>,
+    pub params: Parameters<S>,
+    pub boundary: BoundaryConfig<D, S>,
+    /// Number of steps taken so far.
+    pub steps: u64,
+    /// Simulated time so far.
+    pub time: Float,
+}
+
+impl<const D: usize, S: Scalar> Simulation<D, S> {
+    pub fn new(size: CoordInt<D>, delta: Float) -> Self {
+        Simulation {
+            densities: Grid::new(size, delta),
+            velocities: Grid::new(size, delta),
+            pressure: Grid::new(size, delta),
+            fields: BTreeMap::new(),
+            params: Parameters::default(),
+            boundary: BoundaryConfig::new(size, delta),
+            steps: 0,
+            time: 0.0,
+        }
+    }
+
+    pub fn size(&self) -> CoordInt<D> {
+        self.densities.size()
+    }
+
+    pub fn delta(&self) -> Float {
+        self.densities.delta()
+    }
+
+    /// Advances the state by one time step `params.dt`.
+    ///
+    /// The velocity is diffused, projected, self-advected and projected again;
+    /// the density and extra fields are then advected through it and diffused.
+    pub fn step(&mut self) {
+        let Parameters {
+            dt,
+            viscosity,
+            diffusion,
+            pressure_solver,
+            solver_iterations,
+        } = self.params;
+        let solver = (pressure_solver, solver_iterations);
+        let boundary = &self.boundary;
+
+        diffuse(
+            &mut self.velocities,
+            boundary,
+            viscosity * dt,
+            solver,
+            |u, coord, axis, step| boundary.ghost_velocity(u, coord, axis, step),
+        );
+        project(
+            &mut self.velocities,
+            &mut self.pressure,
+            boundary,
+            dt,
+            solver,
+        );
+        self.velocities = advect(&self.velocities, &self.velocities, boundary, dt);
+        project(
+            &mut self.velocities,
+            &mut self.pressure,
+            boundary,
+            dt,
+            solver,
+        );
+
+        let scalars = std::iter::once(&mut self.densities).chain(self.fields.values_mut());
+        for field in scalars {
+            *field = advect(field, &self.velocities, boundary, dt);
+            diffuse(
+                field,
+                boundary,
+                diffusion * dt,
+                solver,
+                |f, coord, axis, step| boundary.ghost_scalar(f, coord, axis, step),
+            );
+        }
+
+        self.steps += 1;
+        self.time += dt.to_f64();
+    }
+
+    /// Takes `steps` steps, calling `after_step` after each one, for example
+    /// to record frames. Stops at the first error `after_step` returns.
+    pub fn run<E>(
+        &mut self,
+        steps: u64,
+        mut after_step: impl FnMut(&Self) -> Result<(), E>,
+    ) -> Result<(), E> {
+        for _ in 0..steps {
+            self.step();
+            after_step(self)?;
+        }
+        Ok(())
+    }
+}
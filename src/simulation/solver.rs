@@ -0,0 +1,217 @@
+//! Kernels of the stable-fluids scheme (Stam, 1999) on a cell-centred grid:
+//! implicit diffusion, semi-Lagrangian advection and pressure projection.
+
+use super::{
+    boundary::BoundaryConfig,
+    grid::{unflatten, CoordInt, Grid, Int},
+    scalar::{GridValue, Scalar},
+    vector::Vector,
+};
+
+/// Iterative method for the implicit diffusion and pressure systems.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PressureSolver {
+    /// Updates every cell from the previous iterate; order independent.
+    Jacobi,
+    /// Updates cells in place, converging about twice as fast as Jacobi.
+    #[default]
+    GaussSeidel,
+}
+
+/// Runs `iterations` sweeps of `x = (rhs + a Σ neighbours) / centre` over the
+/// fluid cells, reading neighbours through `ghost`.
+pub(crate) fn relax<T, S, G, const D: usize>(
+    x: &mut Grid<T, D>,
+    rhs: &Grid<T, D>,
+    boundary: &BoundaryConfig<D, S>,
+    (a, centre): (S, S),
+    (method, iterations): (PressureSolver, u32),
+    ghost: G,
+) where
+    T: GridValue<Scalar = S>,
+    S: Scalar,
+    G: Fn(&Grid<T, D>, CoordInt<D>, usize, Int) -> T,
+{
+    let size = x.size();
+    let neighbours = |x: &Grid<T, D>, coord: CoordInt<D>| {
+        (0..D).fold(T::default(), |acc, axis| {
+            acc + ghost(x, coord, axis, -1) + ghost(x, coord, axis, 1)
+        })
+    };
+    for _ in 0..iterations {
+        match method {
+            PressureSolver::GaussSeidel => {
+                for index in 0..x.as_slice().len() {
+                    let coord = unflatten(&size, index);
+                    if boundary.is_solid(&coord) {
+                        continue;
+                    }
+                    let value = (rhs.as_slice()[index].clone() + neighbours(x, coord) * a) / centre;
+                    x.as_mut_slice()[index] = value;
+                }
+            }
+            PressureSolver::Jacobi => {
+                let mut next = Grid::new(size, x.delta());
+                for (index, (coord, value)) in next.indexed_iter_mut().enumerate() {
+                    *value = if boundary.is_solid(&coord) {
+                        x.as_slice()[index].clone()
+                    } else {
+                        (rhs.as_slice()[index].clone() + neighbours(x, coord) * a) / centre
+                    };
+                }
+                *x = next;
+            }
+        }
+    }
+}
+
+/// Solves `(1 - k dt ∇²) x = x₀` for a diffusion coefficient `k`.
+pub(crate) fn diffuse<T, S, G, const D: usize>(
+    field: &mut Grid<T, D>,
+    boundary: &BoundaryConfig<D, S>,
+    k_dt: S,
+    solver: (PressureSolver, u32),
+    ghost: G,
+) where
+    T: GridValue<Scalar = S>,
+    S: Scalar,
+    G: Fn(&Grid<T, D>, CoordInt<D>, usize, Int) -> T,
+{
+    if k_dt <= S::ZERO {
+        return;
+    }
+    let delta = S::from_f64(field.delta());
+    let a = k_dt / (delta * delta);
+    let centre = S::ONE + a * S::from_f64(2.0 * D as f64);
+    let rhs = field.map(T::clone);
+    relax(field, &rhs, boundary, (a, centre), solver, ghost);
+}
+
+/// Multilinear interpolation at `pos`, wrapping around periodic axes and
+/// clamping to the outermost cells on the others.
+pub(crate) fn sample<T, S, const D: usize>(
+    grid: &Grid<T, D>,
+    boundary: &BoundaryConfig<D, S>,
+    pos: &Vector<D, S>,
+) -> T
+where
+    T: GridValue<Scalar = S>,
+    S: Scalar,
+{
+    let size = grid.size();
+    let delta = S::from_f64(grid.delta());
+    let mut lower = CoordInt::<D>::default();
+    let mut upper = CoordInt::<D>::default();
+    let mut weights = [S::ZERO; D];
+    for (i, weight) in weights.iter_mut().enumerate() {
+        let n = size.0[i];
+        let c = pos.0[i] / delta;
+        if boundary.is_periodic(i) {
+            let floor = c.floor();
+            *weight = c - floor;
+            lower.0[i] = (floor.to_f64() as i64).rem_euclid(n as i64) as Int;
+            upper.0[i] = (lower.0[i] + 1) % n;
+        } else {
+            let c = c.max(S::ZERO).min(S::from_f64((n - 1) as f64));
+            lower.0[i] = (c.floor().to_f64() as Int).min(n - 2).max(0);
+            upper.0[i] = (lower.0[i] + 1).min(n - 1);
+            *weight = c - S::from_f64(lower.0[i] as f64);
+        }
+    }
+
+    let mut sum = T::default();
+    for corner in 0..1 << D {
+        let mut index = lower;
+        let mut weight = S::ONE;
+        for (j, w) in weights.iter().enumerate() {
+            if corner & (1 << j) != 0 {
+                index.0[j] = upper.0[j];
+                weight *= *w;
+            } else {
+                weight *= S::ONE - *w;
+            }
+        }
+        sum = sum + grid.as_slice()[grid.flatten_index(&index)].clone() * weight;
+    }
+    sum
+}
+
+/// Semi-Lagrangian advection of `field` through `velocity` over `dt`, tracing
+/// each departure point with a midpoint (second-order Runge–Kutta) step.
+pub(crate) fn advect<T, S, const D: usize>(
+    field: &Grid<T, D>,
+    velocity: &Grid<Vector<D, S>, D>,
+    boundary: &BoundaryConfig<D, S>,
+    dt: S,
+) -> Grid<T, D>
+where
+    T: GridValue<Scalar = S>,
+    S: Scalar,
+{
+    let delta = S::from_f64(field.delta());
+    let half = dt * S::from_f64(0.5);
+    let mut out = Grid::new(field.size(), field.delta());
+    for (index, (coord, value)) in out.indexed_iter_mut().enumerate() {
+        if boundary.is_solid(&coord) {
+            *value = field.as_slice()[index].clone();
+            continue;
+        }
+        let pos = Vector::from_coord_int(coord, delta);
+        let mid = pos - velocity.as_slice()[index] * half;
+        let departure = pos - sample(velocity, boundary, &mid) * dt;
+        *value = sample(field, boundary, &departure);
+    }
+    out
+}
+
+/// Makes `velocity` divergence free by subtracting `dt ∇p`, solving for the
+/// pressure `p` starting from its previous value.
+pub(crate) fn project<S: Scalar, const D: usize>(
+    velocity: &mut Grid<Vector<D, S>, D>,
+    pressure: &mut Grid<S, D>,
+    boundary: &BoundaryConfig<D, S>,
+    dt: S,
+    solver: (PressureSolver, u32),
+) {
+    let delta = S::from_f64(velocity.delta());
+    let two_delta = delta * S::from_f64(2.0);
+
+    // ∇²p = ∇·u / dt, written as p = (Σ neighbours - h² ∇·u / dt) / 2D
+    let mut rhs = Grid::new(velocity.size(), velocity.delta());
+    for (coord, value) in rhs.indexed_iter_mut() {
+        if boundary.is_solid(&coord) {
+            continue;
+        }
+        let divergence: S = (0..D)
+            .map(|axis| {
+                boundary.ghost_velocity(velocity, coord, axis, 1).0[axis]
+                    - boundary.ghost_velocity(velocity, coord, axis, -1).0[axis]
+            })
+            .sum::<S>()
+            / two_delta;
+        *value = -delta * delta * divergence / dt;
+    }
+    let centre = S::from_f64(2.0 * D as f64);
+    relax(
+        pressure,
+        &rhs,
+        boundary,
+        (S::ONE, centre),
+        solver,
+        |p, coord, axis, step| boundary.ghost_pressure(p, coord, axis, step),
+    );
+
+    for (coord, value) in velocity.indexed_iter_mut() {
+        if boundary.is_solid(&coord) {
+            *value = Vector::default();
+            continue;
+        }
+        for axis in 0..D {
+            let gradient = (boundary.ghost_pressure(pressure, coord, axis, 1)
+                - boundary.ghost_pressure(pressure, coord, axis, -1))
+                / two_delta;
+            value.0[axis] -= dt * gradient;
+        }
+    }
+}
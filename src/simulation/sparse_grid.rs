@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use super::{
+    grid::{interpolate, CoordInt, Grid, Int},
+    scalar::{GridValue, Scalar},
+    stencil::{first_derivative, Accuracy, Stencil},
+    vector::{Float, Vector},
+};
+
+/// Edge length of a tile, in cells, along every axis.
+pub const TILE_SIZE: Int = 8;
+
+fn tile_capacity<const D: usize>() -> usize {
+    (TILE_SIZE as usize).pow(D as u32)
+}
+
+/// A grid that only stores the tiles holding values other than its background.
+///
+/// The domain is split into `TILE_SIZE^D` tiles. Reading a cell of an
+/// unallocated tile yields the background value, writing to it allocates the
+/// tile. Tiles that become uniformly background are released by `set` and
+/// `prune`.
+pub struct SparseGrid<T, const D: usize> {
+    tiles: HashMap<CoordInt<D>, Vec<T>>,
+    size: CoordInt<D>,
+    delta: Float,
+    background: T,
+}
+
+impl<T: Default + Clone + PartialEq, const D: usize> SparseGrid<T, D> {
+    pub fn new(size: CoordInt<D>, delta: Float) -> Self {
+        Self::with_background(size, delta, T::default())
+    }
+
+    pub fn with_background(size: CoordInt<D>, delta: Float, background: T) -> Self {
+        SparseGrid {
+            tiles: HashMap::new(),
+            size,
+            delta,
+            background,
+        }
+    }
+
+    pub fn from_dense(grid: &Grid<T, D>, background: T) -> Self {
+        let mut sparse = Self::with_background(grid.size(), grid.delta(), background);
+        for (coord, value) in grid {
+            sparse.set(coord, value.clone());
+        }
+        sparse
+    }
+
+    pub fn to_dense(&self) -> Grid<T, D> {
+        let mut grid = Grid::filled(self.size, self.delta, self.background.clone());
+        for (coord, value) in self.active_cells() {
+            if let Some(cell) = grid.get_mut(&coord) {
+                *cell = value.clone();
+            }
+        }
+        grid
+    }
+
+    pub fn size(&self) -> CoordInt<D> {
+        self.size
+    }
+
+    pub fn delta(&self) -> Float {
+        self.delta
+    }
+
+    pub fn background(&self) -> &T {
+        &self.background
+    }
+
+    fn in_bounds(&self, index: &CoordInt<D>) -> bool {
+        index
+            .0
+            .iter()
+            .zip(self.size.0.iter())
+            .all(|(&i, &dim)| i >= 0 && i < dim)
+    }
+
+    fn split_index(index: &CoordInt<D>) -> (CoordInt<D>, usize) {
+        let tile = CoordInt(index.0.map(|i| i / TILE_SIZE));
+        let local = index.0.iter().fold(0, |acc, &i| {
+            acc * TILE_SIZE as usize + (i % TILE_SIZE) as usize
+        });
+        (tile, local)
+    }
+
+    pub fn get(&self, index: &CoordInt<D>) -> Option<&T> {
+        if !self.in_bounds(index) {
+            return None;
+        }
+        let (tile, local) = Self::split_index(index);
+        match self.tiles.get(&tile) {
+            Some(values) => values.get(local),
+            None => Some(&self.background),
+        }
+    }
+
+    /// Returns a mutable reference to the cell, allocating its tile if needed.
+    ///
+    /// Writing the background value through this reference does not release
+    /// the tile; call `prune` after bulk updates.
+    pub fn get_mut(&mut self, index: &CoordInt<D>) -> Option<&mut T> {
+        if !self.in_bounds(index) {
+            return None;
+        }
+        let (tile, local) = Self::split_index(index);
+        let background = &self.background;
+        self.tiles
+            .entry(tile)
+            .or_insert_with(|| vec![background.clone(); tile_capacity::<D>()])
+            .get_mut(local)
+    }
+
+    /// Writes a value, allocating or releasing the cell's tile as required.
+    pub fn set(&mut self, index: CoordInt<D>, value: T) {
+        if !self.in_bounds(&index) {
+            return;
+        }
+        let (tile, local) = Self::split_index(&index);
+        if value == self.background {
+            if let Some(values) = self.tiles.get_mut(&tile) {
+                values[local] = value;
+                if values.iter().all(|v| *v == self.background) {
+                    self.tiles.remove(&tile);
+                }
+            }
+        } else if let Some(cell) = self.get_mut(&index) {
+            *cell = value;
+        }
+    }
+
+    /// Releases every tile whose cells all hold the background value.
+    pub fn prune(&mut self) {
+        let background = &self.background;
+        self.tiles
+            .retain(|_, values| values.iter().any(|v| v != background));
+    }
+
+    pub fn active_tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Iterates over the allocated tiles as `(tile origin, values)` pairs.
+    ///
+    /// The origin is the coordinate of the tile's first cell; values are laid
+    /// out in the same row-major order as `Grid`.
+    pub fn active_tiles(&self) -> impl Iterator<Item = (CoordInt<D>, &[T])> {
+        self.tiles
+            .iter()
+            .map(|(tile, values)| (CoordInt(tile.0.map(|i| i * TILE_SIZE)), values.as_slice()))
+    }
+
+    /// Iterates over the in-bounds cells of the allocated tiles.
+    pub fn active_cells(&self) -> impl Iterator<Item = (CoordInt<D>, &T)> {
+        self.active_tiles().flat_map(move |(origin, values)| {
+            values.iter().enumerate().filter_map(move |(local, value)| {
+                let mut coord = origin;
+                let mut rest = local;
+                for i in (0..D).rev() {
+                    coord.0[i] += (rest % TILE_SIZE as usize) as Int;
+                    rest /= TILE_SIZE as usize;
+                }
+                self.in_bounds(&coord).then_some((coord, value))
+            })
+        })
+    }
+}
+
+impl<T: GridValue + PartialEq, const D: usize> SparseGrid<T, D> {
+    pub fn get_at(&self, pos: &Vector<D, T::Scalar>) -> T {
+        interpolate(self.size, self.delta, pos, |index| {
+            self.get(index).unwrap().clone()
+        })
+    }
+
+    pub fn advect(
+        &self,
+        velocity: &SparseGrid<Vector<D, T::Scalar>, D>,
+        coord: CoordInt<D>,
+        dt: T::Scalar,
+    ) -> T {
+        let velocity = velocity.get(&coord).expect("coord not in grid");
+        let new_pos =
+            Vector::from_coord_int(coord, T::Scalar::from_f64(self.delta)) - *velocity * dt;
+        self.get_at(&new_pos)
+    }
+
+    pub fn laplace(&self, coord: CoordInt<D>) -> T {
+        (0..D).fold(T::default(), |acc, axis| {
+            acc + Stencil::second_difference(axis).apply(self, coord)
+        })
+    }
+}
+
+impl<S: Scalar, const D: usize> SparseGrid<S, D> {
+    pub fn gradient(&self, coord: CoordInt<D>) -> Vector<D, S> {
+        Vector(std::array::from_fn(|axis| {
+            first_derivative(self, coord, axis, Accuracy::Second, |v: &S| *v)
+        }))
+    }
+}
+
+impl<S: Scalar, const D: usize> SparseGrid<Vector<D, S>, D> {
+    pub fn divergence(&self, coord: CoordInt<D>) -> S {
+        (0..D)
+            .map(|axis| {
+                first_derivative(self, coord, axis, Accuracy::Second, |v: &Vector<D, S>| {
+                    v.0[axis]
+                })
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,238 @@
+//! Finite-difference operators described as weighted cell offsets.
+
+use super::{
+    grid::{CoordInt, Int},
+    iter::CoordRange,
+    scalar::{GridValue, Scalar},
+    vector::Float,
+    view::GridRead,
+};
+
+/// A linear operator `Σ wₖ f(x + oₖ) / Δ^order` over `N` fixed offsets.
+///
+/// Weights are for unit spacing; `order` is the derivative order, so applying
+/// the stencil divides by the grid spacing raised to it. Stencils are plain
+/// arrays and cost nothing to build, so operators can construct them per cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stencil<const D: usize, const N: usize> {
+    taps: [(CoordInt<D>, Float); N],
+    order: i32,
+}
+
+impl<const D: usize, const N: usize> Stencil<D, N> {
+    pub fn new(taps: [(CoordInt<D>, Float); N], order: i32) -> Self {
+        Stencil { taps, order }
+    }
+
+    pub fn taps(&self) -> &[(CoordInt<D>, Float); N] {
+        &self.taps
+    }
+
+    pub fn order(&self) -> i32 {
+        self.order
+    }
+
+    /// Whether every tap around `coord` lies inside a grid of `size`.
+    pub fn fits(&self, size: CoordInt<D>, coord: CoordInt<D>) -> bool {
+        self.taps.iter().all(|(offset, _)| {
+            let tap = coord + *offset;
+            (0..D).all(|i| 0 <= tap.0[i] && tap.0[i] < size.0[i])
+        })
+    }
+
+    /// Applies the stencil at `coord`, reading taps outside the grid as the
+    /// centre value.
+    pub fn apply<T, G>(&self, grid: &G, coord: CoordInt<D>) -> T
+    where
+        T: GridValue,
+        G: GridRead<T, D>,
+    {
+        self.apply_map(grid, coord, T::clone)
+    }
+
+    /// Like `apply`, but on `map` of each cell, such as one vector component.
+    pub fn apply_map<T, U, G, F>(&self, grid: &G, coord: CoordInt<D>, map: F) -> U
+    where
+        U: GridValue,
+        G: GridRead<T, D>,
+        F: Fn(&T) -> U,
+    {
+        let centre = grid.get(&coord).expect("coord not in grid");
+        let sum = self
+            .taps
+            .iter()
+            .fold(U::default(), |acc, (offset, weight)| {
+                let value = grid.get(&(coord + *offset)).unwrap_or(centre);
+                acc + map(value) * U::Scalar::from_f64(*weight)
+            });
+        sum / U::Scalar::from_f64(grid.delta().powi(self.order))
+    }
+
+    /// Applies the stencil at `coord`, or returns `None` if a tap falls
+    /// outside the grid.
+    pub fn try_apply<T, G>(&self, grid: &G, coord: CoordInt<D>) -> Option<T>
+    where
+        T: GridValue,
+        G: GridRead<T, D>,
+    {
+        self.fits(grid.size(), coord)
+            .then(|| self.apply(grid, coord))
+    }
+}
+
+/// Accuracy order of the central differences used by the grid operators.
+///
+/// Whatever the order, cells too close to a wall for the central stencil use
+/// second-order one-sided differences instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Accuracy {
+    #[default]
+    Second,
+    Fourth,
+}
+
+/// First derivative of `map(cell)` along `axis` at `coord`.
+///
+/// Uses the central difference of the requested accuracy where it fits, the
+/// second-order central one next to a wall, and second-order one-sided
+/// differences on the wall itself. Axes two cells long fall back to a plain
+/// first-order difference and single-cell axes have zero derivative.
+///
+/// Panics if `coord` is outside the grid.
+pub fn first_derivative<T, U, G, F, const D: usize>(
+    grid: &G,
+    coord: CoordInt<D>,
+    axis: usize,
+    accuracy: Accuracy,
+    map: F,
+) -> U
+where
+    U: GridValue,
+    G: GridRead<T, D>,
+    F: Fn(&T) -> U,
+{
+    assert!(grid.get(&coord).is_some(), "coord not in grid");
+    let size = grid.size();
+    let fourth = Stencil::central_difference_4th(axis);
+    let central = Stencil::central_difference(axis);
+    let forward = Stencil::forward_difference(axis);
+    let backward = Stencil::backward_difference(axis);
+    if accuracy == Accuracy::Fourth && fourth.fits(size, coord) {
+        fourth.apply_map(grid, coord, map)
+    } else if central.fits(size, coord) {
+        central.apply_map(grid, coord, map)
+    } else if forward.fits(size, coord) {
+        forward.apply_map(grid, coord, map)
+    } else if backward.fits(size, coord) {
+        backward.apply_map(grid, coord, map)
+    } else if size.0[axis] == 2 {
+        let step = if coord.0[axis] == 0 { 1 } else { -1 };
+        let taps = [(0, -Float::from(step)), (step, Float::from(step))];
+        Stencil::new(axis_taps(axis, taps), 1).apply_map(grid, coord, map)
+    } else {
+        U::default()
+    }
+}
+
+/// Taps along a single axis, one per `(step, weight)` pair.
+fn axis_taps<const D: usize, const N: usize>(
+    axis: usize,
+    taps: [(Int, Float); N],
+) -> [(CoordInt<D>, Float); N] {
+    taps.map(|(step, weight)| (CoordInt::default().offset(axis, step), weight))
+}
+
+/// Taps over the whole `3^D` box, weighted by how many offset components are
+/// non-zero: `weights[0]` for the centre, `weights[1]` for faces and so on.
+fn box_taps<const D: usize, const N: usize>(weights: [Float; 4]) -> [(CoordInt<D>, Float); N] {
+    let mut offsets = CoordRange::new(CoordInt([-1; D]), CoordInt([2; D]));
+    std::array::from_fn(|_| {
+        let offset = offsets.next().expect("box has 3^D taps");
+        let rank = offset.0.iter().filter(|&&c| c != 0).count();
+        (offset, weights[rank])
+    })
+}
+
+impl<const D: usize> Stencil<D, 2> {
+    /// Second-order central first derivative along `axis`.
+    pub fn central_difference(axis: usize) -> Self {
+        Stencil::new(axis_taps(axis, [(-1, -0.5), (1, 0.5)]), 1)
+    }
+}
+
+impl<const D: usize> Stencil<D, 4> {
+    /// Fourth-order central first derivative along `axis`.
+    pub fn central_difference_4th(axis: usize) -> Self {
+        let taps = [
+            (-2, 1.0 / 12.0),
+            (-1, -8.0 / 12.0),
+            (1, 8.0 / 12.0),
+            (2, -1.0 / 12.0),
+        ];
+        Stencil::new(axis_taps(axis, taps), 1)
+    }
+}
+
+impl<const D: usize> Stencil<D, 3> {
+    /// Second-order one-sided first derivative along `axis`, reading the cell
+    /// and the two cells after it.
+    pub fn forward_difference(axis: usize) -> Self {
+        Stencil::new(axis_taps(axis, [(0, -1.5), (1, 2.0), (2, -0.5)]), 1)
+    }
+
+    /// Second-order one-sided first derivative along `axis`, reading the cell
+    /// and the two cells before it.
+    pub fn backward_difference(axis: usize) -> Self {
+        Stencil::new(axis_taps(axis, [(-2, 0.5), (-1, -2.0), (0, 1.5)]), 1)
+    }
+
+    /// Second-order central second derivative along `axis`.
+    pub fn second_difference(axis: usize) -> Self {
+        Stencil::new(axis_taps(axis, [(-1, 1.0), (0, -2.0), (1, 1.0)]), 2)
+    }
+}
+
+impl Stencil<2, 5> {
+    /// The 5-point Laplacian.
+    pub fn laplace_5() -> Self {
+        let mut taps = [(CoordInt::default(), -4.0); 5];
+        for (tap, neighbour) in taps[1..]
+            .iter_mut()
+            .zip(CoordInt::default().face_neighbours())
+        {
+            *tap = (neighbour, 1.0);
+        }
+        Stencil::new(taps, 2)
+    }
+}
+
+impl Stencil<2, 9> {
+    /// The isotropic 9-point Laplacian.
+    pub fn laplace_9() -> Self {
+        Stencil::new(box_taps([-20.0 / 6.0, 4.0 / 6.0, 1.0 / 6.0, 0.0]), 2)
+    }
+}
+
+impl Stencil<3, 7> {
+    /// The 7-point Laplacian.
+    pub fn laplace_7() -> Self {
+        let mut taps = [(CoordInt::default(), -6.0); 7];
+        for (tap, neighbour) in taps[1..]
+            .iter_mut()
+            .zip(CoordInt::default().face_neighbours())
+        {
+            *tap = (neighbour, 1.0);
+        }
+        Stencil::new(taps, 2)
+    }
+}
+
+impl Stencil<3, 27> {
+    /// The isotropic 27-point Laplacian.
+    pub fn laplace_27() -> Self {
+        Stencil::new(
+            box_taps([-128.0 / 30.0, 14.0 / 30.0, 3.0 / 30.0, 1.0 / 30.0]),
+            2,
+        )
+    }
+}
@@ -1,8 +1,27 @@
 #[cfg(test)]
 mod tests {
     use crate::simulation::{
-        grid::{CoordInt, Grid},
+        boundary::Boundary,
+        error::{CheckpointError, GridError, ImageError, NpyError, SceneError},
+        grid::{CoordInt, Grid, Int},
+        io::{
+            crc32,
+            image::{Channel, Image},
+            npy::{write_npy, Npz, NpzWriter},
+            render::{render, Colormap, FrameRecorder, FrameSource},
+            vtk::{Encoding, ImageData, TimeSeries},
+            zlib::Compression,
+        },
+        iter::CoordRange,
+        matrix::Matrix,
+        parallel::available_threads,
+        simulation::Simulation,
+        solver::PressureSolver,
+        sparse_grid::SparseGrid,
+        stencil::{Accuracy, Stencil},
         vector::{Float, Vector},
+        vector_field::VectorField,
+        view::{GridRead, GridView},
     };
     use approx::assert_relative_eq;
 
@@ -38,6 +57,88 @@ mod tests {
         assert_eq!(v2, Vector([3.0, 4.0]));
     }
 
+    #[test]
+    fn test_vector_products_and_norms() {
+        let v1 = Vector([3.0, 4.0]);
+        let v2 = Vector([-1.0, 2.0]);
+
+        assert_eq!(v1.dot(&v2), 5.0);
+        assert_eq!(v1.norm_squared(), 25.0);
+        assert_eq!(v1.norm(), 5.0);
+        assert_relative_eq!(v1.normalize(), Vector([0.6, 0.8]));
+        assert_relative_eq!(v1.normalize().norm(), 1.0);
+        assert_eq!(Vector::<2>::default().normalize(), Vector([0.0, 0.0]));
+
+        assert_eq!(v1.perp(), Vector([-4.0, 3.0]));
+        assert_eq!(v1.perp().dot(&v1), 0.0);
+
+        let a = Vector([1.0, 2.0, 3.0]);
+        let b = Vector([-2.0, 0.5, 4.0]);
+        let c = a.cross(&b);
+        assert_eq!(c, Vector([6.5, -10.0, 4.5]));
+        assert_eq!(c.dot(&a), 0.0);
+        assert_eq!(c.dot(&b), 0.0);
+        assert_eq!(
+            Vector([1.0, 0.0, 0.0]).cross(&Vector([0.0, 1.0, 0.0])),
+            Vector([0.0, 0.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn test_vector_operators() {
+        let mut v = Vector([1.0, -2.0, 3.0]);
+        assert_eq!(-v, Vector([-1.0, 2.0, -3.0]));
+
+        v += Vector([1.0, 1.0, 1.0]);
+        assert_eq!(v, Vector([2.0, -1.0, 4.0]));
+        v -= Vector([0.5, 0.5, 0.5]);
+        assert_eq!(v, Vector([1.5, -1.5, 3.5]));
+        v *= 2.0;
+        assert_eq!(v, Vector([3.0, -3.0, 7.0]));
+        v /= 4.0;
+        assert_eq!(v, Vector([0.75, -0.75, 1.75]));
+
+        assert_eq!(v[1], -0.75);
+        v[1] = 5.0;
+        assert_eq!(v, Vector([0.75, 5.0, 1.75]));
+
+        let w = Vector([1.0, -6.0, 1.0]);
+        assert_eq!(v.min(w), Vector([0.75, -6.0, 1.0]));
+        assert_eq!(v.max(w), Vector([1.0, 5.0, 1.75]));
+        assert_eq!(w.abs(), Vector([1.0, 6.0, 1.0]));
+
+        let vectors = [Vector([1.0, 2.0]), Vector([3.0, 4.0]), Vector([-0.5, 0.5])];
+        assert_eq!(vectors.iter().sum::<Vector<2>>(), Vector([3.5, 6.5]));
+        assert_eq!(vectors.into_iter().sum::<Vector<2>>(), Vector([3.5, 6.5]));
+        assert_eq!(
+            Vec::<Vector<2>>::new().into_iter().sum::<Vector<2>>(),
+            Vector([0.0, 0.0])
+        );
+    }
+
+    #[test]
+    fn test_vector_conversions() {
+        let v: Vector<3> = [1.0, 2.0, 3.0].into();
+        assert_eq!(v, Vector([1.0, 2.0, 3.0]));
+        let array: [Float; 3] = v.into();
+        assert_eq!(array, [1.0, 2.0, 3.0]);
+
+        let v: Vector<2, f32> = (1.5, -2.5).into();
+        assert_eq!(v, Vector([1.5, -2.5]));
+        let (x, y) = v.into();
+        assert_eq!((x, y), (1.5, -2.5));
+
+        let v = Vector::from((1.0, 2.0, 3.0));
+        let (x, y, z) = v.into();
+        assert_eq!((x, y, z), (1.0, 2.0, 3.0));
+
+        assert!(Vector::<3>::try_from(vec![1.0, 2.0]).is_err());
+        assert_eq!(
+            Vector::<2>::try_from(vec![1.0, 2.0]),
+            Ok(Vector([1.0, 2.0]))
+        );
+    }
+
     #[test]
     fn test_gradient() {
         let tolerance = 1e-10;
@@ -61,16 +162,13 @@ mod tests {
             }
         }
 
+        // one-sided differences keep the slope exact up to the walls
         for i in 0..m {
             for j in 0..n {
-                let expected_px = if i == 0 || i == m - 1 { px / 2.0 } else { px };
-                let expected_py = if j == 0 || j == n - 1 { py / 2.0 } else { py };
                 let gradient = grid.gradient(CoordInt([i, j]));
-                assert_relative_eq!(
-                    gradient,
-                    Vector([expected_px, expected_py]),
-                    epsilon = tolerance
-                );
+                assert_relative_eq!(gradient, Vector([px, py]), epsilon = tolerance);
+                let gradient = grid.gradient_with(CoordInt([i, j]), Accuracy::Fourth);
+                assert_relative_eq!(gradient, Vector([px, py]), epsilon = tolerance);
             }
         }
     }
@@ -103,12 +201,10 @@ mod tests {
 
         for i in 0..m {
             for j in 0..n {
-                let expected_kxx = if i == 0 || i == m - 1 { kxx / 2.0 } else { kxx };
-
-                let expected_kyy = if j == 0 || j == n - 1 { kyy / 2.0 } else { kyy };
-
                 let divergence = grid.divergence(CoordInt([i, j]));
-                assert_relative_eq!(divergence, expected_kxx + expected_kyy, epsilon = tolerance);
+                assert_relative_eq!(divergence, kxx + kyy, epsilon = tolerance);
+                let divergence = grid.divergence_with(CoordInt([i, j]), Accuracy::Fourth);
+                assert_relative_eq!(divergence, kxx + kyy, epsilon = tolerance);
             }
         }
     }
@@ -246,7 +342,7 @@ mod tests {
     }
 
     #[test]
-    fn test_iter(){
+    fn test_iter() {
         // 1D grid
         let m = 5;
         let mut grid = Grid::new(CoordInt([m]), 1.0);
@@ -258,7 +354,7 @@ mod tests {
 
         let mut new_count = 0;
 
-        for (coord, value) in grid.into_iter(){
+        for (coord, value) in grid.into_iter() {
             assert_eq!(*value, new_count);
             new_count += 1;
             println!("coord: {:?}, value: {}\n", coord, value);
@@ -266,11 +362,10 @@ mod tests {
 
         assert_eq!(count, new_count);
 
-        
         // 2D grid
         let m = 5;
         let n = 8;
-        
+
         let mut grid = Grid::new(CoordInt([m, n]), 1.0);
         let mut count = 0;
         for i in 0..m {
@@ -282,7 +377,7 @@ mod tests {
 
         let mut new_count = 0;
 
-        for (coord, value) in grid.into_iter(){
+        for (coord, value) in grid.into_iter() {
             assert_eq!(*value, new_count);
             new_count += 1;
             println!("coord: {:?}, value: {}\n", coord, value);
@@ -290,7 +385,6 @@ mod tests {
 
         assert_eq!(count, new_count);
 
-
         // 3D grid
 
         let m = 5;
@@ -310,7 +404,7 @@ mod tests {
 
         let mut new_count = 0;
 
-        for (coord, value) in grid.into_iter(){
+        for (coord, value) in grid.into_iter() {
             assert_eq!(*value, new_count);
             new_count += 1;
             println!("coord: {:?}, value: {}\n", coord, value);
@@ -318,4 +412,2554 @@ mod tests {
 
         assert_eq!(count, new_count);
     }
+
+    #[test]
+    fn test_sparse_grid_background_and_tiles() {
+        let mut grid = SparseGrid::<Float, 3>::with_background(CoordInt([512, 512, 512]), 1.0, 0.5);
+
+        assert_eq!(grid.get(&CoordInt([100, 200, 300])), Some(&0.5));
+        assert_eq!(grid.get(&CoordInt([512, 0, 0])), None);
+        assert_eq!(grid.get(&CoordInt([0, -1, 0])), None);
+        assert_eq!(grid.active_tile_count(), 0);
+
+        grid.set(CoordInt([100, 200, 300]), 1.0);
+        grid.set(CoordInt([101, 201, 301]), 2.0);
+        grid.set(CoordInt([400, 0, 0]), 3.0);
+        assert_eq!(grid.active_tile_count(), 2);
+        assert_eq!(grid.get(&CoordInt([100, 200, 300])), Some(&1.0));
+        assert_eq!(grid.get(&CoordInt([102, 200, 300])), Some(&0.5));
+
+        // writing the background releases the tile once it is uniform again
+        grid.set(CoordInt([400, 0, 0]), 0.5);
+        assert_eq!(grid.active_tile_count(), 1);
+
+        // get_mut allocates, prune releases
+        *grid.get_mut(&CoordInt([0, 0, 0])).unwrap() = 0.5;
+        assert_eq!(grid.active_tile_count(), 2);
+        grid.prune();
+        assert_eq!(grid.active_tile_count(), 1);
+
+        let mut active: Vec<_> = grid.active_cells().filter(|(_, v)| **v != 0.5).collect();
+        active.sort_by_key(|(coord, _)| coord.0);
+        assert_eq!(
+            active,
+            vec![
+                (CoordInt([100, 200, 300]), &1.0),
+                (CoordInt([101, 201, 301]), &2.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sparse_grid_edge_tiles() {
+        // 10 is not a multiple of the tile size, edge tiles are partially outside
+        let mut grid = SparseGrid::<i32, 2>::new(CoordInt([10, 10]), 1.0);
+        for i in 0..10 {
+            for j in 0..10 {
+                grid.set(CoordInt([i, j]), i * 10 + j + 1);
+            }
+        }
+        assert_eq!(grid.active_tile_count(), 4);
+        assert_eq!(grid.active_cells().count(), 100);
+
+        let dense = grid.to_dense();
+        for (coord, value) in &dense {
+            assert_eq!(grid.get(&coord), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_sparse_grid_operators_match_dense() {
+        let tolerance = 1e-10;
+
+        let m = 12;
+        let n = 9;
+        let delta = 0.2;
+
+        let mut dense = Grid::new(CoordInt([m, n]), delta);
+        let mut velocity = Grid::new(CoordInt([m, n]), delta);
+        for i in 0..m {
+            for j in 0..n {
+                let x = i as Float * delta;
+                let y = j as Float * delta;
+                *dense.get_mut(&CoordInt([i, j])).unwrap() = 0.7 * x * x * y - 0.3 * y * y;
+                *velocity.get_mut(&CoordInt([i, j])).unwrap() =
+                    Vector([0.3 * x - 0.1 * y, 0.9 * x]);
+            }
+        }
+
+        let sparse = SparseGrid::from_dense(&dense, 0.0);
+        let sparse_velocity = SparseGrid::from_dense(&velocity, Vector::default());
+
+        for i in 0..m {
+            for j in 0..n {
+                let coord = CoordInt([i, j]);
+                assert_relative_eq!(
+                    sparse.gradient(coord),
+                    dense.gradient(coord),
+                    epsilon = tolerance
+                );
+                assert_relative_eq!(
+                    sparse.laplace(coord),
+                    dense.laplace(coord),
+                    epsilon = tolerance
+                );
+                assert_relative_eq!(
+                    sparse_velocity.divergence(coord),
+                    velocity.divergence(coord),
+                    epsilon = tolerance
+                );
+                assert_relative_eq!(
+                    sparse.advect(&sparse_velocity, coord, 0.05),
+                    dense.advect(&velocity, coord, 0.05),
+                    epsilon = tolerance
+                );
+            }
+        }
+
+        let pos = Vector([1.13, 0.77]);
+        assert_relative_eq!(sparse.get_at(&pos), dense.get_at(&pos), epsilon = tolerance);
+    }
+
+    fn rotating_field_2d(m: i32, n: i32, delta: Float) -> Grid<Vector<2>, 2> {
+        let mut grid = Grid::new(CoordInt([m, n]), delta);
+        for i in 0..m {
+            for j in 0..n {
+                let x = i as Float * delta;
+                let y = j as Float * delta;
+                *grid.get_mut(&CoordInt([i, j])).unwrap() =
+                    Vector([0.3 * x - 0.5 * y + 0.1 * x * y, 0.8 * x - 0.4 * y * y]);
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_vector_field_layout_conversion() {
+        let aos = rotating_field_2d(5, 8, 0.2);
+        let soa = VectorField::from(&aos);
+
+        for (coord, value) in &aos {
+            assert_eq!(soa.get(&coord), Some(*value));
+            assert_eq!(soa.component(0).get(&coord), Some(&value.0[0]));
+            assert_eq!(soa.component(1).get(&coord), Some(&value.0[1]));
+        }
+        assert_eq!(soa.get(&CoordInt([5, 0])), None);
+
+        let back = soa.to_aos();
+        for (coord, value) in &aos {
+            assert_eq!(back.get(&coord), Some(value));
+        }
+
+        let [u, v] = VectorField::from(&aos).into_components();
+        let rebuilt = VectorField::from_components([u, v]);
+        assert_eq!(
+            rebuilt.get(&CoordInt([3, 4])),
+            aos.get(&CoordInt([3, 4])).copied()
+        );
+    }
+
+    #[test]
+    fn test_vector_field_operators_match_grid() {
+        let tolerance = 1e-10;
+        let m = 5;
+        let n = 8;
+        let delta = 0.2;
+        let dt = 0.05;
+
+        let aos = rotating_field_2d(m, n, delta);
+        let soa = VectorField::from(&aos);
+
+        for i in 0..m {
+            for j in 0..n {
+                let coord = CoordInt([i, j]);
+                assert_relative_eq!(
+                    soa.divergence(coord),
+                    aos.divergence(coord),
+                    epsilon = tolerance
+                );
+                assert_relative_eq!(soa.curl(coord), aos.curl(coord), epsilon = tolerance);
+
+                let x = i as Float * delta;
+                let y = j as Float * delta;
+                let velocity = aos.get(&coord).unwrap();
+                let expected = aos.get_at(&(Vector([x, y]) - *velocity * dt));
+                assert_relative_eq!(soa.advect(&soa, coord, dt), expected, epsilon = tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_curl() {
+        let tolerance = 1e-10;
+        let m = 5;
+        let delta = 0.2;
+
+        // solid body rotation (-y, x, 0) has curl (0, 0, 2)
+        let mut aos = Grid::new(CoordInt([m, m, m]), delta);
+        for (coord, _) in &Grid::<Float, 3>::new(CoordInt([m, m, m]), delta) {
+            let x = coord.0[0] as Float * delta;
+            let y = coord.0[1] as Float * delta;
+            *aos.get_mut(&coord).unwrap() = Vector([-y, x, 0.0]);
+        }
+        let soa = VectorField::from(&aos);
+
+        for i in 1..m - 1 {
+            for j in 1..m - 1 {
+                for k in 1..m - 1 {
+                    let coord = CoordInt([i, j, k]);
+                    assert_relative_eq!(
+                        aos.curl(coord),
+                        Vector([0.0, 0.0, 2.0]),
+                        epsilon = tolerance
+                    );
+                    assert_relative_eq!(
+                        soa.curl(coord),
+                        Vector([0.0, 0.0, 2.0]),
+                        epsilon = tolerance
+                    );
+                }
+            }
+        }
+
+        let aos = rotating_field_2d(m, m, delta);
+        // d(0.8x - 0.4y^2)/dx - d(0.3x - 0.5y + 0.1xy)/dy = 0.8 + 0.5 - 0.1x
+        let coord = CoordInt([2, 3]);
+        assert_relative_eq!(
+            aos.curl(coord),
+            1.3 - 0.1 * 2.0 * delta,
+            epsilon = tolerance
+        );
+    }
+
+    #[test]
+    fn test_parallel_fields_match_serial() {
+        let m = 13;
+        let n = 7;
+        let delta = 0.2;
+        let dt = 0.05;
+
+        let velocity = rotating_field_2d(m, n, delta);
+        let mut scalar = Grid::new(CoordInt([m, n]), delta);
+        for i in 0..m {
+            for j in 0..n {
+                let x = i as Float * delta;
+                let y = j as Float * delta;
+                *scalar.get_mut(&CoordInt([i, j])).unwrap() = (x * y).sin() + x * x;
+            }
+        }
+
+        for threads in [1, 2, 3, 5, 64, available_threads()] {
+            let mut gradient = Grid::new(CoordInt([m, n]), delta);
+            let mut divergence = Grid::new(CoordInt([m, n]), delta);
+            let mut laplace = Grid::new(CoordInt([m, n]), delta);
+            let mut advected = Grid::new(CoordInt([m, n]), delta);
+            let mut curl = Grid::new(CoordInt([m, n]), delta);
+
+            scalar.gradient_field(&mut gradient, threads);
+            velocity.divergence_field(&mut divergence, threads);
+            scalar.laplace_field(&mut laplace, threads);
+            scalar.advect_field(&velocity, dt, &mut advected, threads);
+            velocity.curl_field(&mut curl, threads);
+
+            for (coord, _) in &scalar {
+                assert_eq!(*gradient.get(&coord).unwrap(), scalar.gradient(coord));
+                assert_eq!(*divergence.get(&coord).unwrap(), velocity.divergence(coord));
+                assert_eq!(*laplace.get(&coord).unwrap(), scalar.laplace(coord));
+                assert_eq!(
+                    *advected.get(&coord).unwrap(),
+                    scalar.advect(&velocity, coord, dt)
+                );
+                assert_eq!(*curl.get(&coord).unwrap(), velocity.curl(coord));
+            }
+        }
+
+        // 3D curl
+        let size = CoordInt([4, 5, 6]);
+        let mut velocity = Grid::new(size, delta);
+        for (coord, _) in &Grid::<Float, 3>::new(size, delta) {
+            let [x, y, z] = coord.0.map(|c| c as Float * delta);
+            *velocity.get_mut(&coord).unwrap() = Vector([y * z, x * x - z, x * y * z]);
+        }
+        let mut curl = Grid::new(size, delta);
+        velocity.curl_field(&mut curl, 3);
+        for (coord, value) in &curl {
+            assert_eq!(*value, velocity.curl(coord));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "output grid size mismatch")]
+    fn test_parallel_field_size_mismatch() {
+        let scalar = Grid::<Float, 2>::new(CoordInt([4, 4]), 1.0);
+        let mut out = Grid::new(CoordInt([4, 5]), 1.0);
+        scalar.gradient_field(&mut out, 2);
+    }
+
+    fn ramp_grid(m: i32, n: i32) -> Grid<Float, 2> {
+        let mut grid = Grid::new(CoordInt([m, n]), 1.0);
+        for i in 0..m {
+            for j in 0..n {
+                *grid.get_mut(&CoordInt([i, j])).unwrap() = (i * n + j) as Float - 4.0;
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn test_grid_elementwise_algebra() {
+        let a = ramp_grid(3, 4);
+        let b = a.map(|v| v * v);
+
+        let sum = (&a + &b).unwrap();
+        let diff = (&b - &a).unwrap();
+        let scaled = &a * 2.0;
+        let halved = a.map(|v| *v) / 2.0;
+        for (coord, v) in &a {
+            assert_eq!(*sum.get(&coord).unwrap(), v + v * v);
+            assert_eq!(*diff.get(&coord).unwrap(), v * v - v);
+            assert_eq!(*scaled.get(&coord).unwrap(), 2.0 * v);
+            assert_eq!(*halved.get(&coord).unwrap(), v / 2.0);
+        }
+
+        let mut y = a.map(|_| 1.0);
+        y.axpy(0.5, &a).unwrap();
+        for (coord, v) in &a {
+            assert_eq!(*y.get(&coord).unwrap(), 1.0 + 0.5 * v);
+        }
+
+        let zipped = a.zip_map(&b, |x, y| (*x, *y)).unwrap();
+        assert_eq!(*zipped.get(&CoordInt([1, 1])).unwrap(), (1.0, 1.0));
+
+        y.fill(3.0);
+        assert!(y.as_slice().iter().all(|v| *v == 3.0));
+
+        let vectors = a.map(|v| Vector([*v, 1.0]));
+        assert_eq!(vectors.sum(), Vector([18.0, 12.0]));
+    }
+
+    #[test]
+    fn test_grid_shape_mismatch() {
+        let a = ramp_grid(3, 4);
+        let mut b = ramp_grid(4, 3);
+        let expected = GridError::ShapeMismatch {
+            expected: vec![3, 4],
+            found: vec![4, 3],
+        };
+
+        assert_eq!((&a + &b).err(), Some(expected.clone()));
+        assert_eq!((&a - &b).err(), Some(expected.clone()));
+        assert_eq!(a.dot(&b).err(), Some(expected.clone()));
+        assert_eq!(a.zip_map(&b, |x, y| x + y).err(), Some(expected));
+        assert!(b.axpy(1.0, &a).is_err());
+
+        // sizes whose cell count overflows are rejected, whatever the data
+        let overflowing = Grid::<Float, 3>::from_vec(CoordInt([65536, 65536, 0]), 1.0, vec![]);
+        assert!(matches!(overflowing, Err(GridError::ShapeMismatch { .. })));
+        let huge = Grid::<Float, 3>::from_vec(CoordInt([Int::MAX; 3]), 1.0, vec![]);
+        assert!(matches!(huge, Err(GridError::ShapeMismatch { .. })));
+        let negative = Grid::<Float, 2>::from_vec(CoordInt([-1, 2]), 1.0, vec![]);
+        assert!(matches!(negative, Err(GridError::ShapeMismatch { .. })));
+        let empty = Grid::<Float, 2>::from_vec(CoordInt([0, 5]), 1.0, vec![]).unwrap();
+        assert_eq!(empty.as_slice().len(), 0);
+    }
+
+    #[test]
+    fn test_grid_reductions() {
+        let a = ramp_grid(3, 4);
+
+        assert_eq!(a.sum(), 18.0);
+        assert_eq!(a.mean(), 1.5);
+        assert_eq!(
+            a.dot(&a).unwrap(),
+            (-4..8).map(|v| (v * v) as Float).sum::<Float>()
+        );
+        assert_eq!(a.min(), Some(-4.0));
+        assert_eq!(a.max(), Some(7.0));
+        assert_eq!(a.argmin(), Some((CoordInt([0, 0]), -4.0)));
+        assert_eq!(a.argmax(), Some((CoordInt([2, 3]), 7.0)));
+
+        assert_eq!(a.norm_l1(), 10.0 + 28.0);
+        assert_relative_eq!(a.norm_l2(), a.dot(&a).unwrap().sqrt());
+        assert_eq!(a.norm_linf(), 7.0);
+
+        let mut b = a.map(|v| -v);
+        *b.get_mut(&CoordInt([1, 2])).unwrap() = Float::NAN;
+        assert_eq!(b.argmax(), Some((CoordInt([0, 0]), 4.0)));
+        assert_eq!(b.norm_linf(), 7.0);
+
+        let empty = Grid::<Float, 2>::new(CoordInt([0, 3]), 1.0);
+        assert_eq!(empty.argmax(), None);
+        assert_eq!(empty.sum(), 0.0);
+    }
+
+    #[test]
+    fn test_iter_mut_and_values() {
+        let mut grid = Grid::<i32, 2>::new(CoordInt([3, 4]), 1.0);
+        for (i, value) in grid.iter_mut().enumerate() {
+            *value = i as i32;
+        }
+        for (coord, value) in grid.indexed_iter_mut() {
+            assert_eq!(*value, coord.0[0] * 4 + coord.0[1]);
+            *value *= 10;
+        }
+        for (coord, value) in &mut grid {
+            *value += coord.0[0];
+        }
+
+        assert_eq!(
+            grid.values().copied().collect::<Vec<_>>()[..5],
+            [0, 10, 20, 30, 41]
+        );
+        assert_eq!(grid.coords().len(), 12);
+        assert_eq!(grid.coords().next_back(), Some(CoordInt([2, 3])));
+        assert_eq!(grid.iter().len(), 12);
+        assert_eq!(grid.iter().next_back(), Some((CoordInt([2, 3]), &112)));
+
+        let mut coords = grid.coords();
+        assert_eq!(coords.next(), Some(CoordInt([0, 0])));
+        assert_eq!(coords.next_back(), Some(CoordInt([2, 3])));
+        assert_eq!(coords.len(), 10);
+        assert_eq!(coords.count(), 10);
+    }
+
+    #[test]
+    fn test_region_iterators() {
+        let m = 5;
+        let n = 6;
+        let o = 4;
+        let mut grid = Grid::<i32, 3>::new(CoordInt([m, n, o]), 1.0);
+        for (coord, value) in &mut grid {
+            *value = coord.0[0] * 100 + coord.0[1] * 10 + coord.0[2];
+        }
+
+        let region: Vec<_> = grid
+            .region(CoordInt([1, 2, -3]), CoordInt([3, 4, 2]))
+            .collect();
+        assert_eq!(region.len(), 2 * 2 * 2);
+        assert_eq!(region[0], (CoordInt([1, 2, 0]), &120));
+        assert_eq!(region[7], (CoordInt([2, 3, 1]), &231));
+        let reversed: Vec<_> = grid
+            .region(CoordInt([1, 2, -3]), CoordInt([3, 4, 2]))
+            .rev()
+            .collect();
+        assert_eq!(reversed, region.iter().rev().cloned().collect::<Vec<_>>());
+        assert_eq!(
+            grid.region(CoordInt([3, 0, 0]), CoordInt([1, 6, 4])).len(),
+            0
+        );
+
+        let interior = grid.interior();
+        assert_eq!(interior.len(), 3 * 4 * 2);
+        for (coord, _) in interior {
+            assert!((0..3).all(|i| coord.0[i] > 0 && coord.0[i] < grid.size().0[i] - 1));
+        }
+
+        let boundary = grid.boundary();
+        assert_eq!(boundary.len(), (m * n * o - 3 * 4 * 2) as usize);
+        let mut boundary_coords: Vec<_> = grid.boundary().map(|(coord, _)| coord).collect();
+        let interior_coords: Vec<_> = grid.interior().map(|(coord, _)| coord).collect();
+        boundary_coords.extend(interior_coords.iter().copied());
+        boundary_coords.sort_by_key(|c| c.0);
+        assert_eq!(boundary_coords, grid.coords().collect::<Vec<_>>());
+
+        let mut boundary = grid.boundary();
+        assert_eq!(boundary.next_back(), Some((CoordInt([4, 5, 3]), &453)));
+        assert_eq!(boundary.next(), Some((CoordInt([0, 0, 0]), &0)));
+        assert_eq!(boundary.len(), (m * n * o - 3 * 4 * 2 - 2) as usize);
+
+        for (_, value) in grid.boundary_mut() {
+            *value = -1;
+        }
+        for (_, value) in grid.interior_mut() {
+            *value = -2;
+        }
+        let region_mut = grid.region_mut(CoordInt([0, 0, 0]), CoordInt([1, 1, 1]));
+        assert_eq!(region_mut.len(), 1);
+        let boundary_coords: Vec<_> = grid.boundary().map(|(coord, _)| coord).collect();
+        let boundary_mut: Vec<_> = grid.boundary_mut().map(|(coord, _)| coord).collect();
+        assert_eq!(boundary_mut, boundary_coords);
+        let interior_back: Vec<_> = grid.interior_mut().rev().map(|(coord, _)| coord).collect();
+        assert!(interior_back.iter().rev().eq(&interior_coords));
+        let mut region_mut = grid.region_mut(CoordInt([1, 2, 1]), CoordInt([3, 4, 4]));
+        assert_eq!(region_mut.len(), 2 * 2 * 3);
+        assert_eq!(region_mut.next().unwrap().0, CoordInt([1, 2, 1]));
+        assert_eq!(region_mut.next_back().unwrap().0, CoordInt([2, 3, 3]));
+        let middle: Vec<_> = region_mut.map(|(coord, _)| coord).collect();
+        assert_eq!(middle.len(), 10);
+        assert_eq!(middle[..2], [CoordInt([1, 2, 2]), CoordInt([1, 2, 3])]);
+        assert_eq!(
+            grid.region_mut(CoordInt([1, 1, 2]), CoordInt([4, 5, 2]))
+                .len(),
+            0
+        );
+        assert_eq!(grid.values().filter(|v| **v == -2).count(), 3 * 4 * 2);
+        assert_eq!(
+            grid.values().filter(|v| **v == -1).count(),
+            (m * n * o - 3 * 4 * 2) as usize
+        );
+
+        // thin grids have no interior
+        let thin = Grid::<i32, 2>::new(CoordInt([1, 4]), 1.0);
+        assert_eq!(thin.interior().len(), 0);
+        assert_eq!(thin.boundary().len(), 4);
+        assert_eq!(thin.boundary().count(), 4);
+        let mut line = Grid::<i32, 1>::new(CoordInt([5]), 1.0);
+        let ends: Vec<_> = line.boundary_mut().map(|(coord, _)| coord).collect();
+        assert_eq!(ends, [CoordInt([0]), CoordInt([4])]);
+        assert_eq!(line.interior_mut().len(), 3);
+    }
+
+    fn sum_field<const D: usize>(field: &impl GridRead<i32, D>) -> i32 {
+        let mut sum = 0;
+        let size = field.size();
+        for coord in Grid::<(), D>::new(size, field.delta()).coords() {
+            sum += field.get(&coord).unwrap();
+        }
+        sum
+    }
+
+    #[test]
+    fn test_grid_window_view() {
+        let mut grid = Grid::<i32, 2>::new(CoordInt([5, 6]), 0.5);
+        for (coord, value) in &mut grid {
+            *value = coord.0[0] * 10 + coord.0[1];
+        }
+
+        let view = grid.view(CoordInt([1, 2]), CoordInt([4, 4])).unwrap();
+        assert_eq!(view.size(), CoordInt([3, 2]));
+        assert_eq!(view.delta(), 0.5);
+        assert_eq!(view.get(&CoordInt([0, 0])), Some(&12));
+        assert_eq!(view.get(&CoordInt([2, 1])), Some(&33));
+        assert_eq!(view.get(&CoordInt([3, 0])), None);
+        assert_eq!(view.get(&CoordInt([0, -1])), None);
+        assert_eq!(
+            view.iter().map(|(_, v)| *v).collect::<Vec<_>>(),
+            vec![12, 13, 22, 23, 32, 33]
+        );
+        assert_eq!(view.iter().len(), 6);
+        assert_eq!(view.iter().next_back(), Some((CoordInt([2, 1]), &33)));
+        assert_eq!(sum_field(&view), 135);
+        assert_eq!(view.to_grid().as_slice(), &[12, 13, 22, 23, 32, 33]);
+
+        assert!(grid.view(CoordInt([1, 2]), CoordInt([6, 4])).is_none());
+        assert!(grid.view(CoordInt([3, 2]), CoordInt([1, 4])).is_none());
+
+        let mut view = grid.view_mut(CoordInt([1, 2]), CoordInt([4, 4])).unwrap();
+        *view.get_mut(&CoordInt([0, 0])).unwrap() = -1;
+        assert!(view.get_mut(&CoordInt([0, 2])).is_none());
+        for (coord, value) in view.iter_mut() {
+            *value += coord.0[0] * 1000;
+        }
+        assert_eq!(sum_field(&view), 135 - 13 + 3 * 1000 * 2);
+        assert_eq!(view.iter_mut().len(), 6);
+        let (last, value) = view.iter_mut().next_back().unwrap();
+        assert_eq!((last, *value), (CoordInt([2, 1]), 2033));
+        view.fill(7);
+        assert_eq!(grid.values().filter(|v| **v == 7).count(), 6);
+        assert_eq!(grid.get(&CoordInt([0, 2])), Some(&2));
+        assert_eq!(grid.get(&CoordInt([1, 4])), Some(&14));
+    }
+
+    #[test]
+    fn test_grid_slice_view() {
+        let size = CoordInt([3, 4, 5]);
+        let mut grid = Grid::<Float, 3>::new(size, 2.0);
+        for (coord, value) in &mut grid {
+            let [x, y, z] = coord.0.map(|c| c as Float * 2.0);
+            *value = 0.5 * x - 0.25 * y + z + 100.0;
+        }
+
+        let slice: GridView<_, 3, 2> = grid.slice(1, 2).unwrap();
+        assert_eq!(slice.size(), CoordInt([3, 5]));
+        assert_eq!(slice.get(&CoordInt([2, 4])), grid.get(&CoordInt([2, 2, 4])));
+        assert_eq!(slice.iter().count(), 15);
+        for (index, value) in slice.iter() {
+            assert_eq!(
+                Some(value),
+                grid.get(&CoordInt([index.0[0], 2, index.0[1]]))
+            );
+        }
+        // interpolation inside the slice plane
+        assert_relative_eq!(
+            slice.get_at(&Vector([1.5, 3.0])),
+            0.5 * 1.5 - 0.25 * 4.0 + 3.0 + 100.0,
+            epsilon = 1e-12
+        );
+
+        assert!(grid.slice::<2>(3, 0).is_none());
+        assert!(grid.slice::<2>(0, 3).is_none());
+        assert!(grid.slice::<3>(0, 0).is_none());
+
+        let mut slice = grid.slice_mut::<2>(0, 1).unwrap();
+        assert_eq!(slice.size(), CoordInt([4, 5]));
+        slice.fill(-1.0);
+        {
+            let mut cells = slice.iter_mut().rev();
+            *cells.next().unwrap().1 = -2.0;
+            assert_eq!(cells.len(), 19);
+        }
+        assert_eq!(grid[CoordInt([1, 3, 4])], -2.0);
+        grid[CoordInt([1, 3, 4])] = -1.0;
+        assert_eq!(grid.values().filter(|v| **v == -1.0).count(), 20);
+        for (coord, value) in &grid {
+            assert_eq!(*value == -1.0, coord.0[0] == 1);
+        }
+
+        // across the last axis the viewed cells are not contiguous
+        let mut slice = grid.slice_mut::<2>(2, 3).unwrap();
+        let coords: Vec<_> = slice.iter_mut().map(|(index, _)| index).collect();
+        assert_eq!(
+            coords,
+            CoordRange::new(CoordInt([0, 0]), CoordInt([3, 4])).collect::<Vec<_>>()
+        );
+        for (index, value) in slice.iter_mut().rev() {
+            *value = (index.0[0] * 10 + index.0[1]) as Float;
+        }
+        assert_eq!(grid[CoordInt([2, 1, 3])], 21.0);
+        assert_eq!(grid[CoordInt([0, 3, 3])], 3.0);
+        assert_eq!(grid[CoordInt([1, 3, 4])], -1.0);
+    }
+
+    #[test]
+    fn test_single_precision_operators() {
+        let m = 5;
+        let n = 8;
+        let delta = 0.2;
+        let px = -0.1f32;
+        let py = 0.4f32;
+
+        let mut grid = Grid::<f32, 2>::new(CoordInt([m, n]), delta);
+        let mut velocity = Grid::<Vector<2, f32>, 2>::new(CoordInt([m, n]), delta);
+        for (coord, value) in &mut grid {
+            let [x, y] = coord.0.map(|c| c as f32 * delta as f32);
+            *value = 0.3 + px * x + py * y;
+        }
+        for (_, value) in &mut velocity {
+            *value = Vector([0.5, 1.0]);
+        }
+
+        let gradient = grid.gradient(CoordInt([2, 3]));
+        assert_relative_eq!(gradient, Vector([px, py]), epsilon = 1e-5);
+
+        let at = grid.get_at(&Vector([0.31f32, 0.57]));
+        assert_relative_eq!(at, 0.3 + px * 0.31 + py * 0.57, epsilon = 1e-5);
+
+        let dt = 0.05f32;
+        let advected = grid.advect(&velocity, CoordInt([2, 3]), dt);
+        let (x, y) = (0.4 - 0.5 * dt, 0.6 - 1.0 * dt);
+        assert_relative_eq!(advected, 0.3 + px * x + py * y, epsilon = 1e-5);
+
+        assert_relative_eq!(velocity.divergence(CoordInt([2, 3])), 0.0f32);
+        assert_relative_eq!(grid.laplace(CoordInt([2, 3])), 0.0f32, epsilon = 1e-4);
+        assert_eq!(grid.map(|v| *v as Float).size(), grid.size());
+
+        let simulation = Simulation::<2, f32>::new(CoordInt([m, n]), delta);
+        assert_eq!(simulation.densities.sum(), 0.0f32);
+        assert_eq!(simulation.velocities.sum(), Vector::<2, f32>::default());
+    }
+
+    #[test]
+    fn test_matrix_algebra() {
+        let a = Matrix([[1.0, 2.0], [3.0, 4.0]]);
+        let b = Matrix([[0.5, -1.0], [2.0, 0.0]]);
+
+        assert_eq!(a + b, Matrix([[1.5, 1.0], [5.0, 4.0]]));
+        assert_eq!(a - b, Matrix([[0.5, 3.0], [1.0, 4.0]]));
+        assert_eq!(-a, Matrix([[-1.0, -2.0], [-3.0, -4.0]]));
+        assert_eq!(a * 2.0, Matrix([[2.0, 4.0], [6.0, 8.0]]));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(a / 2.0, Matrix([[0.5, 1.0], [1.5, 2.0]]));
+        assert_eq!(a * b, Matrix([[4.5, -1.0], [9.5, -3.0]]));
+        assert_eq!(a * Vector([1.0, -1.0]), Vector([-1.0, -1.0]));
+        assert_eq!(a * Matrix::identity(), a);
+        assert_eq!(a[(1, 0)], 3.0);
+
+        assert_eq!(a.transpose(), Matrix([[1.0, 3.0], [2.0, 4.0]]));
+        assert_eq!(a.trace(), 5.0);
+        assert_relative_eq!(a.determinant(), -2.0);
+        assert_eq!(a.double_dot(&Matrix::identity()), a.trace());
+
+        let sym = a.symmetric_part();
+        let anti = a.antisymmetric_part();
+        assert_eq!(sym, sym.transpose());
+        assert_eq!(anti, -anti.transpose());
+        assert_eq!(sym + anti, a);
+
+        assert_eq!(Matrix([[3.0]]).determinant(), 3.0);
+        let c = Matrix([[2.0, -3.0, 1.0], [2.0, 0.0, -1.0], [1.0, 4.0, 5.0]]);
+        assert_relative_eq!(c.determinant(), 49.0, epsilon = 1e-12);
+        assert_relative_eq!(
+            (c * c.transpose()).determinant(),
+            49.0 * 49.0,
+            epsilon = 1e-9
+        );
+        let singular = Matrix([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 1.0]]);
+        assert_eq!(singular.determinant(), 0.0);
+        let permutation = Matrix([
+            [0.0, 1.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0, 0.0],
+        ]);
+        assert_eq!(permutation.determinant(), 1.0);
+    }
+
+    #[test]
+    fn test_jacobian() {
+        let tolerance = 1e-10;
+        let size = CoordInt([5, 6, 4]);
+        let delta = 0.25;
+        let k = Matrix([[0.3, -0.1, 0.7], [0.9, -0.4, 0.2], [-0.5, 0.6, 0.1]]);
+
+        let mut velocity = Grid::new(size, delta);
+        for (coord, value) in &mut velocity {
+            *value = k * Vector::from_coord_int(coord, delta);
+        }
+
+        let jacobian = velocity.jacobian();
+        for (coord, value) in jacobian.interior() {
+            assert_relative_eq!(*value, k, epsilon = tolerance);
+            assert_relative_eq!(
+                value.trace(),
+                velocity.divergence(coord),
+                epsilon = tolerance
+            );
+            let rotation = value.antisymmetric_part();
+            let curl = velocity.curl(coord);
+            assert_relative_eq!(curl[2], 2.0 * rotation[(1, 0)], epsilon = tolerance);
+        }
+        for (coord, value) in &jacobian {
+            assert_eq!(*value, velocity.jacobian_at(coord));
+        }
+
+        // matrix grids interpolate like any other cell value; stay inside the interior
+        let pos = Vector([0.3, 0.6, 0.4]);
+        assert_relative_eq!(jacobian.get_at(&pos), k, epsilon = tolerance);
+    }
+
+    #[test]
+    fn test_coord_arithmetic() {
+        let a = CoordInt([3, -1, 4]);
+        let b = CoordInt([1, 5, -9]);
+        assert_eq!(a + b, CoordInt([4, 4, -5]));
+        assert_eq!(a - b, CoordInt([2, -6, 13]));
+        assert_eq!(-a, CoordInt([-3, 1, -4]));
+        assert_eq!(a + b - b, a);
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+
+        assert_eq!(CoordInt::<3>::unit(1), CoordInt([0, 1, 0]));
+        assert_eq!(a.offset(2, -3), CoordInt([3, -1, 1]));
+        assert_eq!(a.offset(0, 2), a + CoordInt::unit(0) + CoordInt::unit(0));
+    }
+
+    #[test]
+    fn test_neighbourhoods() {
+        let centre = CoordInt([2, 5]);
+        let faces: Vec<_> = centre.face_neighbours().collect();
+        assert_eq!(
+            faces,
+            vec![
+                CoordInt([1, 5]),
+                CoordInt([3, 5]),
+                CoordInt([2, 4]),
+                CoordInt([2, 6])
+            ]
+        );
+        assert_eq!(centre.face_neighbours().len(), 4);
+        let mut reversed: Vec<_> = centre.face_neighbours().rev().collect();
+        reversed.reverse();
+        assert_eq!(reversed, faces);
+
+        let moore: Vec<_> = centre.moore_neighbours().collect();
+        assert_eq!(moore.len(), 8);
+        assert_eq!(moore[0], CoordInt([1, 4]));
+        assert_eq!(moore[7], CoordInt([3, 6]));
+        assert!(!moore.contains(&centre));
+        assert!(faces.iter().all(|f| moore.contains(f)));
+
+        let mut moore_3d = CoordInt([0, 0, 0]).moore_neighbours();
+        assert_eq!(moore_3d.len(), 26);
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..13 {
+            seen.insert(moore_3d.next().unwrap());
+        }
+        assert_eq!(moore_3d.len(), 13);
+        seen.extend(moore_3d.by_ref().rev());
+        assert_eq!(moore_3d.len(), 0);
+        assert_eq!(seen.len(), 26);
+        assert!(seen.iter().all(|c| c.0.iter().all(|v| v.abs() <= 1)));
+
+        let offsets = [CoordInt([0, 0]), CoordInt([2, 0]), CoordInt([-1, 3])];
+        let shifted: Vec<_> = centre.stencil(&offsets).collect();
+        assert_eq!(
+            shifted,
+            vec![CoordInt([2, 5]), CoordInt([4, 5]), CoordInt([1, 8])]
+        );
+    }
+
+    fn polynomial_grid<const D: usize>(
+        size: CoordInt<D>,
+        delta: Float,
+        f: impl Fn(&Vector<D>) -> Float,
+    ) -> Grid<Float, D> {
+        let mut grid = Grid::new(size, delta);
+        for (coord, value) in &mut grid {
+            *value = f(&Vector::from_coord_int(coord, delta));
+        }
+        grid
+    }
+
+    #[test]
+    fn test_standard_stencils() {
+        let tolerance = 1e-9;
+        let delta = 0.5;
+        let centre = CoordInt([3, 3]);
+        let x = 3.0 * delta;
+
+        // 4th-order central differences are exact up to quartics, the
+        // one-sided and 2nd-order ones up to quadratics
+        let quartic = polynomial_grid(CoordInt([7, 7]), delta, |p| {
+            p[0].powi(4) - 2.0 * p[0] * p[1]
+        });
+        let c4 = Stencil::central_difference_4th(0).apply(&quartic, centre);
+        assert_relative_eq!(c4, 4.0 * x.powi(3) - 2.0 * x, epsilon = tolerance);
+
+        let quadratic = polynomial_grid(CoordInt([7, 7]), delta, |p| {
+            3.0 * p[0] * p[0] + p[1] * p[1] - p[0] * p[1]
+        });
+        let slope = 6.0 * x - x;
+        for value in [
+            Stencil::central_difference(0).apply(&quadratic, centre),
+            Stencil::forward_difference(0).apply(&quadratic, centre),
+            Stencil::backward_difference(0).apply(&quadratic, centre),
+        ] {
+            assert_relative_eq!(value, slope, epsilon = tolerance);
+        }
+        let dxx: Float = Stencil::second_difference(0).apply(&quadratic, centre);
+        assert_relative_eq!(dxx, 6.0, epsilon = tolerance);
+        for value in [
+            Stencil::laplace_5().apply(&quadratic, centre),
+            Stencil::laplace_9().apply(&quadratic, centre),
+            quadratic.laplace(centre),
+        ] {
+            assert_relative_eq!(value, 8.0, epsilon = tolerance);
+        }
+
+        let quadratic_3d = polynomial_grid(CoordInt([5, 5, 5]), delta, |p| {
+            p[0] * p[0] - 2.0 * p[1] * p[2] + 4.0 * p[2] * p[2]
+        });
+        let centre_3d = CoordInt([2, 2, 2]);
+        for value in [
+            Stencil::laplace_7().apply(&quadratic_3d, centre_3d),
+            Stencil::laplace_27().apply(&quadratic_3d, centre_3d),
+        ] {
+            assert_relative_eq!(value, 10.0, epsilon = tolerance);
+        }
+        assert_eq!(Stencil::laplace_27().taps().len(), 27);
+        let weight_sum: Float = Stencil::laplace_27().taps().iter().map(|(_, w)| w).sum();
+        assert_relative_eq!(weight_sum, 0.0, epsilon = tolerance);
+
+        // taps outside the grid: fallback in `apply`, refusal in `try_apply`
+        let edge = CoordInt([0, 3]);
+        assert!(!Stencil::central_difference(0).fits(quadratic.size(), edge));
+        assert!(Stencil::forward_difference(0).fits(quadratic.size(), edge));
+        assert_eq!(
+            Stencil::central_difference(0).try_apply(&quadratic, edge),
+            None::<Float>
+        );
+        assert_eq!(
+            Stencil::forward_difference(0).apply(&quadratic, edge),
+            quadratic.derivative(edge, 0)
+        );
+    }
+
+    #[test]
+    fn test_custom_stencil() {
+        // a user-defined mixed derivative d2/dxdy
+        let mixed = Stencil::new(
+            [
+                (CoordInt([1, 1]), 0.25),
+                (CoordInt([-1, -1]), 0.25),
+                (CoordInt([1, -1]), -0.25),
+                (CoordInt([-1, 1]), -0.25),
+            ],
+            2,
+        );
+        let grid = polynomial_grid(CoordInt([6, 6]), 0.1, |p| 3.0 * p[0] * p[1] + p[0] * p[0]);
+        for (coord, _) in grid.interior() {
+            assert_relative_eq!(mixed.apply(&grid, coord), 3.0, epsilon = 1e-9);
+        }
+
+        // the same stencil applies to vector grids, sparse grids and views
+        let mut velocity = Grid::new(CoordInt([6, 6]), 0.1);
+        for (coord, value) in &mut velocity {
+            *value = Vector([1.0, 2.0]) * *grid.get(&coord).unwrap();
+        }
+        let centre = CoordInt([2, 3]);
+        assert_relative_eq!(
+            mixed.apply(&velocity, centre),
+            Vector([3.0, 6.0]),
+            epsilon = 1e-9
+        );
+        let sparse = SparseGrid::from_dense(&grid, 0.0);
+        assert_relative_eq!(mixed.apply(&sparse, centre), 3.0, epsilon = 1e-9);
+        let view = grid.view(CoordInt([1, 1]), CoordInt([5, 5])).unwrap();
+        assert_relative_eq!(mixed.apply(&view, CoordInt([1, 2])), 3.0, epsilon = 1e-9);
+    }
+
+    /// Largest derivative error of `sin(x)` on `[0, 1]` sampled with `n`
+    /// cells, over the cells accepted by `filter`.
+    fn sine_derivative_error(n: i32, accuracy: Accuracy, filter: impl Fn(i32) -> bool) -> Float {
+        let delta = 1.0 / (n - 1) as Float;
+        let grid = polynomial_grid(CoordInt([n]), delta, |p| p[0].sin());
+        (0..n)
+            .filter(|&i| filter(i))
+            .map(|i| {
+                let x = i as Float * delta;
+                (grid.derivative_with(CoordInt([i]), 0, accuracy) - x.cos()).abs()
+            })
+            .fold(0.0, Float::max)
+    }
+
+    #[test]
+    fn test_derivative_convergence() {
+        let order = |accuracy, filter: &dyn Fn(i32, i32) -> bool| {
+            let coarse = sine_derivative_error(33, accuracy, |i| filter(i, 33));
+            let fine = sine_derivative_error(65, accuracy, |i| filter(i, 65));
+            (coarse / fine).log2()
+        };
+        let everywhere = |_, _| true;
+        let deep_interior = |i, n| 2 <= i && i < n - 2;
+
+        let second = order(Accuracy::Second, &everywhere);
+        assert!((1.8..2.3).contains(&second), "second order: {second}");
+        let fourth = order(Accuracy::Fourth, &deep_interior);
+        assert!((3.8..4.3).contains(&fourth), "fourth order: {fourth}");
+        // the walls stay second order whatever the interior accuracy
+        let walls = order(Accuracy::Fourth, &everywhere);
+        assert!((1.8..2.3).contains(&walls), "wall order: {walls}");
+
+        // degenerate axes
+        let mut short = Grid::filled(CoordInt([2, 1]), 0.5, 0.0);
+        *short.get_mut(&CoordInt([1, 0])).unwrap() = 1.0;
+        assert_eq!(short.gradient(CoordInt([0, 0])), Vector([2.0, 0.0]));
+        assert_eq!(short.gradient(CoordInt([1, 0])), Vector([2.0, 0.0]));
+    }
+
+    #[test]
+    fn test_fallible_operators() {
+        let grid = ramp_grid(4, 5);
+        let mut velocity = Grid::filled(grid.size(), grid.delta(), Vector([0.5, -0.25]));
+
+        let outside = CoordInt([4, 0]);
+        let out_of_bounds = GridError::OutOfBounds {
+            index: vec![4, 0],
+            size: vec![4, 5],
+        };
+        assert_eq!(grid.try_get(&outside), Err(out_of_bounds.clone()));
+        assert_eq!(grid.try_gradient(outside), Err(out_of_bounds.clone()));
+        assert_eq!(grid.try_laplace(outside), Err(out_of_bounds.clone()));
+        assert_eq!(velocity.try_divergence(outside), Err(out_of_bounds.clone()));
+        assert_eq!(grid.try_advect(&velocity, outside, 0.1), Err(out_of_bounds));
+        assert!(grid.try_gradient(CoordInt([-1, 2])).is_err());
+
+        let inside = CoordInt([1, 2]);
+        assert_eq!(grid.try_gradient(inside), Ok(grid.gradient(inside)));
+        assert_eq!(grid.try_laplace(inside), Ok(grid.laplace(inside)));
+        assert_eq!(
+            velocity.try_divergence(inside),
+            Ok(velocity.divergence(inside))
+        );
+        assert_eq!(
+            grid.try_advect(&velocity, inside, 0.1),
+            Ok(grid.advect(&velocity, inside, 0.1))
+        );
+
+        let small_velocity = Grid::new(CoordInt([4, 4]), grid.delta());
+        assert!(matches!(
+            grid.try_advect(&small_velocity, inside, 0.1),
+            Err(GridError::ShapeMismatch { .. })
+        ));
+        assert_eq!(
+            grid.try_advect(&velocity, inside, Float::NAN),
+            Err(GridError::NonFinite)
+        );
+        *velocity.get_mut(&inside).unwrap() = Vector([Float::INFINITY, 0.0]);
+        assert_eq!(
+            grid.try_advect(&velocity, inside, 0.1),
+            Err(GridError::NonFinite)
+        );
+        assert_eq!(
+            grid.try_get_at(&Vector([Float::NAN, 0.0])),
+            Err(GridError::NonFinite)
+        );
+        assert!(Grid::<Float, 2>::new(CoordInt([0, 3]), 1.0)
+            .try_get_at(&Vector([0.0, 0.0]))
+            .is_err());
+
+        let mut poisoned = ramp_grid(4, 5);
+        *poisoned.get_mut(&CoordInt([2, 2])).unwrap() = Float::NAN;
+        assert_eq!(poisoned.try_gradient(inside), Err(GridError::NonFinite));
+        assert_eq!(poisoned.try_laplace(inside), Err(GridError::NonFinite));
+
+        // single-cell axes interpolate instead of reading out of bounds
+        let line = Grid::filled(CoordInt([3, 1]), 0.5, 2.0);
+        assert_eq!(line.get_at(&Vector([0.7, 0.2])), 2.0);
+
+        assert_eq!(
+            CoordInt::<3>::try_from(vec![1, 2]),
+            Err(GridError::DimensionMismatch {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(CoordInt::<2>::try_from_iter([4, 5]), Ok(CoordInt([4, 5])));
+        assert!(CoordInt::<2>::try_from_iter(0..3).is_err());
+        let big = Int::MAX as usize + 1;
+        assert_eq!(
+            CoordInt::<2>::try_from_iter([1, big]),
+            Err(GridError::ComponentOverflow { value: big })
+        );
+        assert_eq!((3..5).collect::<CoordInt<2>>(), CoordInt([3, 4]));
+        assert_eq!(
+            Vector::<3>::try_from(vec![1.0]),
+            Err(GridError::DimensionMismatch {
+                expected: 3,
+                found: 1
+            })
+        );
+        assert_eq!(
+            GridError::DimensionMismatch {
+                expected: 3,
+                found: 1
+            }
+            .to_string(),
+            "dimension mismatch: expected 3 components, found 1"
+        );
+    }
+
+    #[test]
+    fn test_checked_and_unchecked_access() {
+        let mut grid = Grid::new(CoordInt([20, 20]), 1.0);
+        for (i, value) in grid.iter_mut().enumerate() {
+            *value = i as Float;
+        }
+
+        // these used to alias other cells through the flat index
+        assert!(grid.get_mut(&CoordInt([0, 25])).is_none());
+        assert!(grid.get_mut(&CoordInt([1, -1])).is_none());
+        assert!(grid.get_mut(&CoordInt([20, 0])).is_none());
+        assert_eq!(grid[CoordInt([1, 5])], 25.0);
+
+        grid[CoordInt([3, 4])] = -1.0;
+        assert_eq!(grid.get(&CoordInt([3, 4])), Some(&-1.0));
+        *grid.get_mut(&CoordInt([19, 19])).unwrap() = -2.0;
+        assert_eq!(grid[CoordInt([19, 19])], -2.0);
+
+        for (coord, value) in &grid {
+            // SAFETY: the coordinates come from the grid itself
+            assert_eq!(unsafe { grid.get_unchecked(&coord) }, value);
+        }
+        let coord = CoordInt([7, 2]);
+        // SAFETY: `coord` is inside the 20x20 grid
+        unsafe { *grid.get_unchecked_mut(&coord) = 9.5 };
+        assert_eq!(grid[coord], 9.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "index [0, 25] out of bounds")]
+    fn test_index_out_of_bounds() {
+        let mut grid: Grid<Float, 2> = Grid::new(CoordInt([20, 20]), 1.0);
+        grid[CoordInt([0, 25])] = 1.0;
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 components, found 3")]
+    fn test_collect_coord_wrong_length() {
+        let _: CoordInt<2> = (0..3).collect();
+    }
+
+    #[test]
+    #[should_panic(expected = "coordinate component 2147483648 exceeds")]
+    fn test_collect_coord_overflow() {
+        let _: CoordInt<1> = [Int::MAX as usize + 1].into_iter().collect();
+    }
+
+    #[test]
+    #[should_panic(expected = "coord not in grid")]
+    fn test_gradient_outside_grid() {
+        ramp_grid(10, 10).gradient(CoordInt([-5, 0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "coord not in grid")]
+    fn test_gradient_outside_short_axis() {
+        Grid::<Float, 2>::new(CoordInt([2, 3]), 1.0).gradient(CoordInt([2, 0]));
+    }
+
+    /// A lid-driven box with a density blob, an extra field and an obstacle.
+    fn cavity_simulation() -> Simulation<2> {
+        let mut sim = Simulation::<2>::new(CoordInt([12, 10]), 0.1);
+        sim.params.viscosity = 0.01;
+        sim.params.diffusion = 0.001;
+        sim.params.pressure_solver = PressureSolver::Jacobi;
+        sim.boundary
+            .set_face(0, 1, Boundary::Moving(Vector([0.0, 1.0])));
+        sim.boundary.set_axis(1, Boundary::Periodic);
+        sim.boundary.obstacles[CoordInt([5, 4])] = true;
+        for (coord, density) in sim.densities.indexed_iter_mut() {
+            *density = if (3..6).contains(&coord.0[0]) {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        let mut temperature = Grid::new(sim.size(), sim.delta());
+        temperature[CoordInt([8, 2])] = 5.0;
+        sim.fields.insert("temperature".to_string(), temperature);
+        sim
+    }
+
+    fn assert_same_state(a: &Simulation<2>, b: &Simulation<2>) {
+        assert_eq!(a.steps, b.steps);
+        assert_eq!(a.time, b.time);
+        assert_eq!(a.params, b.params);
+        assert_eq!(a.densities.as_slice(), b.densities.as_slice());
+        assert_eq!(a.velocities.as_slice(), b.velocities.as_slice());
+        assert_eq!(a.pressure.as_slice(), b.pressure.as_slice());
+        assert_eq!(
+            a.fields.keys().collect::<Vec<_>>(),
+            b.fields.keys().collect::<Vec<_>>()
+        );
+        for (name, field) in &a.fields {
+            assert_eq!(field.as_slice(), b.fields[name].as_slice());
+        }
+        for axis in 0..2 {
+            for side in 0..2 {
+                assert_eq!(a.boundary.face(axis, side), b.boundary.face(axis, side));
+            }
+        }
+        assert_eq!(
+            a.boundary.obstacles.as_slice(),
+            b.boundary.obstacles.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_projection() {
+        let mut sim = cavity_simulation();
+        for _ in 0..20 {
+            sim.step();
+        }
+        assert_eq!(sim.steps, 20);
+        assert_relative_eq!(sim.time, 2.0, epsilon = 1e-12);
+        assert!(sim
+            .velocities
+            .as_slice()
+            .iter()
+            .all(|v| v.0.iter().all(|c| c.is_finite())));
+        // the lid drags the fluid next to it along
+        assert!(sim.velocities[CoordInt([11, 5])].0[1] > 0.1);
+        assert_eq!(sim.velocities[CoordInt([5, 4])], Vector([0.0, 0.0]));
+
+        // a uniformly compressing fluid nearly comes to rest away from the
+        // walls, where the collocated grid leaves a residual
+        let mut sim = Simulation::<2>::new(CoordInt([16, 16]), 1.0 / 16.0);
+        sim.params.solver_iterations = 200;
+        sim.params.pressure_solver = PressureSolver::GaussSeidel;
+        for (coord, v) in sim.velocities.indexed_iter_mut() {
+            let x = coord.0[0] as Float / 16.0 - 0.5;
+            *v = Vector([-x, 0.0]);
+        }
+        let divergence = |sim: &Simulation<2>| {
+            sim.velocities
+                .region(CoordInt([3, 0]), CoordInt([13, 16]))
+                .map(|(coord, _)| sim.velocities.divergence(coord).abs())
+                .sum::<Float>()
+        };
+        assert_relative_eq!(divergence(&sim), 160.0, epsilon = 1e-9);
+        sim.step();
+        assert!(divergence(&sim) < 0.5);
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let mut sim = cavity_simulation();
+        for _ in 0..3 {
+            sim.step();
+        }
+        let path = std::env::temp_dir().join(format!("nsh-checkpoint-{}.bin", std::process::id()));
+        sim.save_checkpoint(&path).unwrap();
+        let loaded = Simulation::<2>::load_checkpoint(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_same_state(&sim, &loaded.unwrap());
+
+        // single-precision state loads into a double-precision simulation
+        let mut single = Simulation::<2, f32>::new(CoordInt([3, 2]), 0.5);
+        single.densities[CoordInt([1, 1])] = 0.1;
+        single.params.dt = 0.25;
+        let mut bytes = Vec::new();
+        single.write_checkpoint(&mut bytes).unwrap();
+        let double = Simulation::<2, f64>::read_checkpoint(bytes.as_slice()).unwrap();
+        assert_eq!(double.densities[CoordInt([1, 1])], 0.1f32 as f64);
+        assert_eq!(double.params.dt, 0.25);
+    }
+
+    #[test]
+    fn test_checkpoint_restart() {
+        let mut straight = cavity_simulation();
+        for _ in 0..8 {
+            straight.step();
+        }
+
+        let mut first = cavity_simulation();
+        for _ in 0..5 {
+            first.step();
+        }
+        let mut bytes = Vec::new();
+        first.write_checkpoint(&mut bytes).unwrap();
+        let mut resumed = Simulation::<2>::read_checkpoint(bytes.as_slice()).unwrap();
+        for _ in 0..3 {
+            resumed.step();
+        }
+        assert_same_state(&straight, &resumed);
+    }
+
+    #[test]
+    fn test_checkpoint_rejects_incompatible() {
+        let mut bytes = Vec::new();
+        cavity_simulation().write_checkpoint(&mut bytes).unwrap();
+        let read = |bytes: &[u8]| Simulation::<2>::read_checkpoint(bytes).err().unwrap();
+
+        let mut version = bytes.clone();
+        version[8..12].copy_from_slice(&7u32.to_le_bytes());
+        assert!(matches!(
+            read(&version),
+            CheckpointError::UnsupportedVersion {
+                found: 7,
+                supported: 1
+            }
+        ));
+        assert_eq!(
+            read(&version).to_string(),
+            "unsupported checkpoint version 7, expected 1"
+        );
+
+        let error = Simulation::<3>::read_checkpoint(bytes.as_slice())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            CheckpointError::DimensionMismatch {
+                expected: 3,
+                found: 2
+            }
+        ));
+
+        let mut flipped = bytes.clone();
+        let middle = flipped.len() / 2;
+        flipped[middle] ^= 0x10;
+        assert!(matches!(
+            read(&flipped),
+            CheckpointError::ChecksumMismatch { .. }
+        ));
+
+        assert!(matches!(
+            read(b"not a checkpoint"),
+            CheckpointError::BadMagic
+        ));
+        assert!(matches!(read(&bytes[..10]), CheckpointError::Corrupt(_)));
+
+        // sizes whose cell count overflows, with a valid checksum
+        let sim = Simulation::<3>::new(CoordInt([1, 1, 1]), 1.0);
+        let mut bytes = Vec::new();
+        sim.write_checkpoint(&mut bytes).unwrap();
+        let name = bytes.windows(9).position(|w| w == b"densities").unwrap();
+        let size = name + 9 + 1 + 8;
+        for extents in [[65536, 65536, 0], [u32::MAX; 3], [i32::MAX as u32; 3]] {
+            let mut huge = bytes.clone();
+            for (axis, extent) in extents.iter().enumerate() {
+                huge[size + 4 * axis..size + 4 * axis + 4].copy_from_slice(&extent.to_le_bytes());
+            }
+            let body = huge.len() - 4;
+            let checksum = crc32::checksum(&huge[..body]);
+            huge[body..].copy_from_slice(&checksum.to_le_bytes());
+            let error = Simulation::<3>::read_checkpoint(huge.as_slice()).err();
+            assert!(matches!(error, Some(CheckpointError::Corrupt(_))));
+        }
+        let missing = Simulation::<2>::load_checkpoint("/nonexistent/checkpoint.bin");
+        assert!(matches!(missing.err().unwrap(), CheckpointError::Io(_)));
+    }
+
+    #[test]
+    fn test_vtk_image_data() {
+        let density = ramp_grid(2, 3);
+        let mut velocity = Grid::<Vector<2>, 2>::new(CoordInt([2, 3]), 1.0);
+        velocity[CoordInt([1, 0])] = Vector([0.5, -2.0]);
+        let mut image = ImageData::new(CoordInt([2, 3]), 1.0).with_origin([0.5, -1.0]);
+        image.add_scalar("density", &density).unwrap();
+        image.add_vector("velocity", &velocity).unwrap();
+        let wrong = Grid::new(CoordInt([3, 2]), 1.0);
+        assert!(matches!(
+            image.add_scalar("wrong", &wrong),
+            Err(GridError::ShapeMismatch { .. })
+        ));
+
+        let mut ascii = Vec::new();
+        image.write(&mut ascii, Encoding::Ascii).unwrap();
+        let ascii = String::from_utf8(ascii).unwrap();
+        assert!(ascii.contains(r#"WholeExtent="0 1 0 2 0 0" Origin="0.5 -1 0" Spacing="1 1 1""#));
+        assert!(ascii.contains(r#"<PointData Scalars="density" Vectors="velocity">"#));
+        // x varies fastest: the ramp is 3 x + y
+        let rows = [density[CoordInt([0, 0])], density[CoordInt([1, 0])]];
+        assert!(ascii.contains(&format!("{:?} {:?}\n", rows[0], rows[1])));
+        assert!(ascii.contains("0.0 0.0 0.0 0.5 -2.0 0.0\n"));
+
+        let mut raw = Vec::new();
+        image.write(&mut raw, Encoding::Appended).unwrap();
+        let start = raw.windows(5).position(|w| w == b"\n   _").unwrap() + 5;
+        let block = &raw[start..];
+        let read_f64 =
+            |offset: usize| Float::from_le_bytes(block[offset..offset + 8].try_into().unwrap());
+        assert_eq!(u64::from_le_bytes(block[..8].try_into().unwrap()), 6 * 8);
+        let values = (0..6).map(|i| read_f64(8 + 8 * i)).collect::<Vec<_>>();
+        let expected =
+            [[0, 0], [1, 0], [0, 1], [1, 1], [0, 2], [1, 2]].map(|c| density[CoordInt(c)]);
+        assert_eq!(values, expected);
+        let second = 8 + 6 * 8;
+        assert!(String::from_utf8_lossy(&raw).contains(&format!(r#"offset="{second}""#)));
+        assert_eq!(read_f64(second + 8 + 3 * 8), 0.5);
+
+        let line = Grid::<f32, 1>::new(CoordInt([4]), 0.25);
+        let mut image = ImageData::new(CoordInt([4]), 0.25);
+        image.add_scalar("f", &line).unwrap();
+        let mut out = Vec::new();
+        image.write(&mut out, Encoding::Ascii).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(r#"WholeExtent="0 3 0 0 0 0""#));
+        assert!(out.contains(r#"type="Float32""#));
+
+        // views export like the cells they cover
+        let mut volume = Grid::<Float, 3>::new(CoordInt([2, 3, 2]), 1.0);
+        for (coord, value) in volume.indexed_iter_mut() {
+            *value = density[CoordInt([coord.0[0], coord.0[1]])] + 100.0 * coord.0[2] as Float;
+        }
+        let slice = volume.slice::<2>(2, 1).unwrap();
+        let corner = velocity.view(CoordInt([1, 0]), CoordInt([2, 2])).unwrap();
+        let mut image = ImageData::new(CoordInt([2, 3]), 1.0);
+        image.add_scalar("slice", &slice).unwrap();
+        let mut part = ImageData::new(CoordInt([1, 2]), 1.0);
+        part.add_vector("corner", &corner).unwrap();
+        let mut out = Vec::new();
+        image.write(&mut out, Encoding::Ascii).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let rows = [
+            density[CoordInt([0, 2])] + 100.0,
+            density[CoordInt([1, 2])] + 100.0,
+        ];
+        assert!(out.contains(&format!("{:?} {:?}\n", rows[0], rows[1])));
+        let mut out = Vec::new();
+        part.write(&mut out, Encoding::Ascii).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("0.5 -2.0 0.0\n          0.0 0.0 0.0\n"));
+
+        let sim = Simulation::<3>::new(CoordInt([2, 3, 4]), 0.5);
+        let mut out = Vec::new();
+        sim.image_data().write(&mut out, Encoding::Ascii).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(r#"WholeExtent="0 1 0 2 0 3""#));
+        assert!(out.contains(r#"Name="pressure""#));
+    }
+
+    #[test]
+    fn test_vtk_time_series() {
+        let dir = std::env::temp_dir().join(format!("nsh-vtk-{}", std::process::id()));
+        let mut series = TimeSeries::new(&dir, "run", Encoding::Appended).unwrap();
+        let mut sim = cavity_simulation();
+        for _ in 0..2 {
+            series
+                .write_step(sim.steps, sim.time, &sim.image_data())
+                .unwrap();
+            sim.step();
+        }
+        let pvd = std::fs::read_to_string(series.collection_path()).unwrap();
+        let vti = std::fs::read(dir.join("run_000001.vti"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(pvd.contains(r#"<DataSet timestep="0" part="0" file="run_000000.vti"/>"#));
+        assert!(pvd.contains(r#"<DataSet timestep="0.1" part="0" file="run_000001.vti"/>"#));
+        assert_eq!(series.collection().datasets().len(), 2);
+        let vti = String::from_utf8_lossy(&vti.unwrap()).into_owned();
+        assert!(vti.contains(r#"Name="temperature""#));
+        assert!(vti.contains(r#"<AppendedData encoding="raw">"#));
+    }
+
+    #[test]
+    fn test_npy_round_trip() {
+        let grid = ramp_grid(3, 4);
+        let mut bytes = Vec::new();
+        grid.write_npy(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, 'shape': (3, 4), }"));
+        assert!(header.ends_with('\n'));
+        // C order: the last axis varies fastest
+        let first = |i: usize| {
+            Float::from_le_bytes(bytes[10 + header_len + 8 * i..][..8].try_into().unwrap())
+        };
+        assert_eq!(first(1), grid[CoordInt([0, 1])]);
+        let back = Grid::<Float, 2>::read_npy(bytes.as_slice(), 0.5).unwrap();
+        assert_eq!(back.size(), grid.size());
+        assert_eq!(back.delta(), 0.5);
+        assert_eq!(back.as_slice(), grid.as_slice());
+
+        let mut velocity = Grid::<Vector<3, f32>, 3>::new(CoordInt([2, 1, 3]), 1.0);
+        velocity[CoordInt([1, 0, 2])] = Vector([1.0, 2.0, 3.0]);
+        let mut bytes = Vec::new();
+        velocity.write_npy(&mut bytes).unwrap();
+        assert!(std::str::from_utf8(&bytes[10..80])
+            .unwrap()
+            .contains("'<f4'"));
+        assert!(std::str::from_utf8(&bytes[10..80])
+            .unwrap()
+            .contains("(2, 1, 3, 3)"));
+        let back = Grid::<Vector<3, f32>, 3>::read_npy(bytes.as_slice(), 1.0).unwrap();
+        assert_eq!(back.as_slice(), velocity.as_slice());
+
+        // views are written in their own C order
+        let corner = grid.view(CoordInt([1, 1]), CoordInt([3, 3])).unwrap();
+        let mut bytes = Vec::new();
+        write_npy(&corner, &mut bytes).unwrap();
+        let back = Grid::<Float, 2>::read_npy(bytes.as_slice(), 1.0).unwrap();
+        let expected = [[1, 1], [1, 2], [2, 1], [2, 2]].map(|c| grid[CoordInt(c)]);
+        assert_eq!(back.as_slice(), expected);
+        let mut npz = NpzWriter::new(Vec::new());
+        npz.add_grid("plane", &velocity.slice::<2>(1, 0).unwrap())
+            .unwrap();
+        let archive = Npz::read(npz.finish().unwrap().as_slice()).unwrap();
+        let plane = archive.grid::<Vector<3, f32>, 2>("plane", 1.0).unwrap();
+        assert_eq!(plane.size(), CoordInt([2, 3]));
+        assert_eq!(plane[CoordInt([1, 2])], Vector([1.0, 2.0, 3.0]));
+
+        let mut mask = Grid::<bool, 1>::new(CoordInt([5]), 1.0);
+        mask[CoordInt([3])] = true;
+        let path = std::env::temp_dir().join(format!("nsh-mask-{}.npy", std::process::id()));
+        mask.save_npy(&path).unwrap();
+        let loaded = Grid::<bool, 1>::load_npy(&path, 1.0);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().as_slice(), mask.as_slice());
+    }
+
+    #[test]
+    fn test_npy_rejects_mismatches() {
+        let mut bytes = Vec::new();
+        Grid::<Float, 3>::new(CoordInt([2, 2, 3]), 1.0)
+            .write_npy(&mut bytes)
+            .unwrap();
+        let error = Grid::<Float, 2>::read_npy(bytes.as_slice(), 1.0)
+            .err()
+            .unwrap();
+        assert!(
+            matches!(&error, NpyError::Shape { dimensions: 2, components: 1, found } if found == &[2, 2, 3])
+        );
+        assert_eq!(
+            error.to_string(),
+            "array of shape [2, 2, 3] does not fit a 2-dimensional grid"
+        );
+        // the trailing axis is too long for 2-vectors
+        let error = Grid::<Vector<2>, 2>::read_npy(bytes.as_slice(), 1.0)
+            .err()
+            .unwrap();
+        assert!(matches!(error, NpyError::Shape { components: 2, .. }));
+
+        let error = Grid::<f32, 3>::read_npy(bytes.as_slice(), 1.0)
+            .err()
+            .unwrap();
+        assert!(matches!(error, NpyError::Dtype { .. }));
+        assert_eq!(
+            error.to_string(),
+            "npy dtype mismatch: expected <f4, found <f8"
+        );
+
+        let mut fortran = bytes.clone();
+        let at = fortran.windows(5).position(|w| w == b"False").unwrap();
+        fortran[at..at + 5].copy_from_slice(b"True ");
+        let error = Grid::<Float, 3>::read_npy(fortran.as_slice(), 1.0);
+        assert!(matches!(error.err().unwrap(), NpyError::FortranOrder));
+        let truncated = &bytes[..bytes.len() - 1];
+        let error = Grid::<Float, 3>::read_npy(truncated, 1.0);
+        assert!(matches!(error.err().unwrap(), NpyError::Format(_)));
+
+        // shapes whose byte count overflows are malformed, not a panic
+        for shape in ["(65536, 65536, 0)", "(2147483647, 2147483647, 2147483647)"] {
+            let header =
+                format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {shape}, }}\n");
+            let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+            bytes.extend((header.len() as u16).to_le_bytes());
+            bytes.extend(header.as_bytes());
+            let error = Grid::<Float, 3>::read_npy(bytes.as_slice(), 1.0);
+            assert!(matches!(error.err().unwrap(), NpyError::Format(_)));
+        }
+    }
+
+    #[test]
+    fn test_npz_archive() {
+        let density = ramp_grid(3, 4);
+        let mut velocity = Grid::<Vector<2>, 2>::new(CoordInt([3, 4]), 0.5);
+        velocity[CoordInt([2, 3])] = Vector([-1.0, 4.0]);
+
+        let mut npz = NpzWriter::new(Vec::new());
+        npz.add_grid("density", &density).unwrap();
+        npz.add_grid("velocity", &velocity).unwrap();
+        npz.add_metadata("delta", 0.5).unwrap();
+        npz.add_metadata("time", 12.25).unwrap();
+        let bytes = npz.finish().unwrap();
+
+        let archive = Npz::read(bytes.as_slice()).unwrap();
+        assert_eq!(
+            archive.names().collect::<Vec<_>>(),
+            ["delta", "density", "time", "velocity"]
+        );
+        let delta = archive.metadata("delta").unwrap();
+        assert_eq!(delta, 0.5);
+        assert_eq!(archive.metadata("time"), Some(12.25));
+        assert_eq!(archive.metadata("density"), None);
+        let back = archive.grid::<Float, 2>("density", delta).unwrap();
+        assert_eq!(back.as_slice(), density.as_slice());
+        let back = archive.grid::<Vector<2>, 2>("velocity", delta).unwrap();
+        assert_eq!(back.as_slice(), velocity.as_slice());
+        let missing = archive.grid::<Float, 2>("pressure", delta);
+        assert!(matches!(missing.err().unwrap(), NpyError::Missing(_)));
+
+        let mut corrupted = bytes.clone();
+        corrupted[100] ^= 1;
+        assert!(matches!(
+            Npz::read(corrupted.as_slice()).err().unwrap(),
+            NpyError::Format(reason) if reason.contains("checksum")
+        ));
+
+        // zip64 sizes and offsets near u64::MAX must not overflow the reader
+        let zip64 = |local: bool, offset: u64| {
+            let mut bytes = Vec::new();
+            if local {
+                bytes.extend(0x0403_4b50u32.to_le_bytes());
+                bytes.extend([0; 26]);
+                bytes.extend(b"a.npy");
+            }
+            let directory = bytes.len() as u32;
+            bytes.extend(0x0201_4b50u32.to_le_bytes());
+            bytes.extend([0; 16]);
+            bytes.extend([0xff; 8]);
+            bytes.extend(5u16.to_le_bytes());
+            bytes.extend(28u16.to_le_bytes());
+            bytes.extend([0; 10]);
+            bytes.extend([0xff; 4]);
+            bytes.extend(b"a.npy");
+            bytes.extend(1u16.to_le_bytes());
+            bytes.extend(24u16.to_le_bytes());
+            bytes.extend(u64::MAX.to_le_bytes());
+            bytes.extend(u64::MAX.to_le_bytes());
+            bytes.extend(offset.to_le_bytes());
+            bytes.extend(0x0605_4b50u32.to_le_bytes());
+            bytes.extend([0; 6]);
+            bytes.extend(1u16.to_le_bytes());
+            bytes.extend([0; 4]);
+            bytes.extend(directory.to_le_bytes());
+            bytes.extend([0; 2]);
+            Npz::read(bytes.as_slice())
+        };
+        assert!(matches!(
+            zip64(false, u64::MAX - 2).err().unwrap(),
+            NpyError::Format(_)
+        ));
+        assert!(matches!(zip64(true, 0).err().unwrap(), NpyError::Format(_)));
+    }
+
+    /// The chunks of a PNG file as `(type, data)`, checking their CRCs.
+    fn png_chunks(bytes: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = Vec::new();
+        let mut at = 8;
+        while at < bytes.len() {
+            let len = u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+            let body = &bytes[at + 4..at + 8 + len];
+            let crc = u32::from_be_bytes(bytes[at + 8 + len..at + 12 + len].try_into().unwrap());
+            assert_eq!(crc32::checksum(body), crc);
+            chunks.push((body[..4].try_into().unwrap(), body[4..].to_vec()));
+            at += 12 + len;
+        }
+        chunks
+    }
+
+    #[test]
+    fn test_render_and_png() {
+        assert_eq!(Colormap::Grayscale.color(0.5), [128; 3]);
+        assert_eq!(Colormap::Viridis.color(-1.0), [68, 1, 84]);
+        assert_eq!(Colormap::Viridis.color(2.0), [253, 231, 37]);
+        assert_eq!(Colormap::Coolwarm.color(0.5), [221; 3]);
+        assert_eq!(Colormap::Inferno.color(Float::NAN), [0; 3]);
+
+        // axis 0 runs across the image, axis 1 down it
+        let field = ramp_grid(4, 3);
+        let image = render(&field, Colormap::Grayscale, None);
+        assert_eq!((image.width(), image.height()), (4, 3));
+        assert_eq!(image.get(0, 0), Some([0, 0, 0, 255]));
+        assert_eq!(image.get(3, 2), Some([255, 255, 255, 255]));
+        let fixed = render(&field, Colormap::Grayscale, Some((0.0, 100.0)));
+        assert_eq!(fixed.get(3, 2), Some([18, 18, 18, 255]));
+        let view = field.view(CoordInt([0, 0]), CoordInt([4, 1])).unwrap();
+        assert_eq!(render(&view, Colormap::Grayscale, None).height(), 1);
+        let scaled = image.scaled(3);
+        assert_eq!((scaled.width(), scaled.height()), (12, 9));
+        assert_eq!(scaled.get(11, 8), image.get(3, 2));
+
+        let mut stored = Vec::new();
+        image.write_png(&mut stored, Compression::Stored).unwrap();
+        let chunks = png_chunks(&stored);
+        let kinds = chunks.iter().map(|(kind, _)| kind).collect::<Vec<_>>();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, [0, 0, 0, 4, 0, 0, 0, 3, 8, 2, 0, 0, 0]);
+        // a stored zlib stream holds the unfiltered rows verbatim
+        let idat = &chunks[1].1;
+        assert_eq!(&idat[..2], [0x78, 0x01]);
+        let rows = &idat[7..idat.len() - 4];
+        assert_eq!(rows.len(), 3 * (1 + 4 * 3));
+        assert_eq!(&rows[13..17], [0, 23, 23, 23]);
+        let adler = u32::from_be_bytes(idat[idat.len() - 4..].try_into().unwrap());
+        assert_eq!(adler, crate::simulation::io::zlib::adler32(rows));
+
+        // smooth fields deflate well; translucent pixels switch to RGBA
+        let large = render(&ramp_grid(64, 64), Colormap::Viridis, None).scaled(4);
+        let (mut small, mut raw) = (Vec::new(), Vec::new());
+        large.write_png(&mut small, Compression::Deflate).unwrap();
+        large.write_png(&mut raw, Compression::Stored).unwrap();
+        assert!(small.len() * 10 < raw.len());
+        let mut translucent = Image::new(2, 2);
+        translucent.set(1, 1, [255, 0, 0, 128]);
+        let mut bytes = Vec::new();
+        translucent
+            .write_png(&mut bytes, Compression::Deflate)
+            .unwrap();
+        assert_eq!(png_chunks(&bytes)[0].1[9], 6);
+    }
+
+    #[test]
+    fn test_frame_recorder() {
+        let dir = std::env::temp_dir().join(format!("nsh-frames-{}", std::process::id()));
+        let recorder = FrameRecorder::new(&dir, 2)
+            .unwrap()
+            .with_source(FrameSource::Field("temperature".to_string()))
+            .with_colormap(Colormap::Inferno)
+            .with_scale(2);
+        let mut sim = cavity_simulation();
+        let mut written = Vec::new();
+        written.push(recorder.record(&sim).unwrap());
+        sim.run(5, |sim| recorder.record(sim).map(|path| written.push(path)))
+            .unwrap();
+        let missing = FrameRecorder::new(&dir, 1)
+            .unwrap()
+            .with_source(FrameSource::Field("salinity".to_string()))
+            .record(&sim);
+        let frame = std::fs::read(dir.join("frame_000002.png"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sim.steps, 5);
+        let names = written
+            .iter()
+            .map(|path| {
+                path.as_ref()
+                    .map(|p| p.file_name().unwrap().to_str().unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                Some("frame_000000.png"),
+                None,
+                Some("frame_000001.png"),
+                None,
+                Some("frame_000002.png"),
+                None
+            ]
+        );
+        let header = &png_chunks(&frame.unwrap())[0].1;
+        assert_eq!(header[..8], [0, 0, 0, 24, 0, 0, 0, 20]);
+        assert_eq!(missing.err().unwrap().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    /// A PNG with the given IHDR fields, extra chunks before IDAT, and
+    /// filtered scanlines.
+    fn png_file(header: [u8; 13], extra: &[(&[u8; 4], &[u8])], rows: &[u8]) -> Vec<u8> {
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+        let idat = crate::simulation::io::zlib::compress(rows, Compression::Deflate);
+        let chunks = extra
+            .iter()
+            .copied()
+            .chain([(b"IDAT", &idat[..]), (b"IEND", &[][..])]);
+        for (kind, data) in [(b"IHDR", &header[..])].into_iter().chain(chunks) {
+            let body = [&kind[..], data].concat();
+            out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            out.extend_from_slice(&body);
+            out.extend_from_slice(&crc32::checksum(&body).to_be_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn test_image_decoding() {
+        use crate::simulation::io::zlib;
+
+        // a stream using dynamic Huffman codes, as written by zlib
+        let fixture = [
+            0x78, 0xda, 0x25, 0x8a, 0x81, 0x09, 0x00, 0x30, 0x0c, 0xc2, 0x6e, 0x4d, 0xf4, 0xff,
+            0x1b, 0xd6, 0x76, 0x20, 0x18, 0x8c, 0x4a, 0x91, 0x89, 0x64, 0x8b, 0x0f, 0x85, 0xd4,
+            0xae, 0xf1, 0xf6, 0xaa, 0xf3, 0x4c, 0x1f, 0xe7, 0x44, 0x13, 0x22,
+        ];
+        let text = b"bbadabaababacaabaaabacaadaacdbdbaabbcaabadbbbdabcd";
+        assert_eq!(zlib::decompress(&fixture, text.len()).unwrap(), text);
+        let mut corrupt = fixture;
+        corrupt[38] ^= 1;
+        assert!(zlib::decompress(&corrupt, text.len()).is_err());
+        let long = (0..5000u32)
+            .map(|i| (i * i % 251) as u8)
+            .collect::<Vec<_>>();
+        for compression in [Compression::Stored, Compression::Deflate] {
+            let packed = zlib::compress(&long, compression);
+            assert_eq!(zlib::decompress(&packed, long.len()).unwrap(), long);
+            let err = zlib::decompress(&packed, long.len() - 1).unwrap_err();
+            assert_eq!(err, "decompressed data exceeds 4999 bytes");
+        }
+
+        // our own PNGs decode to the same pixels, opaque or not
+        let mut image = render(&ramp_grid(5, 3), Colormap::Viridis, None);
+        for compression in [Compression::Stored, Compression::Deflate] {
+            let mut bytes = Vec::new();
+            image.write_png(&mut bytes, compression).unwrap();
+            assert_eq!(Image::read(&bytes[..]).unwrap(), image);
+        }
+        image.set(2, 1, [1, 2, 3, 4]);
+        let mut bytes = Vec::new();
+        image.write_png(&mut bytes, Compression::Deflate).unwrap();
+        assert_eq!(Image::read(&bytes[..]).unwrap(), image);
+
+        // 2-bit greyscale, packed four pixels to a byte
+        let gray = png_file(
+            [0, 0, 0, 3, 0, 0, 0, 2, 2, 0, 0, 0, 0],
+            &[(b"tEXt", b"Comment\0ignored")],
+            &[0, 0b0001_1000, 0, 0b1111_1100],
+        );
+        let gray = Image::read(&gray[..]).unwrap();
+        assert_eq!(gray.get(1, 0), Some([85, 85, 85, 255]));
+        assert_eq!(gray.get(2, 0), Some([170, 170, 170, 255]));
+        assert_eq!(gray.get(0, 1), Some([255, 255, 255, 255]));
+
+        // palette with transparency, and a row using the sub filter
+        let palette = png_file(
+            [0, 0, 0, 2, 0, 0, 0, 1, 8, 3, 0, 0, 0],
+            &[(b"PLTE", &[10, 20, 30, 40, 50, 60]), (b"tRNS", &[128])],
+            &[1, 1, 0xff],
+        );
+        let palette = Image::read(&palette[..]).unwrap();
+        assert_eq!(palette.pixels(), [[40, 50, 60, 255], [10, 20, 30, 128]]);
+
+        // 16-bit RGB keeps the high byte
+        let deep = png_file(
+            [0, 0, 0, 1, 0, 0, 0, 1, 16, 2, 0, 0, 0],
+            &[],
+            &[0, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc],
+        );
+        assert_eq!(
+            Image::read(&deep[..]).unwrap().pixels(),
+            [[0x12, 0x56, 0x9a, 255]]
+        );
+
+        let mut damaged = bytes.clone();
+        damaged[20] ^= 1;
+        assert!(matches!(
+            Image::read(&damaged[..]),
+            Err(ImageError::Format(reason)) if reason.contains("IHDR")
+        ));
+        let interlaced = png_file([0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 1], &[], &[0, 0]);
+        assert!(matches!(
+            Image::read(&interlaced[..]),
+            Err(ImageError::Unsupported(_))
+        ));
+        let short = png_file([0, 0, 0, 2, 0, 0, 0, 1, 8, 0, 0, 0, 0], &[], &[0, 0]);
+        assert!(matches!(
+            Image::read(&short[..]),
+            Err(ImageError::Format(_))
+        ));
+        // image data that inflates past the size from IHDR stops there
+        let bomb = png_file(
+            [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0],
+            &[],
+            &vec![0; 1 << 20],
+        );
+        assert!(matches!(
+            Image::read(&bomb[..]),
+            Err(ImageError::Format(reason)) if reason.contains("exceeds 2 bytes")
+        ));
+
+        // plain and raw Netpbm files
+        let pgm = Image::read(&b"P2\n# a comment\n3 1\n4\n0 2 4\n"[..]).unwrap();
+        assert_eq!(pgm.get(1, 0), Some([128, 128, 128, 255]));
+        assert_eq!(pgm.get(2, 0), Some([255, 255, 255, 255]));
+        let ppm = Image::read(&b"P3 1 1 255 10 20 30"[..]).unwrap();
+        assert_eq!(ppm.pixels(), [[10, 20, 30, 255]]);
+        let raw = Image::read(&b"P6\n2 1\n255\n\x01\x02\x03\x04\x05\x06"[..]).unwrap();
+        assert_eq!(raw.pixels(), [[1, 2, 3, 255], [4, 5, 6, 255]]);
+        let deep = Image::read(&b"P5 2 1 65535\n\xff\xff\x80\x00"[..]).unwrap();
+        assert_eq!(deep.pixels(), [[255, 255, 255, 255], [128, 128, 128, 255]]);
+
+        assert!(matches!(
+            Image::read(&b"P5 2 1 255\n\x00"[..]),
+            Err(ImageError::Format(_))
+        ));
+        assert!(matches!(
+            Image::read(&b"P2 1 1 4 5"[..]),
+            Err(ImageError::Format(_))
+        ));
+
+        // dimensions whose sample count overflows are malformed, not a panic
+        for bytes in [
+            &b"P6\n4294967295 4294967295\n255\n\x00"[..],
+            &b"P2 4294967295 4294967295 255 0"[..],
+        ] {
+            assert!(matches!(Image::read(bytes), Err(ImageError::Format(_))));
+        }
+        for size in [
+            [0xff; 8],
+            [0, 0, 0, 0, 0, 0, 0, 1],
+            [0, 0, 0, 1, 0, 0, 0, 0],
+        ] {
+            let mut header = [0, 0, 0, 0, 0, 0, 0, 0, 16, 6, 0, 0, 0];
+            header[..8].copy_from_slice(&size);
+            let huge = png_file(header, &[], &[0, 0]);
+            assert!(matches!(Image::read(&huge[..]), Err(ImageError::Format(_))));
+        }
+        assert!(matches!(
+            Image::read(&b"GIF89a"[..]),
+            Err(ImageError::Unsupported(_))
+        ));
+        assert!(matches!(
+            Image::load("/nonexistent/image.png"),
+            Err(ImageError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_image_initial_conditions() {
+        assert_relative_eq!(Channel::Luminance.value([255; 4]), 1.0);
+        assert_relative_eq!(Channel::Alpha.value([0, 0, 0, 51]), 0.2);
+
+        // upsampling interpolates between pixel centres and clamps at the edges
+        let mut image = Image::new(2, 1);
+        image.set(0, 0, [0, 0, 0, 255]);
+        image.set(1, 0, [255, 255, 255, 255]);
+        let grid: Grid<Float, 2> = image.to_grid(Channel::Luminance, CoordInt([4, 2]), 0.5);
+        assert_eq!(grid.delta(), 0.5);
+        for j in 0..2 {
+            let row = (0..4).map(|i| grid[CoordInt([i, j])]).collect::<Vec<_>>();
+            assert_eq!(row, [0.0, 0.25, 0.75, 1.0]);
+        }
+
+        // downsampling averages neighbouring pixels
+        let mut image = Image::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                let red = if x < 2 { 0 } else { 255 };
+                let green = if y == 0 { 255 } else { 0 };
+                image.set(x, y, [red, green, 0, 255]);
+            }
+        }
+        let size = CoordInt([2, 2]);
+        let green: Grid<f32, 2> = image.to_grid(Channel::Green, size, 1.0);
+        assert_eq!(green.as_slice(), [0.5, 0.0, 0.5, 0.0]);
+        let mask = image.to_mask(Channel::Red, 0.5, size, 1.0);
+        assert_eq!(mask.as_slice(), [false, false, true, true]);
+        let velocity = image.to_velocity::<Float>(2.0, size, 1.0);
+        // half black, half white averages to 127.5, just below rest
+        assert_eq!(velocity[CoordInt([0, 0])][0], -2.0);
+        assert_relative_eq!(velocity[CoordInt([0, 0])][1], -1.0 / 127.0);
+        assert_eq!(velocity[CoordInt([1, 1])], Vector([2.0, -2.0]));
+        let mut grey = Image::new(1, 1);
+        grey.set(0, 0, [128, 128, 128, 255]);
+        let rest = grey.to_velocity::<Float>(2.0, CoordInt([2, 2]), 1.0);
+        assert!(rest.as_slice().iter().all(|v| *v == Vector([0.0, 0.0])));
+        grey.set(0, 0, [1, 255, 0, 255]);
+        let full = grey.to_velocity::<Float>(2.0, CoordInt([1, 1]), 1.0);
+        assert_eq!(full[CoordInt([0, 0])], Vector([-2.0, 2.0]));
+
+        let path = std::env::temp_dir().join(format!("nsh-image-{}.ppm", std::process::id()));
+        let mut ppm = b"P6 4 4 255\n".to_vec();
+        for pixel in image.pixels() {
+            ppm.extend_from_slice(&pixel[..3]);
+        }
+        std::fs::write(&path, ppm).unwrap();
+        let loaded = Image::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded, image);
+
+        let mut sim = Simulation::<2, f32>::new(CoordInt([8, 8]), 0.25);
+        sim.set_densities_from_image(&loaded);
+        sim.set_velocities_from_image(&loaded, 1.0);
+        sim.set_obstacles_from_image(&loaded, Channel::Red, 0.5);
+        assert_eq!(sim.densities.size(), CoordInt([8, 8]));
+        assert_eq!(sim.densities.delta(), 0.25);
+        assert_relative_eq!(sim.densities[CoordInt([7, 7])], 0.2126);
+        assert_relative_eq!(sim.densities[CoordInt([0, 0])], 0.7152);
+        assert_eq!(sim.velocities[CoordInt([0, 7])], Vector([-1.0, -1.0]));
+        let solid = sim.boundary.obstacles.as_slice().iter().filter(|&&s| s);
+        assert_eq!(solid.count(), 32);
+        assert!(sim.boundary.obstacles[CoordInt([4, 0])]);
+        sim.step();
+        assert!(sim.densities.as_slice().iter().all(|d| d.is_finite()));
+    }
+
+    const SCENE: &str = r#"
+# heated plume around a cylinder
+dimensions = 2
+size = [16, 12]
+spacing = 0.125   # two world units across
+
+[fluid]
+dt = 0.05
+viscosity = 1e-3
+
+[solver]
+pressure = "jacobi"
+iterations = 20
+
+[boundary]
+x = "periodic"
+"y-" = "free-slip"
+"y+" = { moving = [1, 0] }
+
+[[obstacle]]
+shape = "sphere"
+centre = [1.0, 0.75]
+radius = 0.2
+
+[[obstacle]]
+shape = "box"
+min = [0.0, 0.0]
+max = [2.0, 0.125]
+
+[[emitter]]
+shape = "box"
+min = [0.25, 0.25]
+max = [0.5, 0.5]
+density = 2
+velocity = [0.0, 1.0]
+fields = { temperature = 4.0 }
+
+[[force]]
+acceleration = [0.0, 0.5]
+field = "temperature"
+
+[run]
+steps = 30
+
+[output]
+directory = "frames"
+every = 5
+formats = [
+    "png",
+    "npz",  # one archive per output step
+]
+colormap = "inferno"
+"#;
+
+    #[test]
+    fn test_scene_file() {
+        use crate::simulation::scene::{
+            self, Emitter, Obstacle, OutputFormat, RunLength, Scene, Shape,
+        };
+
+        assert_eq!(scene::dimensions(SCENE).unwrap(), 2);
+        let parsed = Scene::<2>::parse(SCENE, std::path::Path::new("scenes")).unwrap();
+        assert_eq!(parsed.size, CoordInt([16, 12]));
+        assert_eq!(parsed.spacing, 0.125);
+        assert_eq!(parsed.params.dt, 0.05);
+        assert_eq!(parsed.params.viscosity, 1e-3);
+        assert_eq!(parsed.params.diffusion, 0.0);
+        assert_eq!(parsed.params.pressure_solver, PressureSolver::Jacobi);
+        assert_eq!(parsed.params.solver_iterations, 20);
+        assert_eq!(
+            parsed.faces,
+            [
+                [Boundary::Periodic, Boundary::Periodic],
+                [Boundary::FreeSlip, Boundary::Moving(Vector([1.0, 0.0]))]
+            ]
+        );
+        assert_eq!(
+            parsed.obstacles[0],
+            Obstacle::Shape(Shape::Sphere {
+                centre: Vector([1.0, 0.75]),
+                radius: 0.2
+            })
+        );
+        assert_eq!(
+            parsed.emitters,
+            [Emitter {
+                shape: Shape::Box {
+                    min: Vector([0.25, 0.25]),
+                    max: Vector([0.5, 0.5])
+                },
+                density: 2.0,
+                fields: [("temperature".to_string(), 4.0)].into(),
+                velocity: Some(Vector([0.0, 1.0])),
+            }]
+        );
+        assert_eq!(parsed.forces[0].field.as_deref(), Some("temperature"));
+        assert_eq!(parsed.run, Some(RunLength::Steps(30)));
+        let output = parsed.output.as_ref().unwrap();
+        assert_eq!(output.directory, std::path::Path::new("scenes/frames"));
+        assert_eq!(output.every, 5);
+        assert_eq!(output.formats, [OutputFormat::Png, OutputFormat::Npz]);
+        assert_eq!(output.source, FrameSource::Density);
+        assert_eq!(output.colormap, Colormap::Inferno);
+
+        let mut sim = parsed.simulation();
+        assert_eq!(sim.size(), CoordInt([16, 12]));
+        assert!(sim.boundary.is_periodic(0));
+        assert_eq!(
+            *sim.boundary.face(1, 1),
+            Boundary::Moving(Vector([1.0, 0.0]))
+        );
+        // the cylinder covers the cells around (7.5, 5.5), the box the bottom row
+        assert!(sim.boundary.is_solid(&CoordInt([7, 5])));
+        assert!(sim.boundary.is_solid(&CoordInt([0, 0])));
+        assert!(!sim.boundary.is_solid(&CoordInt([0, 1])));
+        let solid = sim.boundary.obstacles.as_slice().iter().filter(|&&s| s);
+        assert_eq!(solid.count(), 16 + 12);
+        assert_eq!(sim.fields.keys().collect::<Vec<_>>(), ["temperature"]);
+
+        // the emitter covers cells 2 and 3 on both axes
+        parsed.apply_sources(&mut sim);
+        assert_relative_eq!(sim.densities.as_slice().iter().sum::<Float>(), 4.0 * 0.1);
+        assert_relative_eq!(sim.densities[CoordInt([2, 3])], 0.1);
+        assert_relative_eq!(sim.fields["temperature"][CoordInt([3, 2])], 0.2);
+        assert_eq!(sim.densities[CoordInt([4, 3])], 0.0);
+        // buoyancy accelerates the heated cells only, on top of the held velocity
+        assert_relative_eq!(sim.velocities[CoordInt([2, 2])][1], 1.0 + 0.05 * 0.5 * 0.2);
+        assert_eq!(sim.velocities[CoordInt([8, 8])], Vector([0.0, 0.0]));
+
+        for _ in 0..10 {
+            parsed.step(&mut sim);
+        }
+        assert_eq!(sim.steps, 10);
+        assert!(sim.densities.as_slice().iter().all(|d| d.is_finite()));
+        assert!(sim.densities.as_slice().iter().sum::<Float>() > 1.0);
+
+        let mask_dir = std::env::temp_dir().join(format!("nsh-scene-{}", std::process::id()));
+        std::fs::create_dir_all(&mask_dir).unwrap();
+        std::fs::write(mask_dir.join("mask.pgm"), b"P2 2 1 1 0 1").unwrap();
+        let file = mask_dir.join("scene.toml");
+        let text = "dimensions = 2\nsize = [4, 2]\nspacing = 1\n\n[[obstacle]]\n\
+                    shape = 'image'\npath = 'mask.pgm'\nthreshold = 0.5\n";
+        std::fs::write(&file, text).unwrap();
+        let loaded = Scene::<2, f32>::load(&file);
+        std::fs::remove_dir_all(&mask_dir).unwrap();
+        let sim = loaded.unwrap().simulation();
+        assert_eq!(
+            sim.boundary.obstacles.as_slice(),
+            [false, false, false, false, true, true, true, true]
+        );
+        assert_eq!(sim.params.dt, 0.1);
+    }
+
+    #[test]
+    fn test_scene_toml_syntax() {
+        use crate::simulation::io::toml::{self, Value};
+
+        let text = r#"
+            basic = """
+first\tline \
+    continued
+"quoted" ""\"""""
+            literal = '''C:\path
+''ends with quotes'''''
+            escapes = "\b\f\u00e9"
+            hex = 0xdead_BEEF
+            octal = 0o755
+            binary = 0b1010
+            inline = { a = 1 , b = [ 1, 2 ] }
+            after = 2
+        "#;
+        let table = toml::parse(text).unwrap();
+        let value = |key: &str| table[key].value.clone();
+        let string = |s: &str| Value::String(s.to_string());
+        assert_eq!(
+            value("basic"),
+            string("first\tline continued\n\"quoted\" \"\"\"\"")
+        );
+        assert_eq!(value("literal"), string("C:\\path\n''ends with quotes''"));
+        assert_eq!(value("escapes"), string("\u{8}\u{c}é"));
+        assert_eq!(value("hex"), Value::Integer(0xdead_beef));
+        assert_eq!(value("octal"), Value::Integer(0o755));
+        assert_eq!(value("binary"), Value::Integer(10));
+        // lines inside multi-line strings still count
+        assert_eq!(table["after"].line, 13);
+
+        for (text, message) in [
+            ("d = 1979-05-27", "dates and times are not supported"),
+            (
+                "d = 1979-05-27T07:32:00Z",
+                "dates and times are not supported",
+            ),
+            ("t = 07:32:00", "dates and times are not supported"),
+            ("n = 0x-1", "invalid value `0x-1`"),
+            ("n = -0b1", "invalid value `-0b1`"),
+            ("n = 0b12", "invalid value `0b12`"),
+            ("s = \"\"\"open", "unterminated string"),
+            // TOML 1.0 keeps inline tables on one line, without a trailing comma
+            ("t = { a = 1,\n b = 2 }", "expected a key"),
+            ("t = { a = 1, }", "expected a key"),
+        ] {
+            assert_eq!(toml::parse(text).unwrap_err().1, message, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_scene_errors() {
+        use crate::simulation::scene::Scene;
+
+        let error = |text: &str| match Scene::<2>::parse(text, std::path::Path::new("")) {
+            Err(SceneError::Invalid { key, line, reason }) => (key, line, reason),
+            Err(other) => panic!("unexpected error {other}"),
+            Ok(_) => panic!("{text:?} parsed"),
+        };
+        let header = "dimensions = 2\nsize = [8, 8]\nspacing = 1.0\n";
+        let with = |rest: &str| format!("{header}{rest}");
+
+        let syntax = Scene::<2>::parse(&with("[fluid]\ndt = = 1\n"), std::path::Path::new(""));
+        match syntax {
+            Err(SceneError::Syntax { line, .. }) => assert_eq!(line, 5),
+            _ => panic!("expected a syntax error"),
+        }
+        let duplicate = Scene::<2>::parse(&with("spacing = 2.0\n"), std::path::Path::new(""));
+        assert_eq!(
+            duplicate.err().unwrap().to_string(),
+            "line 4: key `spacing` defined twice"
+        );
+
+        assert_eq!(
+            error("size = [8, 8]\nspacing = 1\n"),
+            ("dimensions".to_string(), 1, "missing key".to_string())
+        );
+        assert_eq!(
+            error("dimensions = 3\nsize = [8, 8]\n").2,
+            "expected 2, found 3"
+        );
+        assert_eq!(
+            error("dimensions = 2\nsize = [8, 0]\nspacing = 1\n"),
+            (
+                "size[1]".to_string(),
+                2,
+                "expected a positive integer".to_string()
+            )
+        );
+        assert_eq!(
+            error("dimensions = 2\nsize = [8, 8, 8]\nspacing = 1\n").2,
+            "expected 2 elements, found 3"
+        );
+        assert_eq!(
+            error(&with("\n[fluid]\ndt = 0.1\nviscosty = 0.01\n")),
+            ("fluid.viscosty".to_string(), 7, "unknown key".to_string())
+        );
+        assert_eq!(
+            error(&with("[fluid]\ndt = \"fast\"\n")),
+            (
+                "fluid.dt".to_string(),
+                5,
+                "expected a number, found a string".to_string()
+            )
+        );
+        assert_eq!(error(&with("[fluid]\ndt = -1\n")).2, "must be positive");
+        assert_eq!(
+            error(&with("[solver]\npressure = 'multigrid'\n")).0,
+            "solver.pressure"
+        );
+        assert_eq!(
+            error(&with("[boundary]\nx = 'periodic'\n'x+' = 'outflow'\n")),
+            (
+                "boundary.x+".to_string(),
+                6,
+                "periodic faces must come in pairs".to_string()
+            )
+        );
+        assert_eq!(error(&with("[boundary]\nz = 'outflow'\n")).0, "boundary.z");
+        assert!(error(&with("[boundary]\ny = 'moving'\n"))
+            .2
+            .contains("needs a velocity"));
+        assert_eq!(
+            error(&with("[boundary]\ny = { moving = [1, 0], spin = 2 }\n")).0,
+            "boundary.y.spin"
+        );
+        let obstacles = "[[obstacle]]\nshape = 'box'\nmin = [0, 0]\nmax = [1, 1]\n\n\
+                         [[obstacle]]\nshape = 'sphere'\ncentre = [1, 1]\nradius = 0\n";
+        assert_eq!(
+            error(&with(obstacles)),
+            (
+                "obstacle[1].radius".to_string(),
+                12,
+                "must be positive".to_string()
+            )
+        );
+        assert_eq!(
+            error(&with("[[obstacle]]\nshape = 'box'\nmin = [0, 0]\n")),
+            ("obstacle[0].max".to_string(), 4, "missing key".to_string())
+        );
+        assert_eq!(
+            error(&with(
+                "[[obstacle]]\nshape = 'box'\nmin = [0, 2]\nmax = [1, 1]\n"
+            ))
+            .0,
+            "obstacle[0].max"
+        );
+        let (key, _, reason) = error(&with(
+            "[[obstacle]]\nshape = 'image'\npath = 'missing.png'\n",
+        ));
+        assert_eq!(key, "obstacle[0].path");
+        assert!(reason.starts_with("image i/o error"));
+        assert_eq!(
+            error(&with(
+                "[[force]]\nacceleration = [0, 1]\nfield = 'salinity'\n"
+            ))
+            .0,
+            "force[0].field"
+        );
+        assert_eq!(
+            error(&with(
+                "[[emitter]]\nshape = 'box'\nmin = [0, 'a']\nmax = [1, 1]\n"
+            )),
+            (
+                "emitter[0].min[1]".to_string(),
+                6,
+                "expected a finite number".to_string()
+            )
+        );
+        // NaN would slip past the `min < max` check
+        assert_eq!(
+            error(&with(
+                "[[obstacle]]\nshape = 'box'\nmin = [0, nan]\nmax = [1, 1]\n"
+            ))
+            .0,
+            "obstacle[0].min[1]"
+        );
+        let emitter = "[[emitter]]\nshape = 'box'\nmin = [0, 0]\nmax = [1, 1]\n";
+        for (rest, key) in [
+            ("density = nan\n", "emitter[0].density"),
+            ("velocity = [inf, 0]\n", "emitter[0].velocity[0]"),
+            ("fields = { heat = -inf }\n", "emitter[0].fields.heat"),
+        ] {
+            assert_eq!(error(&with(&format!("{emitter}{rest}"))).0, key);
+        }
+        assert_eq!(
+            error(&with("[[force]]\nacceleration = [0, -nan]\n")).0,
+            "force[0].acceleration[1]"
+        );
+        assert_eq!(
+            error(&with("[run]\nsteps = 10\nend_time = 2.0\n")).0,
+            "run.end_time"
+        );
+        assert_eq!(
+            error(&with("[output]\nformats = ['gif']\n")).0,
+            "output.formats[0]"
+        );
+        let three_d = "dimensions = 3\nsize = [4, 4, 4]\nspacing = 1\n\
+                       [output]\nformats = ['png']\n";
+        match Scene::<3>::parse(three_d, std::path::Path::new("")) {
+            Err(SceneError::Invalid { key, .. }) => assert_eq!(key, "output.formats"),
+            _ => panic!("png output accepted in 3D"),
+        }
+    }
+
+    #[test]
+    fn test_batch_run() {
+        use crate::simulation::{
+            batch::{run_file, Options},
+            error::BatchError,
+            scene::RunLength,
+        };
+
+        let dir = std::env::temp_dir().join(format!("nsh-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let scene = dir.join("plume.toml");
+        let text = "dimensions = 2\nsize = [8, 6]\nspacing = 0.25\n\n[fluid]\ndt = 0.1\n\n\
+                    [[emitter]]\nshape = 'box'\nmin = [0.5, 0.0]\nmax = [1.5, 0.5]\n\
+                    density = 1.0\nvelocity = [0.0, 0.5]\n\n\
+                    [run]\nend_time = 0.5\n\n\
+                    [output]\ndirectory = 'out'\nevery = 2\n\
+                    formats = ['png', 'vti', 'npz', 'checkpoint']\n";
+        std::fs::write(&scene, text).unwrap();
+        let options = Options {
+            progress_interval: std::time::Duration::ZERO,
+            ..Options::default()
+        };
+        let mut progress = Vec::new();
+        let summary = run_file(&scene, &options, &mut progress);
+
+        let out = dir.join("out");
+        let mut files = std::fs::read_dir(&out)
+            .map(|entries| {
+                let names = entries.map(|e| e.unwrap().file_name().into_string().unwrap());
+                names.collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        files.sort();
+        let diagnostics = std::fs::read_to_string(out.join("diagnostics.csv"));
+        let checkpoint = Simulation::<2>::load_checkpoint(out.join("checkpoint.bin"));
+        let archive = Npz::load(out.join("state_000004.npz"));
+
+        let overridden = Options {
+            run: Some(RunLength::Steps(3)),
+            output: Some(dir.join("elsewhere")),
+            ..Options::default()
+        };
+        let short = run_file(&scene, &overridden, std::io::sink());
+        let short_diagnostics = std::fs::read_to_string(dir.join("elsewhere/diagnostics.csv"));
+
+        std::fs::write(&scene, "dimensions = 2\nsize = [4, 4]\nspacing = 1\n").unwrap();
+        let endless = run_file(&scene, &Options::default(), std::io::sink());
+        std::fs::write(
+            &scene,
+            "dimensions = 3\nsize = [3, 3, 3]\nspacing = 1\n[run]\nsteps = 2\n\n\
+             [[emitter]]\nshape = 'sphere'\ncentre = [1.5, 1.5, 1.5]\nradius = 1\n\
+             velocity = [1e300, 1e300, 1e300]\n",
+        )
+        .unwrap();
+        let diverged = run_file(&scene, &Options::default(), std::io::sink());
+        let missing = run_file(
+            dir.join("missing.toml"),
+            &Options::default(),
+            std::io::sink(),
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // 0.5 / 0.1 is 5 steps, with outputs at steps 0, 2 and 4
+        let summary = summary.unwrap();
+        assert_eq!(summary.steps, 5);
+        assert_relative_eq!(summary.time, 0.5, epsilon = 1e-12);
+        assert_eq!(summary.outputs, 3);
+        assert_eq!(
+            files,
+            [
+                "checkpoint.bin",
+                "diagnostics.csv",
+                "frame_000000.png",
+                "frame_000001.png",
+                "frame_000002.png",
+                "state.pvd",
+                "state_000000.npz",
+                "state_000000.vti",
+                "state_000002.npz",
+                "state_000002.vti",
+                "state_000004.npz",
+                "state_000004.vti",
+            ]
+        );
+        let diagnostics = diagnostics.unwrap();
+        let rows = diagnostics.lines().collect::<Vec<_>>();
+        assert_eq!(rows[0], "step,time,mass,kinetic_energy,max_speed");
+        assert_eq!(rows.len(), 4);
+        assert!(rows[1].starts_with("0,0,0,0,0"));
+        let mass = rows[3].split(',').nth(2).unwrap().parse::<Float>().unwrap();
+        assert!(mass > 0.0);
+        assert_eq!(checkpoint.unwrap().steps, 4);
+        let archive = archive.unwrap();
+        assert_eq!(archive.metadata("step"), Some(4.0));
+        let density = archive.grid::<Float, 2>("density", 0.25).unwrap();
+        assert_eq!(density.size(), CoordInt([8, 6]));
+
+        let progress = String::from_utf8(progress).unwrap();
+        let lines = progress.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("step 1/5 (20.0%)  t = 0.1000  elapsed 0:00  eta "));
+        assert!(lines[4].starts_with("finished 5 steps (t = 0.5000) in "));
+
+        assert_eq!(short.unwrap().steps, 3);
+        assert_eq!(short_diagnostics.unwrap().lines().count(), 3);
+        let endless = endless.unwrap_err();
+        assert!(matches!(endless, BatchError::NoRunLength));
+        assert_eq!(endless.exit_code(), 2);
+        let diverged = diverged.unwrap_err();
+        assert!(matches!(diverged, BatchError::Diverged { step: 1, .. }));
+        assert_eq!(diverged.exit_code(), 3);
+        let missing = missing.unwrap_err();
+        assert!(matches!(missing, BatchError::Scene(SceneError::Io(_))));
+        assert_eq!(missing.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_lid_driven_cavity() {
+        use crate::simulation::validation::{
+            centreline_u, centreline_v, lid_driven_cavity, run_to_steady_state, GHIA_1982,
+        };
+
+        let mut sim = lid_driven_cavity::<Float>(4, 400.0, 0.05);
+        assert_eq!(sim.size(), CoordInt([4, 4]));
+        assert_relative_eq!(sim.delta(), 0.25);
+        assert_relative_eq!(sim.params.viscosity, 1.0 / 400.0);
+        assert_eq!(
+            *sim.boundary.face(1, 1),
+            Boundary::Moving(Vector([1.0, 0.0]))
+        );
+
+        // u = y, offset in opposite directions either side of the centreline;
+        // v = i along each row
+        for (CoordInt([i, j]), velocity) in sim.velocities.indexed_iter_mut() {
+            let x_offset = 0.1 * (i as Float - 1.5);
+            *velocity = Vector([(j as Float + 0.5) / 4.0 + x_offset, i as Float]);
+        }
+        let ys = [0.0, 0.1, 0.375, 0.5, 0.9, 1.0];
+        for (u, y) in centreline_u(&sim, &ys).into_iter().zip(ys) {
+            assert_relative_eq!(u, y, epsilon = 1e-12);
+        }
+        let v = centreline_v(&sim, &[0.0, 0.125, 0.5, 0.875, 0.9375, 1.0]);
+        assert_eq!(v, vec![0.0, 0.0, 1.5, 3.0, 1.5, 0.0]);
+
+        // bounded by the time limit when the flow never settles
+        let rate = run_to_steady_state(&mut sim, 0.0, 0.15);
+        assert!(rate > 0.0);
+        assert_eq!(sim.steps, 3);
+
+        for profile in GHIA_1982 {
+            assert_eq!(profile.u[0], (1.0, 1.0));
+            assert_eq!(profile.u[16], (0.0, 0.0));
+            assert_eq!(profile.v[8].0, 0.5);
+        }
+    }
+
+    #[test]
+    fn test_taylor_green() {
+        use crate::simulation::validation::{observed_order, TaylorGreen};
+
+        let vortex = TaylorGreen {
+            amplitude: 2.0,
+            viscosity: 0.25,
+        };
+        let sim = vortex.simulation::<Float>(8, 0.1);
+        assert!(sim.boundary.is_periodic(0) && sim.boundary.is_periodic(1));
+        assert_relative_eq!(sim.delta(), std::f64::consts::TAU / 8.0);
+        let initial = vortex.error(&sim);
+        assert_eq!((initial.l2, initial.linf), (0.0, 0.0));
+
+        let [u, v] = vortex.velocity(0.5, 1.5, 2.0);
+        let decay = 2.0 * (-1.0 as Float).exp();
+        assert_relative_eq!(u, decay * 0.5f64.sin() * 1.5f64.cos(), epsilon = 1e-12);
+        assert_relative_eq!(v, -decay * 0.5f64.cos() * 1.5f64.sin(), epsilon = 1e-12);
+
+        // at rest, the whole vortex is the error, peaking below the amplitude
+        // since the cell centres miss the maxima
+        let mut still = Simulation::<2>::new(sim.size(), sim.delta());
+        still.params.viscosity = 0.25;
+        still.time = 2.0;
+        let errors = vortex.error(&still);
+        assert!(errors.linf < decay && errors.linf > 0.8 * decay);
+        assert!(errors.l2 < errors.linf);
+
+        assert_relative_eq!(observed_order(0.4, 0.1, 2.0), 2.0);
+        assert_relative_eq!(observed_order(0.3, 0.1, 3.0), 1.0);
+    }
+
+    #[cfg(feature = "serde")]
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    #[cfg(feature = "serde")]
+    fn assert_grid_round_trip<T, const D: usize>(grid: &Grid<T, D>)
+    where
+        T: Default
+            + Clone
+            + PartialEq
+            + std::fmt::Debug
+            + serde::Serialize
+            + serde::de::DeserializeOwned,
+    {
+        let back = round_trip(grid);
+        assert_eq!(back.size(), grid.size());
+        assert_eq!(back.delta(), grid.delta());
+        assert_eq!(back.as_slice(), grid.as_slice());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let line = polynomial_grid(CoordInt([7]), 0.5, |p| p[0] * p[0]);
+        assert_grid_round_trip(&line);
+        assert_grid_round_trip(&ramp_grid(4, 3));
+        let cube = polynomial_grid(CoordInt([3, 2, 4]), 0.1, |p| p[0] - p[1] * p[2]);
+        assert_grid_round_trip(&cube);
+        assert_grid_round_trip(&rotating_field_2d(5, 4, 0.2));
+        let mut single = Grid::<f32, 3>::new(CoordInt([2, 2, 2]), 0.25);
+        single[CoordInt([1, 0, 1])] = 1.5;
+        assert_grid_round_trip(&single);
+
+        assert_eq!(round_trip(&CoordInt([4])), CoordInt([4]));
+        assert_eq!(round_trip(&CoordInt([-1, 7, 3])), CoordInt([-1, 7, 3]));
+        assert_eq!(round_trip(&Vector([0.5, -2.0])), Vector([0.5, -2.0]));
+        let k = Matrix([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        assert_eq!(round_trip(&k), k);
+
+        let mut simulation = Simulation::<2>::new(CoordInt([3, 4]), 0.5);
+        simulation.densities[CoordInt([2, 1])] = 0.75;
+        simulation.velocities[CoordInt([0, 3])] = Vector([1.0, -1.0]);
+        let back = round_trip(&simulation);
+        assert_eq!(back.densities.as_slice(), simulation.densities.as_slice());
+        assert_eq!(back.velocities.as_slice(), simulation.velocities.as_slice());
+
+        let simulation = cavity_simulation();
+        assert_same_state(&round_trip(&simulation), &simulation);
+        let one_sided = r#"{"faces":[["Periodic","NoSlip"],["NoSlip","NoSlip"]],"obstacles":{"size":[1,1],"delta":1.0,"data":[false]}}"#;
+        let error =
+            serde_json::from_str::<crate::simulation::boundary::BoundaryConfig<2>>(one_sided)
+                .err()
+                .unwrap();
+        assert!(error.to_string().contains("periodic on one face only"));
+
+        assert_eq!(
+            serde_json::to_string(&Grid::filled(CoordInt([2, 1]), 1.0, 3.0)).unwrap(),
+            r#"{"size":[2,1],"delta":1.0,"data":[3.0,3.0]}"#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_malformed_input() {
+        let short = r#"{"size":[2,2],"delta":1.0,"data":[1.0,2.0,3.0]}"#;
+        let error = serde_json::from_str::<Grid<Float, 2>>(short).err().unwrap();
+        assert!(error.to_string().contains("expected 4 components, found 3"));
+
+        let negative = r#"{"size":[-2,2],"delta":1.0,"data":[]}"#;
+        assert!(serde_json::from_str::<Grid<Float, 2>>(negative).is_err());
+        let overflowing = r#"{"size":[65536,65536,0],"delta":1.0,"data":[]}"#;
+        let error = serde_json::from_str::<Grid<Float, 3>>(overflowing)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("grid shape mismatch"));
+        let huge = format!(
+            r#"{{"size":[{0},{0},{0}],"delta":1.0,"data":[]}}"#,
+            i32::MAX
+        );
+        assert!(serde_json::from_str::<Grid<Float, 3>>(&huge).is_err());
+        let wrong_dimension = r#"{"size":[2,2,1],"delta":1.0,"data":[0,0,0,0]}"#;
+        assert!(serde_json::from_str::<Grid<Float, 2>>(wrong_dimension).is_err());
+
+        assert!(serde_json::from_str::<CoordInt<3>>("[1,2]").is_err());
+        assert!(serde_json::from_str::<Vector<2>>("[1.0,2.0,3.0]").is_err());
+    }
 }
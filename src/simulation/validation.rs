@@ -0,0 +1,243 @@
+//! Reference problems for checking the solver against published results.
+//!
+//! The lid-driven cavity is the unit square with no-slip walls whose top
+//! face (upper side of axis 1) slides along axis 0 at unit speed, so that the
+//! Reynolds number is `1 / viscosity`. Its steady state is compared with the
+//! multigrid solutions of Ghia, Ghia & Shin, "High-Re solutions for
+//! incompressible flow using the Navier-Stokes equations and a multigrid
+//! method", J. Comput. Phys. 48 (1982) 387–411.
+//!
+//! The Taylor–Green vortex is a periodic array of counter-rotating vortices
+//! on `[0, 2π]²` that keeps its shape while decaying as `exp(-2νt)`, which
+//! gives an exact solution to measure the discretisation error against.
+
+use std::f64::consts::TAU;
+
+use super::{
+    boundary::Boundary,
+    grid::{CoordInt, Int},
+    scalar::Scalar,
+    simulation::Simulation,
+    vector::{Float, Vector},
+};
+
+/// Centreline velocities of the steady lid-driven cavity from Tables I and
+/// II of Ghia, Ghia & Shin (1982), computed on a 129×129 grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GhiaProfile {
+    pub reynolds: Float,
+    /// `(y, u)` along the vertical centreline `x = 0.5`.
+    pub u: [(Float, Float); 17],
+    /// `(x, v)` along the horizontal centreline `y = 0.5`.
+    pub v: [(Float, Float); 17],
+}
+
+const GHIA_Y: [Float; 17] = [
+    1.0000, 0.9766, 0.9688, 0.9609, 0.9531, 0.8516, 0.7344, 0.6172, 0.5000, 0.4531, 0.2813, 0.1719,
+    0.1016, 0.0703, 0.0625, 0.0547, 0.0000,
+];
+const GHIA_X: [Float; 17] = [
+    1.0000, 0.9688, 0.9609, 0.9531, 0.9453, 0.9063, 0.8594, 0.8047, 0.5000, 0.2344, 0.2266, 0.1563,
+    0.0938, 0.0781, 0.0703, 0.0625, 0.0000,
+];
+
+const fn profile(reynolds: Float, u: [Float; 17], v: [Float; 17]) -> GhiaProfile {
+    let mut profile = GhiaProfile {
+        reynolds,
+        u: [(0.0, 0.0); 17],
+        v: [(0.0, 0.0); 17],
+    };
+    let mut i = 0;
+    while i < 17 {
+        profile.u[i] = (GHIA_Y[i], u[i]);
+        profile.v[i] = (GHIA_X[i], v[i]);
+        i += 1;
+    }
+    profile
+}
+
+/// The published profiles at Re = 100, 400 and 1000.
+pub const GHIA_1982: [GhiaProfile; 3] = [
+    profile(
+        100.0,
+        [
+            1.00000, 0.84123, 0.78871, 0.73722, 0.68717, 0.23151, 0.00332, -0.13641, -0.20581,
+            -0.21090, -0.15662, -0.10150, -0.06434, -0.04775, -0.04192, -0.03717, 0.00000,
+        ],
+        [
+            0.00000, -0.05906, -0.07391, -0.08864, -0.10313, -0.16914, -0.22445, -0.24533, 0.05454,
+            0.17527, 0.17507, 0.16077, 0.12317, 0.10890, 0.10091, 0.09233, 0.00000,
+        ],
+    ),
+    profile(
+        400.0,
+        [
+            1.00000, 0.75837, 0.68439, 0.61756, 0.55892, 0.29093, 0.16256, 0.02135, -0.11477,
+            -0.17119, -0.32726, -0.24299, -0.14612, -0.10338, -0.09266, -0.08186, 0.00000,
+        ],
+        [
+            0.00000, -0.12146, -0.15663, -0.19254, -0.22847, -0.23827, -0.44993, -0.38598, 0.05186,
+            0.30174, 0.30203, 0.28124, 0.22965, 0.20920, 0.19713, 0.18360, 0.00000,
+        ],
+    ),
+    profile(
+        1000.0,
+        [
+            1.00000, 0.65928, 0.57492, 0.51117, 0.46604, 0.33304, 0.18719, 0.05702, -0.06080,
+            -0.10648, -0.27805, -0.38289, -0.29730, -0.22220, -0.20196, -0.18109, 0.00000,
+        ],
+        [
+            0.00000, -0.21388, -0.27669, -0.33714, -0.39188, -0.51550, -0.42665, -0.31966, 0.02526,
+            0.32235, 0.33075, 0.37095, 0.32627, 0.30353, 0.29012, 0.27485, 0.00000,
+        ],
+    ),
+];
+
+/// A lid-driven cavity of `n`×`n` cells at rest, at the given Reynolds number.
+pub fn lid_driven_cavity<S: Scalar>(n: usize, reynolds: Float, dt: Float) -> Simulation<2, S> {
+    let mut sim = Simulation::new(CoordInt([n as Int; 2]), 1.0 / n as Float);
+    sim.params.dt = S::from_f64(dt);
+    sim.params.viscosity = S::from_f64(1.0 / reynolds);
+    sim.boundary
+        .set_face(1, 1, Boundary::Moving(Vector([S::ONE, S::ZERO])));
+    sim
+}
+
+/// Steps until the largest velocity change per unit time falls below
+/// `tolerance`, or until `max_time`. Returns the last rate of change.
+pub fn run_to_steady_state<S: Scalar>(
+    sim: &mut Simulation<2, S>,
+    tolerance: Float,
+    max_time: Float,
+) -> Float {
+    let mut rate = Float::INFINITY;
+    while rate > tolerance && sim.time < max_time {
+        let before = sim.velocities.as_slice().to_vec();
+        sim.step();
+        let change = sim
+            .velocities
+            .as_slice()
+            .iter()
+            .zip(&before)
+            .map(|(&after, &before)| (after - before).norm().to_f64())
+            .fold(0.0, Float::max);
+        rate = change / sim.params.dt.to_f64();
+    }
+    rate
+}
+
+/// Piecewise-linear interpolation at `t` in `[0, 1]` through cell-centred
+/// `values`, with `lower` and `upper` the values on the walls at 0 and 1.
+fn interpolate(values: &[Float], lower: Float, upper: Float, t: Float) -> Float {
+    let n = values.len() as Float;
+    let points = std::iter::once((0.0, lower))
+        .chain(
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| ((i as Float + 0.5) / n, v)),
+        )
+        .chain(std::iter::once((1.0, upper)))
+        .collect::<Vec<_>>();
+    let t = t.clamp(0.0, 1.0);
+    let k = points
+        .partition_point(|&(x, _)| x <= t)
+        .clamp(1, points.len() - 1);
+    let ((x0, a), (x1, b)) = (points[k - 1], points[k]);
+    a + (b - a) * (t - x0) / (x1 - x0)
+}
+
+/// Axis-0 velocity along the vertical centreline at heights `ys`.
+pub fn centreline_u<S: Scalar>(sim: &Simulation<2, S>, ys: &[Float]) -> Vec<Float> {
+    let [nx, ny] = sim.size().0;
+    // average the two columns either side of x = 0.5 (one column if n is odd)
+    let column = (0..ny)
+        .map(|j| {
+            let u = |i: Int| sim.velocities[CoordInt([i, j])][0].to_f64();
+            0.5 * (u((nx - 1) / 2) + u(nx / 2))
+        })
+        .collect::<Vec<_>>();
+    ys.iter()
+        .map(|&y| interpolate(&column, 0.0, 1.0, y))
+        .collect()
+}
+
+/// Axis-1 velocity along the horizontal centreline at positions `xs`.
+pub fn centreline_v<S: Scalar>(sim: &Simulation<2, S>, xs: &[Float]) -> Vec<Float> {
+    let [nx, ny] = sim.size().0;
+    let row = (0..nx)
+        .map(|i| {
+            let v = |j: Int| sim.velocities[CoordInt([i, j])][1].to_f64();
+            0.5 * (v((ny - 1) / 2) + v(ny / 2))
+        })
+        .collect::<Vec<_>>();
+    xs.iter().map(|&x| interpolate(&row, 0.0, 0.0, x)).collect()
+}
+
+/// A Taylor–Green vortex on the periodic square `[0, 2π]²`, with velocity
+/// `A (sin x cos y, -cos x sin y) exp(-2νt)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TaylorGreen {
+    pub amplitude: Float,
+    pub viscosity: Float,
+}
+
+/// Discrete norms of the difference between a computed and an exact field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Errors {
+    /// Root mean square over the cells of the velocity error magnitude.
+    pub l2: Float,
+    /// Largest velocity error magnitude.
+    pub linf: Float,
+}
+
+impl TaylorGreen {
+    /// The exact velocity at `(x, y)` and time `t`.
+    pub fn velocity(&self, x: Float, y: Float, t: Float) -> [Float; 2] {
+        let scale = self.amplitude * (-2.0 * self.viscosity * t).exp();
+        [x.sin() * y.cos() * scale, -x.cos() * y.sin() * scale]
+    }
+
+    /// The initial state on `n`×`n` cells.
+    pub fn simulation<S: Scalar>(&self, n: usize, dt: Float) -> Simulation<2, S> {
+        let mut sim = Simulation::new(CoordInt([n as Int; 2]), TAU / n as Float);
+        sim.params.dt = S::from_f64(dt);
+        sim.params.viscosity = S::from_f64(self.viscosity);
+        sim.boundary.set_axis(0, Boundary::Periodic);
+        sim.boundary.set_axis(1, Boundary::Periodic);
+        let delta = sim.delta();
+        for (coord, velocity) in sim.velocities.indexed_iter_mut() {
+            let [x, y] = coord.0.map(|i| (i as Float + 0.5) * delta);
+            *velocity = Vector(self.velocity(x, y, 0.0).map(S::from_f64));
+        }
+        sim
+    }
+
+    /// The error of `sim`, set up by [`TaylorGreen::simulation`], against the
+    /// exact solution at its current time.
+    pub fn error<S: Scalar>(&self, sim: &Simulation<2, S>) -> Errors {
+        let delta = sim.delta();
+        let [nx, ny] = sim.size().0;
+        let (mut sum, mut linf) = (0.0, 0.0 as Float);
+        for i in 0..nx {
+            for j in 0..ny {
+                let [x, y] = [i, j].map(|k| (k as Float + 0.5) * delta);
+                let exact = self.velocity(x, y, sim.time);
+                let computed = sim.velocities[CoordInt([i, j])].0.map(|c| c.to_f64());
+                let error = (computed[0] - exact[0]).hypot(computed[1] - exact[1]);
+                sum += error * error;
+                linf = linf.max(error);
+            }
+        }
+        Errors {
+            l2: (sum / (nx * ny) as Float).sqrt(),
+            linf,
+        }
+    }
+}
+
+/// Order of convergence between two errors whose resolution differs by
+/// `ratio`, for instance 2 when halving the grid spacing or time step.
+pub fn observed_order(coarse: Float, fine: Float, ratio: Float) -> Float {
+    (coarse / fine).ln() / ratio.ln()
+}
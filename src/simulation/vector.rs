@@ -1,47 +1,161 @@
 use approx::{AbsDiffEq, RelativeEq, UlpsEq};
 
-use super::grid::CoordInt;
+use super::{
+    error::GridError,
+    grid::CoordInt,
+    scalar::{GridValue, Scalar},
+};
 
+/// Default precision, used wherever the scalar type is not spelled out.
 pub type Float = f64;
 
 #[derive(Debug, Clone, Copy)]
-pub struct Vector<const D: usize>(pub [Float; D]);
+pub struct Vector<const D: usize, S: Scalar = Float>(pub [S; D]);
 
-impl<const D: usize> Default for Vector<D> {
+impl<const D: usize, S: Scalar> Default for Vector<D, S> {
     fn default() -> Self {
-        Self([0.0; D])
+        Self([S::ZERO; D])
     }
 }
 
-impl<const D: usize> Vector<D> {
-    pub fn from_coord_int(value: CoordInt<D>, delta: Float) -> Self {
-        value
-            .0
+impl<const D: usize, S: Scalar> Vector<D, S> {
+    pub fn from_coord_int(value: CoordInt<D>, delta: S) -> Self {
+        Vector(value.0.map(|i| S::from_f64(i as f64) * delta))
+    }
+
+    pub fn dot(&self, other: &Self) -> S {
+        self.0
             .iter()
-            .map(|i| *i as Float * delta)
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap()
+            .zip(other.0.iter())
+            .map(|(a, b)| *a * *b)
+            .sum()
+    }
+
+    pub fn norm_squared(&self) -> S {
+        self.dot(self)
+    }
+
+    pub fn norm(&self) -> S {
+        self.norm_squared().sqrt()
+    }
+
+    /// Unit vector in the same direction; the zero vector is returned unchanged.
+    pub fn normalize(self) -> Self {
+        let norm = self.norm();
+        if norm == S::ZERO {
+            self
+        } else {
+            self / norm
+        }
+    }
+
+    /// Component-wise minimum.
+    pub fn min(self, other: Self) -> Self {
+        Vector(std::array::from_fn(|i| self.0[i].min(other.0[i])))
+    }
+
+    /// Component-wise maximum.
+    pub fn max(self, other: Self) -> Self {
+        Vector(std::array::from_fn(|i| self.0[i].max(other.0[i])))
+    }
+
+    /// Component-wise absolute value.
+    pub fn abs(self) -> Self {
+        Vector(self.0.map(S::abs))
+    }
+}
+
+impl<S: Scalar> Vector<3, S> {
+    pub fn cross(&self, other: &Self) -> Self {
+        let [a1, a2, a3] = self.0;
+        let [b1, b2, b3] = other.0;
+        Vector([a2 * b3 - a3 * b2, a3 * b1 - a1 * b3, a1 * b2 - a2 * b1])
+    }
+}
+
+impl<S: Scalar> Vector<2, S> {
+    /// The vector rotated a quarter turn counter-clockwise, `(-y, x)`.
+    pub fn perp(&self) -> Self {
+        Vector([-self.0[1], self.0[0]])
     }
 }
 
-impl<const D: usize> TryFrom<Vec<Float>> for Vector<D> {
-    type Error = &'static str;
+impl<const D: usize, S: Scalar> GridValue for Vector<D, S> {
+    type Scalar = S;
 
-    fn try_from(value: Vec<Float>) -> Result<Self, Self::Error> {
+    fn all_finite(&self) -> bool {
+        self.0.iter().all(|v| v.is_finite())
+    }
+}
+
+impl<const D: usize, S: Scalar> TryFrom<Vec<S>> for Vector<D, S> {
+    type Error = GridError;
+
+    fn try_from(value: Vec<S>) -> Result<Self, Self::Error> {
         if value.len() != D {
-            return Err("Vector dimension mismatch");
-        }
-        let mut array = [0.0; D];
-        for i in 0..D {
-            array[i] = value[i];
+            return Err(GridError::DimensionMismatch {
+                expected: D,
+                found: value.len(),
+            });
         }
+        let mut array = [S::ZERO; D];
+        array.copy_from_slice(&value);
         Ok(Vector(array))
     }
 }
 
+impl<const D: usize, S: Scalar> From<[S; D]> for Vector<D, S> {
+    fn from(value: [S; D]) -> Self {
+        Vector(value)
+    }
+}
+
+impl<const D: usize, S: Scalar> From<Vector<D, S>> for [S; D] {
+    fn from(value: Vector<D, S>) -> Self {
+        value.0
+    }
+}
+
+impl<S: Scalar> From<(S, S)> for Vector<2, S> {
+    fn from((x, y): (S, S)) -> Self {
+        Vector([x, y])
+    }
+}
+
+impl<S: Scalar> From<Vector<2, S>> for (S, S) {
+    fn from(value: Vector<2, S>) -> Self {
+        (value.0[0], value.0[1])
+    }
+}
+
+impl<S: Scalar> From<(S, S, S)> for Vector<3, S> {
+    fn from((x, y, z): (S, S, S)) -> Self {
+        Vector([x, y, z])
+    }
+}
+
+impl<S: Scalar> From<Vector<3, S>> for (S, S, S) {
+    fn from(value: Vector<3, S>) -> Self {
+        (value.0[0], value.0[1], value.0[2])
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::Index<usize> for Vector<D, S> {
+    type Output = S;
+
+    fn index(&self, index: usize) -> &S {
+        &self.0[index]
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::IndexMut<usize> for Vector<D, S> {
+    fn index_mut(&mut self, index: usize) -> &mut S {
+        &mut self.0[index]
+    }
+}
+
 // implement addition for vectors
-impl<const D: usize> std::ops::Add for Vector<D> {
+impl<const D: usize, S: Scalar> std::ops::Add for Vector<D, S> {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
@@ -54,7 +168,7 @@ impl<const D: usize> std::ops::Add for Vector<D> {
 }
 
 // implement subtraction for vectors
-impl<const D: usize> std::ops::Sub for Vector<D> {
+impl<const D: usize, S: Scalar> std::ops::Sub for Vector<D, S> {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
@@ -66,11 +180,55 @@ impl<const D: usize> std::ops::Sub for Vector<D> {
     }
 }
 
+impl<const D: usize, S: Scalar> std::ops::Neg for Vector<D, S> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Vector(self.0.map(|v| -v))
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::AddAssign for Vector<D, S> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::SubAssign for Vector<D, S> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::MulAssign<S> for Vector<D, S> {
+    fn mul_assign(&mut self, scalar: S) {
+        *self = *self * scalar;
+    }
+}
+
+impl<const D: usize, S: Scalar> std::ops::DivAssign<S> for Vector<D, S> {
+    fn div_assign(&mut self, scalar: S) {
+        *self = *self / scalar;
+    }
+}
+
+impl<const D: usize, S: Scalar> std::iter::Sum for Vector<D, S> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |acc, v| acc + v)
+    }
+}
+
+impl<'a, const D: usize, S: Scalar> std::iter::Sum<&'a Vector<D, S>> for Vector<D, S> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |acc, v| acc + *v)
+    }
+}
+
 // implement vector - scalar product
-impl<const D: usize> std::ops::Mul<Float> for Vector<D> {
+impl<const D: usize, S: Scalar> std::ops::Mul<S> for Vector<D, S> {
     type Output = Self;
 
-    fn mul(self, scalar: Float) -> Self {
+    fn mul(self, scalar: S) -> Self {
         let mut result = self;
         for i in 0..D {
             result.0[i] *= scalar;
@@ -80,19 +238,26 @@ impl<const D: usize> std::ops::Mul<Float> for Vector<D> {
 }
 
 // implement scalar - vector product
-impl<const D: usize> std::ops::Mul<Vector<D>> for Float {
-    type Output = Vector<D>;
+macro_rules! impl_scalar_vector_mul {
+    ($t:ty) => {
+        impl<const D: usize> std::ops::Mul<Vector<D, $t>> for $t {
+            type Output = Vector<D, $t>;
 
-    fn mul(self, vector: Vector<D>) -> Vector<D> {
-        vector * self
-    }
+            fn mul(self, vector: Vector<D, $t>) -> Vector<D, $t> {
+                vector * self
+            }
+        }
+    };
 }
 
+impl_scalar_vector_mul!(f32);
+impl_scalar_vector_mul!(f64);
+
 // implement vector - scalar division
-impl<const D: usize> std::ops::Div<Float> for Vector<D> {
+impl<const D: usize, S: Scalar> std::ops::Div<S> for Vector<D, S> {
     type Output = Self;
 
-    fn div(self, scalar: Float) -> Self {
+    fn div(self, scalar: S) -> Self {
         let mut result = self;
         for i in 0..D {
             result.0[i] /= scalar;
@@ -102,27 +267,30 @@ impl<const D: usize> std::ops::Div<Float> for Vector<D> {
 }
 
 // implement equality
-impl<const D: usize> std::cmp::PartialEq for Vector<D> {
+impl<const D: usize, S: Scalar> std::cmp::PartialEq for Vector<D, S> {
     fn eq(&self, other: &Self) -> bool {
         self.0.eq(&other.0)
     }
 }
 
-impl<const D: usize> AbsDiffEq for Vector<D> {
-    type Epsilon = Float;
+impl<const D: usize, S: Scalar> AbsDiffEq for Vector<D, S> {
+    type Epsilon = S;
 
     fn default_epsilon() -> Self::Epsilon {
-        Float::default_epsilon()
+        S::default_epsilon()
     }
 
     fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
-        self.0.abs_diff_eq(&other.0, epsilon)
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(a, b)| a.abs_diff_eq(b, epsilon))
     }
 }
 
-impl<const D: usize> RelativeEq for Vector<D> {
+impl<const D: usize, S: Scalar> RelativeEq for Vector<D, S> {
     fn default_max_relative() -> Self::Epsilon {
-        Float::default_max_relative()
+        S::default_max_relative()
     }
 
     fn relative_eq(
@@ -131,16 +299,22 @@ impl<const D: usize> RelativeEq for Vector<D> {
         epsilon: Self::Epsilon,
         max_relative: Self::Epsilon,
     ) -> bool {
-        self.0.relative_eq(&other.0, epsilon, max_relative)
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(a, b)| a.relative_eq(b, epsilon, max_relative))
     }
 }
 
-impl<const D: usize> UlpsEq for Vector<D> {
+impl<const D: usize, S: Scalar> UlpsEq for Vector<D, S> {
     fn default_max_ulps() -> u32 {
-        Float::default_max_ulps()
+        S::default_max_ulps()
     }
 
     fn ulps_eq(&self, other: &Self, epsilon: Self::Epsilon, max_ulps: u32) -> bool {
-        self.0.ulps_eq(&other.0, epsilon, max_ulps)
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(a, b)| a.ulps_eq(b, epsilon, max_ulps))
     }
 }
@@ -0,0 +1,311 @@
+//! Borrowed windows and lower-dimensional slices of a `Grid`.
+
+use super::{
+    grid::{interpolate, CoordInt, Grid},
+    iter::{CoordRange, RegionIterMut},
+    scalar::GridValue,
+    sparse_grid::SparseGrid,
+    vector::{Float, Vector},
+};
+
+/// Read access to cells, shared by grids and views.
+///
+/// Code that only samples a field, such as the renderer and the exporters,
+/// takes `&impl GridRead<T, D>` so it accepts a whole grid as well as a view.
+pub trait GridRead<T, const D: usize> {
+    fn size(&self) -> CoordInt<D>;
+    fn delta(&self) -> Float;
+    fn get(&self, index: &CoordInt<D>) -> Option<&T>;
+}
+
+impl<T: Default + Clone, const D: usize> GridRead<T, D> for Grid<T, D> {
+    fn size(&self) -> CoordInt<D> {
+        Grid::size(self)
+    }
+
+    fn delta(&self) -> Float {
+        Grid::delta(self)
+    }
+
+    fn get(&self, index: &CoordInt<D>) -> Option<&T> {
+        Grid::get(self, index)
+    }
+}
+
+impl<T: Default + Clone + PartialEq, const D: usize> GridRead<T, D> for SparseGrid<T, D> {
+    fn size(&self) -> CoordInt<D> {
+        SparseGrid::size(self)
+    }
+
+    fn delta(&self) -> Float {
+        SparseGrid::delta(self)
+    }
+
+    fn get(&self, index: &CoordInt<D>) -> Option<&T> {
+        SparseGrid::get(self, index)
+    }
+}
+
+/// Maps `E`-dimensional view coordinates onto `D`-dimensional grid coordinates.
+///
+/// View axis `k` runs along grid axis `axes[k]`, starting at `origin`; grid
+/// axes not listed in `axes` stay fixed at their `origin` value.
+#[derive(Clone, Copy, Debug)]
+struct Mapping<const D: usize, const E: usize> {
+    origin: CoordInt<D>,
+    axes: [usize; E],
+    size: CoordInt<E>,
+}
+
+impl<const D: usize, const E: usize> Mapping<D, E> {
+    fn window<T: Default + Clone>(
+        grid: &Grid<T, D>,
+        lo: CoordInt<D>,
+        hi: CoordInt<D>,
+    ) -> Option<Self> {
+        if E != D {
+            return None;
+        }
+        let grid_size = grid.size();
+        let valid = (0..D).all(|i| 0 <= lo.0[i] && lo.0[i] <= hi.0[i] && hi.0[i] <= grid_size.0[i]);
+        valid.then(|| Mapping {
+            origin: lo,
+            axes: std::array::from_fn(|k| k),
+            size: CoordInt(std::array::from_fn(|k| hi.0[k] - lo.0[k])),
+        })
+    }
+
+    fn slice<T: Default + Clone>(grid: &Grid<T, D>, axis: usize, index: i32) -> Option<Self> {
+        let grid_size = grid.size();
+        if E + 1 != D || axis >= D || index < 0 || index >= grid_size.0[axis] {
+            return None;
+        }
+        let mut origin = CoordInt::<D>::default();
+        origin.0[axis] = index;
+        let axes: [usize; E] = std::array::from_fn(|k| if k < axis { k } else { k + 1 });
+        Some(Mapping {
+            origin,
+            axes,
+            size: CoordInt(axes.map(|a| grid_size.0[a])),
+        })
+    }
+
+    fn contains(&self, index: &CoordInt<E>) -> bool {
+        (0..E).all(|k| 0 <= index.0[k] && index.0[k] < self.size.0[k])
+    }
+
+    fn grid_coord(&self, index: &CoordInt<E>) -> Option<CoordInt<D>> {
+        if !self.contains(index) {
+            return None;
+        }
+        let mut coord = self.origin;
+        for (k, &axis) in self.axes.iter().enumerate() {
+            coord.0[axis] += index.0[k];
+        }
+        Some(coord)
+    }
+
+    /// Inverse of `grid_coord` for a grid coordinate known to be in the view.
+    fn view_coord(&self, coord: &CoordInt<D>) -> CoordInt<E> {
+        CoordInt(std::array::from_fn(|k| {
+            coord.0[self.axes[k]] - self.origin.0[self.axes[k]]
+        }))
+    }
+}
+
+/// A borrowed `E`-dimensional window or slice of a `Grid<T, D>`.
+pub struct GridView<'a, T, const D: usize, const E: usize> {
+    grid: &'a Grid<T, D>,
+    mapping: Mapping<D, E>,
+}
+
+/// A mutably borrowed `E`-dimensional window or slice of a `Grid<T, D>`.
+pub struct GridViewMut<'a, T, const D: usize, const E: usize> {
+    grid: &'a mut Grid<T, D>,
+    mapping: Mapping<D, E>,
+}
+
+impl<T: Default + Clone, const D: usize> Grid<T, D> {
+    /// View of the box `[lo, hi)`, or `None` if it does not fit in the grid.
+    pub fn view(&self, lo: CoordInt<D>, hi: CoordInt<D>) -> Option<GridView<'_, T, D, D>> {
+        Mapping::window(self, lo, hi).map(|mapping| GridView {
+            grid: self,
+            mapping,
+        })
+    }
+
+    pub fn view_mut(
+        &mut self,
+        lo: CoordInt<D>,
+        hi: CoordInt<D>,
+    ) -> Option<GridViewMut<'_, T, D, D>> {
+        Mapping::window(self, lo, hi).map(|mapping| GridViewMut {
+            grid: self,
+            mapping,
+        })
+    }
+
+    /// The `E = D - 1` dimensional slice where `axis` is fixed at `index`.
+    ///
+    /// The remaining axes keep their order. Returns `None` if `E` is not
+    /// `D - 1` or `index` is outside the grid.
+    pub fn slice<const E: usize>(&self, axis: usize, index: i32) -> Option<GridView<'_, T, D, E>> {
+        Mapping::slice(self, axis, index).map(|mapping| GridView {
+            grid: self,
+            mapping,
+        })
+    }
+
+    pub fn slice_mut<const E: usize>(
+        &mut self,
+        axis: usize,
+        index: i32,
+    ) -> Option<GridViewMut<'_, T, D, E>> {
+        Mapping::slice(self, axis, index).map(|mapping| GridViewMut {
+            grid: self,
+            mapping,
+        })
+    }
+}
+
+impl<'a, T: Default + Clone, const D: usize, const E: usize> GridView<'a, T, D, E> {
+    pub fn size(&self) -> CoordInt<E> {
+        self.mapping.size
+    }
+
+    pub fn delta(&self) -> Float {
+        self.grid.delta()
+    }
+
+    pub fn get(&self, index: &CoordInt<E>) -> Option<&'a T> {
+        let grid = self.grid;
+        self.mapping
+            .grid_coord(index)
+            .and_then(|coord| grid.get(&coord))
+    }
+
+    /// Iterates over `(view coord, &value)` in row-major view order.
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (CoordInt<E>, &'a T)> + ExactSizeIterator {
+        let grid = self.grid;
+        let mapping = self.mapping;
+        CoordRange::new(CoordInt::default(), mapping.size).map(move |index| {
+            let coord = mapping.grid_coord(&index).unwrap();
+            (index, &grid.as_slice()[grid.flatten_index(&coord)])
+        })
+    }
+
+    /// Copies the viewed cells into a new grid with the same spacing.
+    pub fn to_grid(&self) -> Grid<T, E> {
+        let mut out = Grid::new(self.size(), self.delta());
+        for (dst, (_, src)) in out.iter_mut().zip(self.iter()) {
+            *dst = src.clone();
+        }
+        out
+    }
+}
+
+impl<T: GridValue, const D: usize, const E: usize> GridView<'_, T, D, E> {
+    /// Interpolates at a position measured from the view's first cell.
+    pub fn get_at(&self, pos: &Vector<E, T::Scalar>) -> T {
+        interpolate(self.size(), self.delta(), pos, |index| {
+            self.get(index).unwrap().clone()
+        })
+    }
+}
+
+impl<T: Default + Clone, const D: usize, const E: usize> GridViewMut<'_, T, D, E> {
+    pub fn size(&self) -> CoordInt<E> {
+        self.mapping.size
+    }
+
+    pub fn delta(&self) -> Float {
+        self.grid.delta()
+    }
+
+    pub fn get(&self, index: &CoordInt<E>) -> Option<&T> {
+        self.mapping
+            .grid_coord(index)
+            .and_then(|coord| self.grid.get(&coord))
+    }
+
+    pub fn get_mut(&mut self, index: &CoordInt<E>) -> Option<&mut T> {
+        self.mapping
+            .grid_coord(index)
+            .and_then(|coord| self.grid.get_mut(&coord))
+    }
+
+    pub fn as_view(&self) -> GridView<'_, T, D, E> {
+        GridView {
+            grid: self.grid,
+            mapping: self.mapping,
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (CoordInt<E>, &T)> + ExactSizeIterator {
+        self.as_view().iter()
+    }
+
+    /// Iterates over `(view coord, &mut value)` in row-major view order,
+    /// visiting only the viewed cells.
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (CoordInt<E>, &mut T)> + ExactSizeIterator {
+        let mapping = self.mapping;
+        // When the last view axis is the grid's last axis, each view row is
+        // one contiguous run of cells; otherwise every cell is its own run.
+        let mut hi = mapping.size;
+        let mut row = 1;
+        if E > 0 && mapping.axes[E - 1] == D - 1 {
+            row = hi.0[E - 1] as usize;
+            hi.0[E - 1] = hi.0[E - 1].min(1);
+        }
+        let runs = CoordRange::new(CoordInt::default(), hi)
+            .map(move |index| (mapping.grid_coord(&index).unwrap(), row));
+        RegionIterMut::new(self.grid, runs)
+            .map(move |(coord, value)| (mapping.view_coord(&coord), value))
+    }
+
+    pub fn fill(&mut self, value: T) {
+        for (_, cell) in self.iter_mut() {
+            *cell = value.clone();
+        }
+    }
+}
+
+impl<T: GridValue, const D: usize, const E: usize> GridViewMut<'_, T, D, E> {
+    pub fn get_at(&self, pos: &Vector<E, T::Scalar>) -> T {
+        self.as_view().get_at(pos)
+    }
+}
+
+impl<T: Default + Clone, const D: usize, const E: usize> GridRead<T, E> for GridView<'_, T, D, E> {
+    fn size(&self) -> CoordInt<E> {
+        GridView::size(self)
+    }
+
+    fn delta(&self) -> Float {
+        GridView::delta(self)
+    }
+
+    fn get(&self, index: &CoordInt<E>) -> Option<&T> {
+        GridView::get(self, index)
+    }
+}
+
+impl<T: Default + Clone, const D: usize, const E: usize> GridRead<T, E>
+    for GridViewMut<'_, T, D, E>
+{
+    fn size(&self) -> CoordInt<E> {
+        GridViewMut::size(self)
+    }
+
+    fn delta(&self) -> Float {
+        GridViewMut::delta(self)
+    }
+
+    fn get(&self, index: &CoordInt<E>) -> Option<&T> {
+        GridViewMut::get(self, index)
+    }
+}
@@ -1,8 +1,9 @@
 extern crate minifb;
 
-use crate::simulation::{
+use nsh::simulation::{
     self,
     grid::{CoordInt, Int},
+    view::GridRead,
 };
 
 pub const WIDTH: usize = 20;
@@ -32,9 +33,21 @@ impl FluidSimulation {
     }
 
     pub fn draw(&self, buffer: &mut [u32], window_width: usize, window_height: usize) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let density = self.grid.get(&CoordInt([x as Int, y as Int]));
+        self.draw_field(&self.grid, buffer, window_width, window_height);
+    }
+
+    /// Draws any 2D scalar field, such as a whole grid or a view into one.
+    pub fn draw_field(
+        &self,
+        field: &impl GridRead<f32, 2>,
+        buffer: &mut [u32],
+        window_width: usize,
+        window_height: usize,
+    ) {
+        let size = field.size();
+        for y in 0..size.0[1] as usize {
+            for x in 0..size.0[0] as usize {
+                let density = field.get(&CoordInt([x as Int, y as Int]));
                 let color = density
                     .map(|d| self.density_to_color(*d))
                     .unwrap_or(0xff0000ff);
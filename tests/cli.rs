@@ -0,0 +1,31 @@
+//! Argument checking of the headless command line.
+
+use std::process::Command;
+
+/// Runs `nsh --headless` with `args` and returns its exit code and stderr.
+fn headless(args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_nsh"))
+        .arg("--headless")
+        .args(args)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    (output.status.code(), stderr)
+}
+
+#[test]
+fn run_lengths_must_be_positive_and_finite() {
+    for time in ["-1", "0", "nan", "inf", "soon"] {
+        let (code, stderr) = headless(&["scene.toml", "--end-time", time]);
+        assert_eq!(code, Some(2), "--end-time {time}");
+        assert!(
+            stderr.starts_with(&format!("nsh: invalid end time \"{time}\"")),
+            "{stderr}"
+        );
+    }
+    for steps in ["0", "-3"] {
+        let (code, stderr) = headless(&["scene.toml", "--steps", steps]);
+        assert_eq!(code, Some(2), "--steps {steps}");
+        assert!(stderr.starts_with("nsh: invalid step count"), "{stderr}");
+    }
+}
@@ -0,0 +1,132 @@
+//! Steady lid-driven cavity flow compared with the centreline profiles of
+//! Ghia, Ghia & Shin (1982).
+//!
+//! The semi-Lagrangian advection is first order and smears the thin layers
+//! along the walls, so the profiles approach the reference only as the grid
+//! is refined and the agreement gets worse as the Reynolds number rises. The
+//! tolerances below bound the RMS and largest deviation over the 17 tabulated
+//! points, with some margin over the errors observed for each grid.
+//!
+//! The 16² and 32² cases at Re = 100 run by default; the 64² cases take
+//! minutes in a debug build and are run with
+//! `cargo test --release -- --ignored`.
+
+use nsh::simulation::validation::{
+    centreline_u, centreline_v, lid_driven_cavity, run_to_steady_state, GhiaProfile, GHIA_1982,
+};
+
+/// Largest velocity change per unit time considered steady.
+const STEADY: f64 = 1e-4;
+
+struct Tolerance {
+    rms: f64,
+    max: f64,
+}
+
+fn reference(reynolds: f64) -> &'static GhiaProfile {
+    GHIA_1982.iter().find(|p| p.reynolds == reynolds).unwrap()
+}
+
+fn check(profile: &[(f64, f64)], computed: &[f64], tolerance: &Tolerance, label: &str) {
+    let errors = profile
+        .iter()
+        .zip(computed)
+        .map(|(&(_, expected), &actual)| actual - expected)
+        .collect::<Vec<_>>();
+    let rms = (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt();
+    let max = errors.iter().fold(0.0f64, |max, e| max.max(e.abs()));
+    eprintln!("{label}: rms error {rms:.4}, max error {max:.4}");
+    assert!(
+        rms <= tolerance.rms && max <= tolerance.max,
+        "{label} deviates from Ghia et al.: rms {rms:.4} (allowed {}), max {max:.4} (allowed {})\n\
+         computed {computed:.3?}\n\
+         expected {:.3?}",
+        tolerance.rms,
+        tolerance.max,
+        profile.iter().map(|p| p.1).collect::<Vec<_>>(),
+    );
+}
+
+fn validate(n: usize, reynolds: f64, dt: f64, max_time: f64, u: Tolerance, v: Tolerance) {
+    let profile = reference(reynolds);
+    let mut sim = lid_driven_cavity::<f64>(n, reynolds, dt);
+    let rate = run_to_steady_state(&mut sim, STEADY, max_time);
+    assert!(
+        rate <= STEADY,
+        "not steady by t = {max_time}: velocity changing at {rate:.2e}"
+    );
+
+    let ys = profile.u.iter().map(|p| p.0).collect::<Vec<_>>();
+    let xs = profile.v.iter().map(|p| p.0).collect::<Vec<_>>();
+    let label = format!("Re = {reynolds}, {n}×{n}");
+    check(
+        &profile.u,
+        &centreline_u(&sim, &ys),
+        &u,
+        &format!("{label}, u"),
+    );
+    check(
+        &profile.v,
+        &centreline_v(&sim, &xs),
+        &v,
+        &format!("{label}, v"),
+    );
+}
+
+/// Observed: u rms 0.029, max 0.054; v rms 0.020, max 0.048.
+#[test]
+fn reynolds_100_coarse() {
+    let tolerance = || Tolerance {
+        rms: 0.04,
+        max: 0.08,
+    };
+    validate(16, 100.0, 0.04, 30.0, tolerance(), tolerance());
+}
+
+/// Observed: u rms 0.013, max 0.023; v rms 0.008, max 0.019.
+#[test]
+fn reynolds_100_medium() {
+    let tolerance = || Tolerance {
+        rms: 0.02,
+        max: 0.04,
+    };
+    validate(32, 100.0, 0.04, 30.0, tolerance(), tolerance());
+}
+
+/// Observed: u rms 0.005, max 0.012; v rms 0.003, max 0.005.
+#[test]
+#[ignore = "slow; run in release"]
+fn reynolds_100() {
+    let tolerance = || Tolerance {
+        rms: 0.01,
+        max: 0.02,
+    };
+    validate(64, 100.0, 0.02, 30.0, tolerance(), tolerance());
+}
+
+/// Observed: u rms 0.042, max 0.085; v rms 0.052, max 0.086.
+#[test]
+#[ignore = "slow; run in release"]
+fn reynolds_400() {
+    let tolerance = || Tolerance {
+        rms: 0.06,
+        max: 0.12,
+    };
+    validate(64, 400.0, 0.02, 60.0, tolerance(), tolerance());
+}
+
+/// Observed: u rms 0.085, max 0.166; v rms 0.103, max 0.150.
+///
+/// 64² does not resolve the wall layers at Re = 1000, so these errors are
+/// several times the grid's error at Re = 100 and the limits only guard
+/// against the solution getting worse. Passing is not a validation at this
+/// Reynolds number; that needs a finer grid than the suite can afford.
+#[test]
+#[ignore = "slow; run in release"]
+fn reynolds_1000() {
+    let tolerance = || Tolerance {
+        rms: 0.12,
+        max: 0.22,
+    };
+    validate(64, 1000.0, 0.02, 80.0, tolerance(), tolerance());
+}
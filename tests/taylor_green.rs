@@ -0,0 +1,96 @@
+//! Convergence of the decaying Taylor–Green vortex towards the exact
+//! solution, as the grid spacing and the time step are refined.
+//!
+//! The stable-fluids scheme is first order in both: semi-Lagrangian advection
+//! with linear interpolation adds a numerical viscosity of about `h |u| / 2`,
+//! and backward Euler diffusion lags the exact decay by `O(dt)`. The two
+//! errors have opposite signs and partly cancel, so each study makes one of
+//! them dominate: the spatial study uses a unit vortex and a time step small
+//! enough that the interpolation error outweighs the time error, and the
+//! temporal study a weak, strongly viscous vortex whose decay is dominated by
+//! the diffusion step.
+//!
+//! Both studies print their errors and observed orders (visible with
+//! `--nocapture`) and require every order between successive refinements to
+//! reach `MIN_ORDER`.
+
+use nsh::simulation::validation::{observed_order, Errors, TaylorGreen};
+
+/// Observed orders are 0.86–1.01; anything much lower means a first-order
+/// term has been lost.
+const MIN_ORDER: f64 = 0.75;
+
+/// Runs the vortex on `n`×`n` cells to time `end` and returns its errors
+/// relative to the amplitude.
+fn run(vortex: TaylorGreen, n: usize, dt: f64, end: f64) -> Errors {
+    let mut sim = vortex.simulation::<f64>(n, dt);
+    let steps = (end / dt).round() as u64;
+    for _ in 0..steps {
+        sim.step();
+    }
+    let Errors { l2, linf } = vortex.error(&sim);
+    Errors {
+        l2: l2 / vortex.amplitude,
+        linf: linf / vortex.amplitude,
+    }
+}
+
+/// Prints the errors of a refinement study by factors of 2 and checks the
+/// orders observed between successive runs.
+fn check_orders(study: &str, resolutions: &[String], errors: &[Errors]) {
+    eprintln!("{study}:");
+    eprintln!(
+        "{:>12} {:>10} {:>6} {:>10} {:>6}",
+        "", "L2", "order", "L∞", "order"
+    );
+    for (k, (resolution, e)) in resolutions.iter().zip(errors).enumerate() {
+        let orders = (k > 0).then(|| {
+            let coarse = errors[k - 1];
+            (
+                observed_order(coarse.l2, e.l2, 2.0),
+                observed_order(coarse.linf, e.linf, 2.0),
+            )
+        });
+        match orders {
+            Some((l2, linf)) => eprintln!(
+                "{resolution:>12} {:>10.3e} {l2:>6.2} {:>10.3e} {linf:>6.2}",
+                e.l2, e.linf
+            ),
+            None => eprintln!(
+                "{resolution:>12} {:>10.3e} {:>6} {:>10.3e}",
+                e.l2, "", e.linf
+            ),
+        }
+        if let Some((l2, linf)) = orders {
+            assert!(
+                l2 >= MIN_ORDER && linf >= MIN_ORDER,
+                "{study}: order {l2:.2} (L2), {linf:.2} (L∞) at {resolution}, \
+                 expected at least {MIN_ORDER}"
+            );
+        }
+    }
+}
+
+#[test]
+fn spatial_convergence() {
+    let vortex = TaylorGreen {
+        amplitude: 1.0,
+        viscosity: 0.01,
+    };
+    let sizes = [16, 32, 64];
+    let errors = sizes.map(|n| run(vortex, n, 1.0 / 64.0, 0.25));
+    let resolutions = sizes.map(|n| format!("{n}×{n}"));
+    check_orders("spatial convergence, dt = 1/64", &resolutions, &errors);
+}
+
+#[test]
+fn temporal_convergence() {
+    let vortex = TaylorGreen {
+        amplitude: 0.01,
+        viscosity: 0.5,
+    };
+    let steps = [0.2, 0.1, 0.05, 0.025];
+    let errors = steps.map(|dt| run(vortex, 32, dt, 0.4));
+    let resolutions = steps.map(|dt| format!("dt = {dt}"));
+    check_orders("temporal convergence, 32×32", &resolutions, &errors);
+}